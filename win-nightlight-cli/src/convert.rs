@@ -0,0 +1,504 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{NaiveTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use win_nightlight_lib::cloudstore::cloudstore_unwrap;
+use win_nightlight_lib::dump::MarshaledStruct;
+use win_nightlight_lib::nightlight_settings::{
+    MAX_COLOR_TEMPERATURE, MIN_COLOR_TEMPERATURE, NightlightSettings, SettingsEncoding,
+    SettingsError,
+};
+use win_nightlight_lib::nightlight_state::{NightlightState, StateEncoding};
+use win_nightlight_lib::reg::RegFile;
+use win_nightlight_lib::{CloudStoreEnvelope, NightlightBlob, hex};
+
+use crate::Schedule;
+
+/// Time of day format of the document fields.
+const TIME_FORMAT: &str = "%H:%M";
+
+/// Output format of `wnl decode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DocumentFormat {
+    /// A JSON object
+    #[default]
+    Json,
+    /// A TOML document
+    Toml,
+}
+
+/// Output format of `wnl encode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum BlobOutput {
+    /// Lowercase hex digits
+    #[default]
+    Hex,
+    /// The raw blob bytes
+    Binary,
+    /// A `.reg` file importing the blobs into the current user's registry
+    Reg,
+}
+
+/// Decoded blobs as written by `wnl decode` and read by `wnl encode`. Field names are part of
+/// the document format and must stay stable.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlobDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<SettingsDocument>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<StateDocument>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsDocument {
+    /// Last-modified Unix seconds; the current time when encoding a document without it
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    /// Same identifiers as accepted by `wnl schedule`
+    pub schedule_mode: String,
+    pub color_temperature: u16,
+    /// 24-hour `HH:MM`
+    pub start_time: String,
+    pub end_time: String,
+    pub sunset_time: String,
+    pub sunrise_time: String,
+    /// Hex of the decoded blob when it held data the fields above can't, like unknown fields
+    /// or wrapper metadata. Encoding keeps that data and takes the rest from the fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateDocument {
+    /// Last-modified Unix seconds; the current time when encoding a document without it
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    pub is_enabled: bool,
+    #[serde(default = "default_initialized")]
+    pub initialized: i32,
+    /// Windows FILETIME of the last transition
+    #[serde(default)]
+    pub last_transition_filetime: u64,
+    /// Like [SettingsDocument::original]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<String>,
+}
+
+fn default_initialized() -> i32 {
+    1
+}
+
+fn parse_time(field: &str, s: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(s, TIME_FORMAT)
+        .map_err(|_| anyhow!("Invalid {field} '{s}', expected 24-hour HH:MM"))
+}
+
+fn now() -> u64 {
+    Utc::now().timestamp() as u64
+}
+
+/// Decodes the hex of an `original` field, if there is one.
+fn parse_original(original: Option<&str>) -> Result<Option<Vec<u8>>> {
+    original
+        .map(|s| hex::decode(s).context("Invalid 'original' hex"))
+        .transpose()
+}
+
+impl SettingsDocument {
+    /// Creates a document for `settings`, which were decoded from `data`.
+    fn new(settings: &NightlightSettings, data: &[u8]) -> Self {
+        let lossy = !settings.unknown_fields.fields.is_empty()
+            || settings.encoding != SettingsEncoding::default()
+            || settings.envelope != CloudStoreEnvelope::default()
+            || settings.serialize_to_bytes() != data;
+        Self {
+            timestamp: Some(settings.timestamp),
+            schedule_mode: Schedule::from(settings.schedule_mode).as_str().to_string(),
+            color_temperature: settings.color_temperature,
+            start_time: settings.start_time.format(TIME_FORMAT).to_string(),
+            end_time: settings.end_time.format(TIME_FORMAT).to_string(),
+            sunset_time: settings.sunset_time.format(TIME_FORMAT).to_string(),
+            sunrise_time: settings.sunrise_time.format(TIME_FORMAT).to_string(),
+            original: lossy.then(|| hex::encode(data)),
+        }
+    }
+
+    fn to_settings(&self) -> Result<NightlightSettings> {
+        if !(MIN_COLOR_TEMPERATURE..=MAX_COLOR_TEMPERATURE).contains(&self.color_temperature) {
+            return Err(SettingsError::InvalidColorTemperature(self.color_temperature).into());
+        }
+        let (unknown_fields, encoding, envelope) = match parse_original(self.original.as_deref())? {
+            Some(data) => {
+                let original = NightlightSettings::deserialize_from_bytes(&data)
+                    .context("Invalid 'original' settings blob")?;
+                (
+                    original.unknown_fields,
+                    original.encoding,
+                    original.envelope,
+                )
+            }
+            None => Default::default(),
+        };
+        Ok(NightlightSettings {
+            timestamp: self.timestamp.unwrap_or_else(now),
+            schedule_mode: Schedule::from_str(&self.schedule_mode)?.into(),
+            color_temperature: self.color_temperature,
+            start_time: parse_time("start_time", &self.start_time)?,
+            end_time: parse_time("end_time", &self.end_time)?,
+            sunset_time: parse_time("sunset_time", &self.sunset_time)?,
+            sunrise_time: parse_time("sunrise_time", &self.sunrise_time)?,
+            unknown_fields,
+            encoding,
+            envelope,
+        })
+    }
+}
+
+impl StateDocument {
+    /// Creates a document for `state`, which was decoded from `data`.
+    fn new(state: &NightlightState, data: &[u8]) -> Self {
+        let lossy = !state.unknown_fields.fields.is_empty()
+            || state.encoding != StateEncoding::default()
+            || state.envelope != CloudStoreEnvelope::default()
+            || state.serialize_to_bytes() != data;
+        Self {
+            timestamp: Some(state.timestamp),
+            is_enabled: state.is_enabled,
+            initialized: state.initialized,
+            last_transition_filetime: state.last_transition_filetime,
+            original: lossy.then(|| hex::encode(data)),
+        }
+    }
+
+    fn to_state(&self) -> Result<NightlightState> {
+        let (unknown_fields, encoding, envelope) = match parse_original(self.original.as_deref())? {
+            Some(data) => {
+                let original = NightlightState::deserialize_from_bytes(&data)
+                    .context("Invalid 'original' state blob")?;
+                (
+                    original.unknown_fields,
+                    original.encoding,
+                    original.envelope,
+                )
+            }
+            None => Default::default(),
+        };
+        Ok(NightlightState {
+            timestamp: self.timestamp.unwrap_or_else(now),
+            is_enabled: self.is_enabled,
+            initialized: self.initialized,
+            last_transition_filetime: self.last_transition_filetime,
+            unknown_fields,
+            encoding,
+            envelope,
+        })
+    }
+}
+
+/// Decodes a settings or state blob, detecting which one it is unless `blob` is given.
+///
+/// Blobs with fields or wrapper data the document fields can't represent are kept in the
+/// section's `original` hex, so encoding the document doesn't drop them.
+pub fn decode(data: &[u8], blob: Option<NightlightBlob>) -> Result<BlobDocument> {
+    let Some(blob) = blob.or_else(|| NightlightBlob::detect(data)) else {
+        // Report why a malformed blob can't be read before guessing at its contents
+        cloudstore_unwrap(data)?;
+        bail!("Not a Night Light settings or state blob");
+    };
+    Ok(match blob {
+        NightlightBlob::Settings => {
+            let settings = NightlightSettings::deserialize_from_bytes(data)?;
+            BlobDocument {
+                settings: Some(SettingsDocument::new(&settings, data)),
+                state: None,
+            }
+        }
+        NightlightBlob::State => {
+            let state = NightlightState::deserialize_from_bytes(data)?;
+            BlobDocument {
+                settings: None,
+                state: Some(StateDocument::new(&state, data)),
+            }
+        }
+    })
+}
+
+/// Encodes every blob in the document with `serialize_to_bytes`, settings first.
+pub fn encode(document: &BlobDocument) -> Result<Vec<(Option<NightlightBlob>, Vec<u8>)>> {
+    let mut blobs = Vec::new();
+    if let Some(settings) = &document.settings {
+        let bytes = settings
+            .to_settings()
+            .context("Invalid settings")?
+            .serialize_to_bytes();
+        blobs.push((Some(NightlightBlob::Settings), bytes));
+    }
+    if let Some(state) = &document.state {
+        let bytes = state
+            .to_state()
+            .context("Invalid state")?
+            .serialize_to_bytes();
+        blobs.push((Some(NightlightBlob::State), bytes));
+    }
+    if blobs.is_empty() {
+        bail!("The document has neither a 'settings' nor a 'state' section");
+    }
+    Ok(blobs)
+}
+
+/// Renders a document in the given format.
+pub fn render_document(document: &BlobDocument, format: DocumentFormat) -> Result<String> {
+    Ok(match format {
+        DocumentFormat::Json => serde_json::to_string_pretty(document)? + "\n",
+        DocumentFormat::Toml => toml::to_string(document)?,
+    })
+}
+
+/// Parses a JSON or TOML document, telling them apart by the leading `{` of JSON.
+pub fn parse_document(contents: &[u8]) -> Result<BlobDocument> {
+    let text = std::str::from_utf8(contents).map_err(|_| anyhow!("Invalid UTF-8 text"))?;
+    if text.trim_start().starts_with('{') {
+        serde_json::from_str(text).context("Invalid JSON document")
+    } else {
+        toml::from_str(text).context("Invalid TOML document")
+    }
+}
+
+/// Parses a JSON dump from `wnl dump`, or returns `None` if `contents` is a different document.
+pub fn parse_dump(contents: &[u8]) -> Result<Option<MarshaledStruct>> {
+    let text = std::str::from_utf8(contents).map_err(|_| anyhow!("Invalid UTF-8 text"))?;
+    if !text.trim_start().starts_with('{') {
+        return Ok(None);
+    }
+    let json: serde_json::Value = serde_json::from_str(text).context("Invalid JSON document")?;
+    if json.get("fields").is_none() {
+        return Ok(None);
+    }
+    MarshaledStruct::from_json(&json)
+        .map(Some)
+        .context("Invalid dump")
+}
+
+/// Renders encoded blobs in the given output format. `.reg` output is UTF-16LE like `regedit`
+/// writes it when `for_file` is set, and UTF-8 otherwise.
+///
+/// Blobs are paired with which one they are, if known; `.reg` output needs to know.
+pub fn render_blobs(
+    blobs: &[(Option<NightlightBlob>, Vec<u8>)],
+    output: BlobOutput,
+    for_file: bool,
+) -> Result<Vec<u8>> {
+    if output == BlobOutput::Reg {
+        let mut file = RegFile::new();
+        for (blob, bytes) in blobs {
+            let blob = blob.ok_or_else(|| anyhow!("Only settings and state blobs can be .reg"))?;
+            file.set_nightlight_data(blob, bytes);
+        }
+        return Ok(if for_file {
+            file.to_utf16le_bytes()
+        } else {
+            file.to_string().into_bytes()
+        });
+    }
+    let [(_, bytes)] = blobs else {
+        bail!("Hex and binary output hold a single blob, use --output reg for both");
+    };
+    Ok(match output {
+        BlobOutput::Hex => (hex::encode(bytes) + "\n").into_bytes(),
+        _ => bytes.clone(),
+    })
+}
+
+/// Writes command output to `path`, or to stdout if it's `None`.
+pub fn write_output(path: Option<&Path>, contents: &[u8]) -> Result<()> {
+    match path {
+        Some(path) => {
+            fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+        }
+        None => Ok(io::stdout().write_all(contents)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use win_nightlight_lib::bond::BondValue;
+
+    // The settings test bytes from nightlight_settings.rs
+    const SETTINGS_BYTES: [u8; 60] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x26, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14,
+        0x0E, 0x01, 0x2E, 0x0F, 0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xCA, 0x32, 0x0E,
+        0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    // The enabled state test bytes from nightlight_state.rs
+    const STATE_BYTES: [u8; 43] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0x89, 0x95, 0xFC, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x15, 0x43, 0x42, 0x01, 0x00, 0x10, 0x00, 0xD0, 0x0A, 0x02, 0xC6, 0x14,
+        0xA9, 0xF6, 0xE2, 0xD3, 0xEF, 0xEA, 0xE6, 0xED, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn decode_settings() {
+        let document = decode(&SETTINGS_BYTES, None).unwrap();
+        assert_eq!(
+            render_document(&document, DocumentFormat::Toml).unwrap(),
+            indoc! {r#"
+                [settings]
+                timestamp = 1742540908
+                schedule_mode = "manual"
+                color_temperature = 2790
+                start_time = "01:15"
+                end_time = "00:00"
+                sunset_time = "19:23"
+                sunrise_time = "07:12"
+            "#}
+        );
+    }
+
+    #[test]
+    fn roundtrips_exact_bytes() {
+        for (bytes, format) in [
+            (&SETTINGS_BYTES[..], DocumentFormat::Json),
+            (&SETTINGS_BYTES[..], DocumentFormat::Toml),
+            (&STATE_BYTES[..], DocumentFormat::Json),
+            (&STATE_BYTES[..], DocumentFormat::Toml),
+        ] {
+            let document = decode(bytes, None).unwrap();
+            let text = render_document(&document, format).unwrap();
+            let blobs = encode(&parse_document(text.as_bytes()).unwrap()).unwrap();
+            assert_eq!(blobs.len(), 1);
+            assert_eq!(blobs[0].1, bytes);
+        }
+    }
+
+    #[test]
+    fn lossy_blobs_keep_their_original() {
+        let mut settings = NightlightSettings::deserialize_from_bytes(&SETTINGS_BYTES).unwrap();
+        settings.unknown_fields.push(90, BondValue::Int32(7));
+        let bytes = settings.serialize_to_bytes();
+
+        for format in [DocumentFormat::Json, DocumentFormat::Toml] {
+            let document = decode(&bytes, None).unwrap();
+            let text = render_document(&document, format).unwrap();
+            let mut document = parse_document(text.as_bytes()).unwrap();
+            assert_eq!(encode(&document).unwrap()[0].1, bytes);
+
+            // Edits apply on top of the original's extra data
+            document.settings.as_mut().unwrap().color_temperature = 3000;
+            let encoded = encode(&document).unwrap();
+            let edited = NightlightSettings::deserialize_from_bytes(&encoded[0].1).unwrap();
+            assert_eq!(edited.color_temperature, 3000);
+            assert_eq!(edited.unknown_fields, settings.unknown_fields);
+        }
+
+        // A canonical blob needs no original
+        let document = decode(&SETTINGS_BYTES, None).unwrap();
+        assert_eq!(document.settings.unwrap().original, None);
+    }
+
+    #[test]
+    fn presence_values_keep_their_original() {
+        // Field 10 of the settings written as true, and field 0 of the state as 1
+        let mut settings = SETTINGS_BYTES;
+        settings[27] = 0x01;
+        let mut state = STATE_BYTES;
+        state[24] = 0x02;
+
+        for bytes in [&settings[..], &state[..]] {
+            let document = decode(bytes, None).unwrap();
+            let text = render_document(&document, DocumentFormat::Json).unwrap();
+            let document = parse_document(text.as_bytes()).unwrap();
+            assert!(text.contains("original"));
+            assert_eq!(encode(&document).unwrap()[0].1, bytes);
+        }
+    }
+
+    #[test]
+    fn dump_documents() {
+        let dumped = win_nightlight_lib::dump::dump(&STATE_BYTES).unwrap();
+        let text = serde_json::to_string(&dumped).unwrap();
+        let dumped = parse_dump(text.as_bytes()).unwrap().unwrap();
+        assert_eq!(dumped.to_bytes(), STATE_BYTES);
+
+        let document = decode(&STATE_BYTES, None).unwrap();
+        let text = render_document(&document, DocumentFormat::Json).unwrap();
+        assert!(parse_dump(text.as_bytes()).unwrap().is_none());
+        assert!(
+            parse_dump(b"[state]\nis_enabled = true\n")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn state_defaults() {
+        let document = parse_document(b"[state]\nis_enabled = true\ntimestamp = 1\n").unwrap();
+        let state = document.state.unwrap().to_state().unwrap();
+        assert!(state.is_enabled);
+        assert_eq!(state.initialized, 1);
+        assert_eq!(state.last_transition_filetime, 0);
+    }
+
+    #[test]
+    fn invalid_documents() {
+        assert!(decode(&SETTINGS_BYTES[..40], None).is_err());
+        assert!(encode(&BlobDocument::default()).is_err());
+        assert!(parse_document(br#"{"settings": {"bogus": 1}}"#).is_err());
+        let mut document = decode(&STATE_BYTES, None).unwrap();
+        document.state.as_mut().unwrap().original = Some("43 42".into());
+        assert!(encode(&document).is_err());
+        let settings = |mode: &str, temp: u16, start: &str| {
+            let json = format!(
+                r#"{{"settings": {{"schedule_mode": "{mode}", "color_temperature": {temp},
+                    "start_time": "{start}", "end_time": "07:00", "sunset_time": "19:00",
+                    "sunrise_time": "07:00"}}}}"#
+            );
+            encode(&parse_document(json.as_bytes()).unwrap())
+        };
+        assert!(settings("manual", 2700, "22:00").is_ok());
+        assert!(settings("sometimes", 2700, "22:00").is_err());
+        assert!(settings("manual", 900, "22:00").is_err());
+        assert!(settings("manual", 2700, "10pm").is_err());
+    }
+
+    #[test]
+    fn outputs() {
+        let settings = decode(&SETTINGS_BYTES, None).unwrap();
+        let state = decode(&STATE_BYTES, None).unwrap();
+        let both = BlobDocument {
+            settings: settings.settings,
+            state: state.state,
+        };
+        let blobs = encode(&both).unwrap();
+
+        let reg = String::from_utf8(render_blobs(&blobs, BlobOutput::Reg, false).unwrap()).unwrap();
+        let parsed = RegFile::parse(&reg).unwrap();
+        assert_eq!(
+            parsed.nightlight_data(NightlightBlob::Settings),
+            Some(&SETTINGS_BYTES[..])
+        );
+        assert_eq!(
+            parsed.nightlight_data(NightlightBlob::State),
+            Some(&STATE_BYTES[..])
+        );
+        let utf16 = render_blobs(&blobs, BlobOutput::Reg, true).unwrap();
+        assert_eq!(RegFile::from_bytes(&utf16).unwrap(), parsed);
+
+        assert!(render_blobs(&blobs, BlobOutput::Hex, false).is_err());
+        assert!(render_blobs(&[(None, STATE_BYTES.to_vec())], BlobOutput::Reg, false).is_err());
+        let hex = render_blobs(&blobs[1..], BlobOutput::Hex, false).unwrap();
+        assert_eq!(hex, (hex::encode(&STATE_BYTES) + "\n").into_bytes());
+        let binary = render_blobs(&blobs[1..], BlobOutput::Binary, false).unwrap();
+        assert_eq!(binary, STATE_BYTES);
+    }
+}
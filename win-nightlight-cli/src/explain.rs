@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use anyhow::Result;
+use win_nightlight_lib::explain::explain;
+use win_nightlight_lib::{NightlightBackend, NightlightBlob};
+
+use crate::input::read_blob;
+
+/// Prints the annotated listing of a blob from a file.
+pub fn print_file(path: &Path) -> Result<()> {
+    print!("{}", explain(&read_blob(Some(path))?, None));
+    Ok(())
+}
+
+/// Prints the annotated listings of the given live blobs, each under a heading if there are
+/// several.
+pub fn print_blobs(backend: &dyn NightlightBackend, blobs: &[NightlightBlob]) -> Result<()> {
+    for (i, &blob) in blobs.iter().enumerate() {
+        let data = match blob {
+            NightlightBlob::Settings => backend.read_settings_bytes()?,
+            NightlightBlob::State => backend.read_state_bytes()?,
+        };
+        if blobs.len() > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("{blob}:");
+        }
+        print!("{}", explain(&data, Some(blob)));
+    }
+    Ok(())
+}
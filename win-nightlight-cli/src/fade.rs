@@ -0,0 +1,95 @@
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use win_nightlight_lib::fade::{Easing, Fade, SystemClock};
+use win_nightlight_lib::{NightlightBackend, NightlightManager};
+
+/// Easing curve of `wnl fade`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FadeEasing {
+    /// Constant change in Kelvin
+    Linear,
+    /// Slow start and end
+    EaseInOut,
+    /// Perceptually even change (linear in mireds)
+    Mired,
+}
+
+impl From<FadeEasing> for Easing {
+    fn from(e: FadeEasing) -> Self {
+        match e {
+            FadeEasing::Linear => Easing::Linear,
+            FadeEasing::EaseInOut => Easing::EaseInOut,
+            FadeEasing::Mired => Easing::Mired,
+        }
+    }
+}
+
+/// Parses durations like `90s`, `30m`, `1h30m` or `500ms`. A bare number means seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let invalid = || anyhow!("Invalid duration '{s}', expected e.g. 90s, 30m or 1h30m");
+    if let Ok(secs) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let value: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let part = match &rest[..unit_len] {
+            "ms" => Some(Duration::from_millis(value)),
+            "s" => Some(Duration::from_secs(value)),
+            "m" => value.checked_mul(60).map(Duration::from_secs),
+            "h" => value.checked_mul(3600).map(Duration::from_secs),
+            _ => None,
+        };
+        total = part
+            .and_then(|part| total.checked_add(part))
+            .ok_or_else(invalid)?;
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+/// Runs a fade, showing progress on a single updating line.
+pub fn run<B: NightlightBackend>(mgr: &NightlightManager<B>, fade: Fade) -> Result<()> {
+    fade.run(mgr, &SystemClock::new(), |p| {
+        print!("\r{}K ({:>3.0}%)", p.temperature, p.progress * 100.0);
+        let _ = std::io::stdout().flush();
+        ControlFlow::Continue(())
+    })?;
+    println!();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        let max_secs = u64::MAX.to_string();
+        assert!(parse_duration(&format!("{max_secs}s1s")).is_err());
+        for bad in ["", "m", "10x", "1.5h", "-5s", "9999999999999999h"] {
+            assert!(parse_duration(bad).is_err(), "{bad}");
+        }
+    }
+}
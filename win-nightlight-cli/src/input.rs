@@ -0,0 +1,63 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use win_nightlight_lib::bond::sniff_protocol;
+use win_nightlight_lib::hex;
+
+/// Parses blob file contents: raw bytes if they start with a Bond marshaled header, otherwise
+/// hex text as accepted by [hex::decode].
+pub fn parse_blob(contents: &[u8]) -> Result<Vec<u8>> {
+    if sniff_protocol(contents).is_some() {
+        return Ok(contents.to_vec());
+    }
+    let text = std::str::from_utf8(contents)
+        .map_err(|_| anyhow!("Expected raw Bond bytes or hex text"))?;
+    Ok(hex::decode(text)?)
+}
+
+/// Reads the contents of `path`, or all of stdin if it's `None`.
+pub fn read_input(path: Option<&Path>) -> Result<Vec<u8>> {
+    match path {
+        Some(path) => fs::read(path).with_context(|| format!("Failed to read {}", path.display())),
+        None => {
+            let mut contents = Vec::new();
+            io::stdin()
+                .read_to_end(&mut contents)
+                .context("Failed to read stdin")?;
+            Ok(contents)
+        }
+    }
+}
+
+/// Reads a blob with raw bytes or hex text from `path`, or from stdin if it's `None`.
+pub fn read_blob(path: Option<&Path>) -> Result<Vec<u8>> {
+    let source = path.map_or("stdin".into(), |p| p.display().to_string());
+    parse_blob(&read_input(path)?).with_context(|| format!("Failed to parse {source}"))
+}
+
+/// Reads a blob given as hex arguments, which are joined so pasted dumps may contain spaces,
+/// or with [read_blob] if there are none.
+pub fn read_blob_or_hex(hex: &[String], path: Option<&Path>) -> Result<Vec<u8>> {
+    if hex.is_empty() {
+        read_blob(path)
+    } else {
+        Ok(hex::decode(&hex.concat())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_and_hex_blobs() {
+        let raw = [0x43, 0x42, 0x01, 0x00, 0x00];
+        assert_eq!(parse_blob(&raw).unwrap(), raw);
+        assert_eq!(parse_blob(b"43 42 01 00 00\n").unwrap(), raw);
+        assert_eq!(parse_blob(b"0x43, 0x42, 0x01, 0x00, 0x00").unwrap(), raw);
+        assert!(parse_blob(b"not hex").is_err());
+        assert!(parse_blob(&[0xFF, 0xFE, 0x00]).is_err());
+    }
+}
@@ -1,12 +1,29 @@
+mod convert;
+mod explain;
+mod fade;
+mod input;
+mod status;
+mod sun;
+
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Local, NaiveTime};
+use chrono::{Local, NaiveDate, NaiveTime};
 use clap::{Parser, Subcommand};
-use indoc::printdoc;
+use convert::{BlobOutput, DocumentFormat};
+use fade::FadeEasing;
+use status::StatusFormat;
+use std::path::PathBuf;
 use std::str::FromStr;
-use win_nightlight_lib::{NightlightManager, RegistryBackend, nightlight_settings::ScheduleMode};
+use std::time::Duration;
+#[cfg(windows)]
+use win_nightlight_lib::RegistryBackend;
+use win_nightlight_lib::fade::Fade;
+use win_nightlight_lib::temperature::TemperatureTarget;
+use win_nightlight_lib::{
+    NightlightBackend, NightlightBlob, NightlightManager, nightlight_settings::ScheduleMode,
+};
 
-const NAIVE_TIME_FORMAT: &str = "%I:%M %p";
-const DATE_TIME_FORMAT: &str = "%Y-%m-%d %I:%M:%S %p %Z";
+/// How often to redo a change when Windows updates the same blob while we are writing it.
+const WRITE_RETRIES: u32 = 3;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -36,6 +53,27 @@ impl FromStr for Schedule {
     }
 }
 
+impl Schedule {
+    /// The mode's command-line identifier.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Schedule::Off => "off",
+            Schedule::Solar => "solar",
+            Schedule::Manual => "manual",
+        }
+    }
+}
+
+impl From<ScheduleMode> for Schedule {
+    fn from(m: ScheduleMode) -> Self {
+        match m {
+            ScheduleMode::Off => Schedule::Off,
+            ScheduleMode::SunsetToSunrise => Schedule::Solar,
+            ScheduleMode::SetHours => Schedule::Manual,
+        }
+    }
+}
+
 impl From<Schedule> for ScheduleMode {
     fn from(s: Schedule) -> Self {
         match s {
@@ -50,8 +88,18 @@ impl From<Schedule> for ScheduleMode {
 enum Commands {
     /// Sets the color temperature in Kelvin (1200 - 6500)
     Temp {
-        #[arg(index = 1)]
-        temperature: u16,
+        /// Kelvin (2700), a relative change (+300, -10%), or a preset (candle, warm, neutral,
+        /// cool, daylight)
+        #[arg(
+            index = 1,
+            allow_hyphen_values = true,
+            required_unless_present = "strength",
+            conflicts_with = "strength"
+        )]
+        temperature: Option<TemperatureTarget>,
+        /// Strength in percent (0 - 100), as on the Settings app slider
+        #[arg(long)]
+        strength: Option<f64>,
     },
     /// Sets the schedule mode ('off', 'solar', or 'manual')
     Schedule {
@@ -64,20 +112,131 @@ enum Commands {
         #[arg(long)]
         end: Option<String>,
     },
+    /// Gradually changes the color temperature
+    Fade {
+        /// Target color temperature in Kelvin (1200 - 6500)
+        #[arg(long)]
+        to: u16,
+        /// How long the fade takes, e.g. 90s, 30m or 1h30m
+        #[arg(long, value_parser = fade::parse_duration)]
+        over: Duration,
+        /// How the temperature changes over time
+        #[arg(long, value_enum, default_value = "mired")]
+        easing: FadeEasing,
+        /// Time between steps
+        #[arg(long, value_parser = fade::parse_duration, default_value = "1s")]
+        step: Duration,
+    },
     /// Enables nightlight
     On,
     /// Disables nightlight
     Off,
     /// Prints the current nightlight state and settings
-    Status,
+    Status {
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: StatusFormat,
+        /// Also print the color temperature as an sRGB white point and gamma ramp
+        #[arg(long)]
+        rgb: bool,
+    },
+    /// Prints an annotated byte-by-byte listing of the settings and/or state blob
+    Explain {
+        /// Only explain the settings blob
+        #[arg(long, conflicts_with = "state")]
+        settings: bool,
+        /// Only explain the state blob
+        #[arg(long)]
+        state: bool,
+        /// Explain a blob file (raw bytes or hex) instead of the live configuration
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["settings", "state"])]
+        file: Option<PathBuf>,
+    },
+    /// Decodes a settings or state blob to JSON or TOML, without touching the registry
+    Decode {
+        /// The blob as hex, spaces allowed; read from --file or stdin if omitted
+        hex: Vec<String>,
+        /// Read the blob (raw bytes or hex) from a file
+        #[arg(long, value_name = "FILE", conflicts_with = "hex")]
+        file: Option<PathBuf>,
+        /// Decode as settings instead of detecting the blob type
+        #[arg(long, conflicts_with = "state")]
+        settings: bool,
+        /// Decode as state instead of detecting the blob type
+        #[arg(long)]
+        state: bool,
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: DocumentFormat,
+    },
+    /// Dumps any CloudStore blob to typed JSON without a schema, decoding nested payloads
+    Dump {
+        /// The blob as hex, spaces allowed; read from --file or stdin if omitted
+        hex: Vec<String>,
+        /// Read the blob (raw bytes or hex) from a file
+        #[arg(long, value_name = "FILE", conflicts_with = "hex")]
+        file: Option<PathBuf>,
+    },
+    /// Encodes a document from 'decode' or 'dump' into blobs, without touching the registry
+    Encode {
+        /// The document; read from stdin if omitted
+        file: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        output: BlobOutput,
+        /// Write to a file instead of stdout
+        #[arg(long, short, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Calculates sunrise and sunset for a location
+    Sun {
+        /// Latitude in degrees, north positive
+        #[arg(long, allow_negative_numbers = true)]
+        lat: f64,
+        /// Longitude in degrees, east positive
+        #[arg(long, allow_negative_numbers = true)]
+        lon: f64,
+        /// Date to calculate for (YYYY-MM-DD), defaults to today
+        #[arg(long)]
+        date: Option<NaiveDate>,
+        /// Store the times as the sunset/sunrise used by the 'solar' schedule
+        #[arg(long)]
+        write: bool,
+    },
+}
+
+/// Returns the backend for the current user's live Night Light configuration.
+#[cfg(windows)]
+fn registry_backend() -> Result<Box<dyn NightlightBackend>> {
+    Ok(Box::new(RegistryBackend))
+}
+
+/// Returns the backend for the current user's live Night Light configuration.
+#[cfg(not(windows))]
+fn registry_backend() -> Result<Box<dyn NightlightBackend>> {
+    anyhow::bail!(
+        "Reading or writing the live Night Light configuration is only supported on Windows"
+    )
+}
+
+/// Returns a manager for the current user's live Night Light configuration.
+fn manager() -> Result<NightlightManager<Box<dyn NightlightBackend>>> {
+    Ok(NightlightManager::new(registry_backend()?).with_retries(WRITE_RETRIES))
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let mgr = NightlightManager::new(RegistryBackend);
 
     match cli.command {
-        Commands::Temp { temperature } => mgr.set_color_temperature(temperature)?,
+        Commands::Temp {
+            temperature,
+            strength,
+        } => {
+            let target = temperature
+                .or(strength.map(TemperatureTarget::Strength))
+                .ok_or_else(|| anyhow!("A temperature or --strength is required"))?;
+            manager()?.adjust_color_temperature(target)?;
+        }
         Commands::Schedule { mode, start, end } => {
             let parse_time = |s: &str| -> Result<NaiveTime> {
                 NaiveTime::parse_from_str(s, "%H:%M")
@@ -87,47 +246,96 @@ fn main() -> Result<()> {
             let start_time = start.as_deref().map(parse_time).transpose()?;
             let end_time = end.as_deref().map(parse_time).transpose()?;
 
-            mgr.set_schedule(mode.into(), start_time, end_time)?;
+            manager()?.set_schedule(mode.into(), start_time, end_time)?;
+        }
+        Commands::Fade {
+            to,
+            over,
+            easing,
+            step,
+        } => {
+            let fade = Fade::new(to, over)
+                .with_easing(easing.into())
+                .with_step(step);
+            fade::run(&manager()?, fade)?;
+        }
+        Commands::On => manager()?.enable()?,
+        Commands::Off => manager()?.disable()?,
+        Commands::Status { format, rgb } => {
+            let mgr = manager()?;
+            let now = Local::now().naive_local();
+            status::print(&mgr.get_settings()?, &mgr.get_state()?, now, format, rgb)?;
+        }
+        Commands::Explain {
+            settings,
+            state,
+            file,
+        } => match file {
+            Some(path) => explain::print_file(&path)?,
+            None => {
+                let blobs: &[NightlightBlob] = match (settings, state) {
+                    (true, _) => &[NightlightBlob::Settings],
+                    (_, true) => &[NightlightBlob::State],
+                    _ => &[NightlightBlob::Settings, NightlightBlob::State],
+                };
+                explain::print_blobs(&registry_backend()?, blobs)?;
+            }
+        },
+        Commands::Decode {
+            hex,
+            file,
+            settings,
+            state,
+            format,
+        } => {
+            let data = input::read_blob_or_hex(&hex, file.as_deref())?;
+            let blob = match (settings, state) {
+                (true, _) => Some(NightlightBlob::Settings),
+                (_, true) => Some(NightlightBlob::State),
+                _ => None,
+            };
+            let document = convert::decode(&data, blob)?;
+            print!("{}", convert::render_document(&document, format)?);
+        }
+        Commands::Dump { hex, file } => {
+            let data = input::read_blob_or_hex(&hex, file.as_deref())?;
+            let dumped = win_nightlight_lib::dump::dump(&data)?;
+            if dumped.to_bytes() != data {
+                eprintln!(
+                    "Warning: the blob isn't canonically encoded; encoding the dump changes it"
+                );
+            }
+            println!("{}", serde_json::to_string_pretty(&dumped)?);
+        }
+        Commands::Encode { file, output, out } => {
+            let contents = input::read_input(file.as_deref())?;
+            let blobs = match convert::parse_dump(&contents)? {
+                Some(dumped) => {
+                    let bytes = dumped.to_bytes();
+                    vec![(NightlightBlob::detect(&bytes), bytes)]
+                }
+                None => convert::encode(&convert::parse_document(&contents)?)?,
+            };
+            let rendered = convert::render_blobs(&blobs, output, out.is_some())?;
+            convert::write_output(out.as_deref(), &rendered)?;
         }
-        Commands::On => mgr.enable()?,
-        Commands::Off => mgr.disable()?,
-        Commands::Status => {
-            let settings = mgr.get_settings()?;
-            let state = mgr.get_state()?;
-
-            let state_last_modified = DateTime::from_timestamp(state.timestamp as i64, 0)
-                .ok_or_else(|| anyhow!("Failed to convert timestamp to DateTime"))?;
-            let settings_last_modified = DateTime::from_timestamp(settings.timestamp as i64, 0)
-                .ok_or_else(|| anyhow!("Failed to convert timestamp to DateTime"))?;
-            let state_last_modified_local: DateTime<Local> = DateTime::from(state_last_modified);
-            let settings_last_modified_local: DateTime<Local> =
-                DateTime::from(settings_last_modified);
-
-            printdoc!(
-                r#"
-                Nightlight state:
-                  - last modified:     {}
-                  - is enabled:        {}
-
-                Nightlight settings
-                  - last modified:     {}
-                  - color temperature: {}K
-                  - schedule mode:     {}
-                  - schedule start:    {}
-                  - schedule end:      {}
-                  - sunset time:       {}
-                  - sunrise time:      {}
-                "#,
-                state_last_modified_local.format(DATE_TIME_FORMAT),
-                state.is_enabled,
-                settings_last_modified_local.format(DATE_TIME_FORMAT),
-                settings.color_temperature,
-                settings.schedule_mode,
-                settings.start_time.format(NAIVE_TIME_FORMAT),
-                settings.end_time.format(NAIVE_TIME_FORMAT),
-                settings.sunset_time.format(NAIVE_TIME_FORMAT),
-                settings.sunrise_time.format(NAIVE_TIME_FORMAT),
-            );
+        Commands::Sun {
+            lat,
+            lon,
+            date,
+            write,
+        } => {
+            let times = sun::calculate(lat, lon, date)?;
+            if write {
+                let (sunrise, sunset) = times.ok_or_else(|| {
+                    anyhow!("No sunrise/sunset to write; the 'solar' schedule can't follow it")
+                })?;
+                manager()?.transaction(|settings, _| {
+                    settings.set_sunrise_time(sunrise);
+                    settings.set_sunset_time(sunset);
+                    Ok(())
+                })?;
+            }
         }
     }
     Ok(())
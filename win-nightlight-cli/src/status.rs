@@ -0,0 +1,419 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use indoc::printdoc;
+use serde::Serialize;
+use serde_json::Value;
+use win_nightlight_lib::EffectiveStatus;
+use win_nightlight_lib::color::{gamma_ramp, white_point};
+use win_nightlight_lib::effective::effective_status;
+use win_nightlight_lib::nightlight_settings::NightlightSettings;
+use win_nightlight_lib::nightlight_state::NightlightState;
+
+use crate::Schedule;
+
+pub const NAIVE_TIME_FORMAT: &str = "%I:%M %p";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%d %I:%M:%S %p %Z";
+const NAIVE_DATE_TIME_FORMAT: &str = "%Y-%m-%d %I:%M %p";
+/// Prefix for the variable names printed by `--format env`.
+const ENV_PREFIX: &str = "NIGHTLIGHT";
+
+/// Output format of `wnl status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum StatusFormat {
+    /// Human-readable summary
+    #[default]
+    Text,
+    /// A JSON object
+    Json,
+    /// A TOML document
+    Toml,
+    /// Shell `KEY=value` lines, e.g. for `eval` or an env file
+    Env,
+}
+
+/// Machine-readable status. Field names are part of the `--format` output and must stay stable.
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    effective: EffectiveReport,
+    state: StateReport,
+    settings: SettingsReport,
+    /// Only present with `--rgb`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<ColorReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct EffectiveReport {
+    active: bool,
+    /// [win_nightlight_lib::ActiveReason::as_str]
+    reason: &'static str,
+    /// Local ISO-8601 date and time without offset, or empty if the status won't change
+    next_transition: String,
+}
+
+#[derive(Debug, Serialize)]
+struct StateReport {
+    /// ISO-8601 UTC
+    last_modified: String,
+    is_enabled: bool,
+    initialized: i32,
+    /// ISO-8601 UTC, or empty if the FILETIME predates the Unix epoch
+    last_transition: String,
+    last_transition_filetime: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct SettingsReport {
+    /// ISO-8601 UTC
+    last_modified: String,
+    color_temperature: u16,
+    /// Same identifiers as accepted by `wnl schedule`
+    schedule_mode: &'static str,
+    /// 24-hour `HH:MM`
+    schedule_start: String,
+    schedule_end: String,
+    sunset_time: String,
+    sunrise_time: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ColorReport {
+    /// `#rrggbb`
+    white_point: String,
+    /// Channel multipliers, 0.0 to 1.0
+    red: f64,
+    green: f64,
+    blue: f64,
+    gamma_ramp: GammaRampReport,
+}
+
+/// 256 16-bit entries per channel
+#[derive(Debug, Serialize)]
+struct GammaRampReport {
+    red: Vec<u16>,
+    green: Vec<u16>,
+    blue: Vec<u16>,
+}
+
+impl ColorReport {
+    fn new(color_temperature: u16) -> Self {
+        let white = white_point(color_temperature);
+        let ramp = gamma_ramp(color_temperature);
+        Self {
+            white_point: white.to_hex(),
+            red: white.red,
+            green: white.green,
+            blue: white.blue,
+            gamma_ramp: GammaRampReport {
+                red: ramp.red.to_vec(),
+                green: ramp.green.to_vec(),
+                blue: ramp.blue.to_vec(),
+            },
+        }
+    }
+}
+
+fn unix_to_utc(timestamp: u64) -> Result<DateTime<Utc>> {
+    i64::try_from(timestamp)
+        .ok()
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .ok_or_else(|| anyhow!("Failed to convert timestamp to DateTime"))
+}
+
+fn iso8601(dt: DateTime<Utc>) -> String {
+    dt.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn time_24h(t: NaiveTime) -> String {
+    t.format("%H:%M").to_string()
+}
+
+impl StatusReport {
+    fn new(
+        settings: &NightlightSettings,
+        state: &NightlightState,
+        effective: &EffectiveStatus,
+    ) -> Result<Self> {
+        Ok(Self {
+            effective: EffectiveReport {
+                active: effective.active,
+                reason: effective.reason.as_str(),
+                next_transition: effective
+                    .next_transition
+                    .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+                    .unwrap_or_default(),
+            },
+            state: StateReport {
+                last_modified: iso8601(unix_to_utc(state.timestamp)?),
+                is_enabled: state.is_enabled,
+                initialized: state.initialized,
+                last_transition: state.last_transition().map(iso8601).unwrap_or_default(),
+                last_transition_filetime: state.last_transition_filetime,
+            },
+            settings: SettingsReport {
+                last_modified: iso8601(unix_to_utc(settings.timestamp)?),
+                color_temperature: settings.color_temperature,
+                schedule_mode: Schedule::from(settings.schedule_mode).as_str(),
+                schedule_start: time_24h(settings.start_time),
+                schedule_end: time_24h(settings.end_time),
+                sunset_time: time_24h(settings.sunset_time),
+                sunrise_time: time_24h(settings.sunrise_time),
+            },
+            color: None,
+        })
+    }
+}
+
+/// Quotes a value for POSIX shells unless it only contains safe characters.
+fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "+-.:_/@".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// Flattens nested objects into `PREFIX_PARENT_CHILD=value` lines. Arrays become a single
+/// space-separated value.
+fn env_lines(prefix: &str, value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                env_lines(&format!("{prefix}_{}", key.to_uppercase()), value, out);
+            }
+        }
+        Value::String(s) => out.push(format!("{prefix}={}", shell_quote(s))),
+        Value::Array(items) => {
+            let joined = items
+                .iter()
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            out.push(format!("{prefix}={}", shell_quote(&joined)));
+        }
+        other => out.push(format!("{prefix}={other}")),
+    }
+}
+
+/// Renders the status in a machine-readable format.
+fn render(report: &StatusReport, format: StatusFormat) -> Result<String> {
+    Ok(match format {
+        StatusFormat::Text => unreachable!("text status is printed directly"),
+        StatusFormat::Json => serde_json::to_string_pretty(report)? + "\n",
+        StatusFormat::Toml => toml::to_string(report)?,
+        StatusFormat::Env => {
+            let mut lines = Vec::new();
+            env_lines(ENV_PREFIX, &serde_json::to_value(report)?, &mut lines);
+            lines.join("\n") + "\n"
+        }
+    })
+}
+
+fn print_text(
+    settings: &NightlightSettings,
+    state: &NightlightState,
+    effective: &EffectiveStatus,
+    rgb: bool,
+) -> Result<()> {
+    let next_transition = match effective.next_transition {
+        Some(t) => t.format(NAIVE_DATE_TIME_FORMAT).to_string(),
+        None => "none".to_string(),
+    };
+    let state_last_modified_local: DateTime<Local> = unix_to_utc(state.timestamp)?.into();
+    let settings_last_modified_local: DateTime<Local> = unix_to_utc(settings.timestamp)?.into();
+
+    printdoc!(
+        r#"
+        Nightlight is {} ({})
+          - next transition:   {}
+
+        Nightlight state:
+          - last modified:     {}
+          - is enabled:        {}
+
+        Nightlight settings
+          - last modified:     {}
+          - color temperature: {}K
+          - schedule mode:     {}
+          - schedule start:    {}
+          - schedule end:      {}
+          - sunset time:       {}
+          - sunrise time:      {}
+        "#,
+        if effective.active {
+            "active"
+        } else {
+            "inactive"
+        },
+        effective.reason,
+        next_transition,
+        state_last_modified_local.format(DATE_TIME_FORMAT),
+        state.is_enabled,
+        settings_last_modified_local.format(DATE_TIME_FORMAT),
+        settings.color_temperature,
+        settings.schedule_mode,
+        settings.start_time.format(NAIVE_TIME_FORMAT),
+        settings.end_time.format(NAIVE_TIME_FORMAT),
+        settings.sunset_time.format(NAIVE_TIME_FORMAT),
+        settings.sunrise_time.format(NAIVE_TIME_FORMAT),
+    );
+
+    if rgb {
+        let white = white_point(settings.color_temperature);
+        let ramp = gamma_ramp(settings.color_temperature);
+        printdoc!(
+            r#"
+
+            Color (approximate sRGB)
+              - white point:       {} (r {:.3}, g {:.3}, b {:.3})
+              - gamma ramp max:    r {}, g {}, b {}
+            "#,
+            white.to_hex(),
+            white.red,
+            white.green,
+            white.blue,
+            ramp.red[255],
+            ramp.green[255],
+            ramp.blue[255],
+        );
+    }
+    Ok(())
+}
+
+/// Prints the settings and state, and the effective status at the local time `now`, in the
+/// requested format. With `rgb`, also prints the white point and gamma ramp of the color
+/// temperature.
+pub fn print(
+    settings: &NightlightSettings,
+    state: &NightlightState,
+    now: NaiveDateTime,
+    format: StatusFormat,
+    rgb: bool,
+) -> Result<()> {
+    let effective = effective_status(settings, state, now);
+    if format == StatusFormat::Text {
+        return print_text(settings, state, &effective, rgb);
+    }
+    let mut report = StatusReport::new(settings, state, &effective)?;
+    if rgb {
+        report.color = Some(ColorReport::new(settings.color_temperature));
+    }
+    print!("{}", render(&report, format)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use win_nightlight_lib::nightlight_settings::{ScheduleMode, SettingsEncoding};
+    use win_nightlight_lib::nightlight_state::StateEncoding;
+    use win_nightlight_lib::{BondStruct, CloudStoreEnvelope};
+
+    fn report() -> StatusReport {
+        let settings = NightlightSettings {
+            timestamp: 1742540908,
+            schedule_mode: ScheduleMode::SunsetToSunrise,
+            color_temperature: 2790,
+            start_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            sunset_time: NaiveTime::from_hms_opt(19, 23, 0).unwrap(),
+            sunrise_time: NaiveTime::from_hms_opt(7, 12, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        };
+        let state = NightlightState {
+            timestamp: 1742670473,
+            is_enabled: false,
+            initialized: 1,
+            last_transition_filetime: 133_871_411_809_270_569,
+            unknown_fields: BondStruct::new(),
+            encoding: StateEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        };
+        let now = NaiveDate::from_ymd_opt(2025, 3, 22)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        StatusReport::new(&settings, &state, &effective_status(&settings, &state, now)).unwrap()
+    }
+
+    #[test]
+    fn json_format() {
+        let json: Value =
+            serde_json::from_str(&render(&report(), StatusFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["effective"]["active"], false);
+        assert_eq!(json["effective"]["reason"], "day");
+        assert_eq!(json["effective"]["next_transition"], "2025-03-22T19:23:00");
+        assert_eq!(json["state"]["last_modified"], "2025-03-22T19:07:53Z");
+        assert_eq!(
+            json["state"]["last_transition"],
+            "2025-03-22T18:19:40.927056900Z"
+        );
+        assert_eq!(json["settings"]["schedule_mode"], "solar");
+        assert_eq!(json["settings"]["schedule_start"], "22:00");
+        assert_eq!(json["settings"]["color_temperature"], 2790);
+    }
+
+    #[test]
+    fn toml_format() {
+        let toml = render(&report(), StatusFormat::Toml).unwrap();
+        assert!(toml.contains("[state]\n"));
+        assert!(toml.contains("is_enabled = false\n"));
+        assert!(toml.contains("[settings]\n"));
+        assert!(toml.contains("sunrise_time = \"07:12\"\n"));
+    }
+
+    #[test]
+    fn env_format() {
+        let env = render(&report(), StatusFormat::Env).unwrap();
+        let lines: Vec<&str> = env.lines().collect();
+        assert!(lines.contains(&"NIGHTLIGHT_STATE_IS_ENABLED=false"));
+        assert!(lines.contains(&"NIGHTLIGHT_EFFECTIVE_ACTIVE=false"));
+        assert!(lines.contains(&"NIGHTLIGHT_STATE_LAST_MODIFIED=2025-03-22T19:07:53Z"));
+        assert!(lines.contains(&"NIGHTLIGHT_SETTINGS_SCHEDULE_MODE=solar"));
+        assert!(lines.contains(&"NIGHTLIGHT_SETTINGS_SUNSET_TIME=19:23"));
+    }
+
+    #[test]
+    fn color_section() {
+        let json = render(&report(), StatusFormat::Json).unwrap();
+        assert!(!json.contains("\"color\""));
+
+        let mut report = report();
+        report.color = Some(ColorReport::new(2700));
+        let json: Value =
+            serde_json::from_str(&render(&report, StatusFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["color"]["white_point"], "#ffa759");
+        assert_eq!(json["color"]["red"], 1.0);
+        assert_eq!(
+            json["color"]["gamma_ramp"]["blue"]
+                .as_array()
+                .unwrap()
+                .len(),
+            256
+        );
+        assert_eq!(json["color"]["gamma_ramp"]["red"][255], 65535);
+
+        let toml = render(&report, StatusFormat::Toml).unwrap();
+        assert!(toml.contains("[color]\n"));
+        assert!(toml.contains("white_point = \"#ffa759\"\n"));
+
+        let env = render(&report, StatusFormat::Env).unwrap();
+        assert!(env.contains("NIGHTLIGHT_COLOR_WHITE_POINT='#ffa759'\n"));
+        assert!(env.contains("NIGHTLIGHT_COLOR_GAMMA_RAMP_RED='0 257 514 "));
+    }
+
+    #[test]
+    fn shell_quoting() {
+        assert_eq!(shell_quote("19:23"), "19:23");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("it's on"), r"'it'\''s on'");
+    }
+}
@@ -0,0 +1,40 @@
+use anyhow::{Result, anyhow};
+use chrono::{FixedOffset, Local, NaiveDate, NaiveTime, Offset, TimeZone};
+use win_nightlight_lib::solar::{self, SunTimes};
+
+use crate::status::NAIVE_TIME_FORMAT;
+
+/// Returns the local UTC offset in effect at noon on `date`.
+fn local_offset(date: NaiveDate) -> Result<FixedOffset> {
+    Local
+        .from_local_datetime(&date.and_time(NaiveTime::from_hms_opt(12, 0, 0).unwrap()))
+        .earliest()
+        .map(|dt| dt.offset().fix())
+        .ok_or_else(|| anyhow!("Failed to determine the local UTC offset on {date}"))
+}
+
+/// Calculates sunrise and sunset on `date` (default today) in the local time zone and prints
+/// them. Returns `(sunrise, sunset)`, or `None` during polar day or night.
+pub fn calculate(
+    latitude: f64,
+    longitude: f64,
+    date: Option<NaiveDate>,
+) -> Result<Option<(NaiveTime, NaiveTime)>> {
+    let date = date.unwrap_or_else(|| Local::now().date_naive());
+    let times = solar::sun_times(latitude, longitude, date, local_offset(date)?)?;
+    Ok(match times {
+        SunTimes::RiseSet { sunrise, sunset } => {
+            println!("Sunrise on {date}: {}", sunrise.format(NAIVE_TIME_FORMAT));
+            println!("Sunset on {date}:  {}", sunset.format(NAIVE_TIME_FORMAT));
+            Some((sunrise, sunset))
+        }
+        SunTimes::PolarDay => {
+            println!("The sun does not set on {date} (polar day)");
+            None
+        }
+        SunTimes::PolarNight => {
+            println!("The sun does not rise on {date} (polar night)");
+            None
+        }
+    })
+}
@@ -0,0 +1,442 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    Data, DeriveInput, Error, Fields, GenericParam, Ident, LitInt, LitStr, PathArguments, Type,
+    parse_macro_input,
+};
+
+/// Derives `BondSchema`, a CompactBinary reader and writer for a struct with named fields.
+///
+/// Every field needs a `#[bond(...)]` attribute:
+/// - `id = N, ty = "T"`: field `N` with Bond type `T`, one of `bool`, `uint8`, `uint16`,
+///   `uint32`, `uint64`, `int8`, `int16`, `int32`, `int64`, `float`, `double`, `string`,
+///   `wstring`, `struct` (a type that is itself `BondSchema`, including `BondStruct`) or
+///   `list<int8>` (`Vec<u8>` or a zero-copy `&[u8]`). The Rust type must be the one the
+///   matching `read_*`/`write_*` method uses, e.g. `i16` for `int16`.
+///   - An `Option<_>` field is only written when `Some`, and is `None` when absent.
+///   - Other fields take their `Default` value when absent and are always written, unless
+///     `omit_default` is given, in which case the default value isn't written.
+///   - `presence` marks a `bool` field whose value is whether the field exists at all; it's
+///     written with the type's default value when `true`. The value read is dropped; use an
+///     `Option<_>` field to keep it.
+/// - `unknown`: a `BondStruct` that collects every other field (including known IDs with an
+///   unexpected type) and writes them back interleaved in ID order. A known field whose ID
+///   is also captured isn't written, so the captured value takes its place. Without it,
+///   other fields are skipped.
+///
+/// The struct may have at most one lifetime parameter, which borrowed `list<int8>` fields
+/// share with the input.
+#[proc_macro_derive(BondStruct, attributes(bond))]
+pub fn derive_bond_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a field's value is encoded, from its `ty` attribute.
+enum Kind {
+    /// A primitive with `read_<method>`/`write_<method>` methods; `by_ref` for strings,
+    /// whose writers take `&str`.
+    Primitive {
+        variant: Ident,
+        method: Ident,
+        by_ref: bool,
+    },
+    Struct,
+    Int8List,
+}
+
+impl Kind {
+    fn parse(ty: &LitStr) -> syn::Result<Kind> {
+        let primitive = |variant: &str, by_ref| Kind::Primitive {
+            variant: Ident::new(variant, ty.span()),
+            method: Ident::new(&ty.value(), ty.span()),
+            by_ref,
+        };
+        Ok(match ty.value().as_str() {
+            "bool" => primitive("Bool", false),
+            "uint8" => primitive("UInt8", false),
+            "uint16" => primitive("UInt16", false),
+            "uint32" => primitive("UInt32", false),
+            "uint64" => primitive("UInt64", false),
+            "int8" => primitive("Int8", false),
+            "int16" => primitive("Int16", false),
+            "int32" => primitive("Int32", false),
+            "int64" => primitive("Int64", false),
+            "float" => primitive("Float", false),
+            "double" => primitive("Double", false),
+            "string" => primitive("String", true),
+            "wstring" => primitive("WString", true),
+            "struct" => Kind::Struct,
+            "list<int8>" => Kind::Int8List,
+            other => {
+                return Err(Error::new(
+                    ty.span(),
+                    format!("unsupported Bond type `{other}`"),
+                ));
+            }
+        })
+    }
+
+    fn variant(&self) -> Ident {
+        match self {
+            Kind::Primitive { variant, .. } => variant.clone(),
+            Kind::Struct => Ident::new("Struct", Span::call_site()),
+            Kind::Int8List => Ident::new("List", Span::call_site()),
+        }
+    }
+}
+
+struct KnownField {
+    ident: Ident,
+    ty: Type,
+    id: u16,
+    kind: Kind,
+    optional: bool,
+    omit_default: bool,
+    presence: bool,
+}
+
+enum BondField {
+    Known(Box<KnownField>),
+    Unknown(Ident),
+}
+
+/// Returns true if `ty` is spelled `Option<...>`.
+fn is_option(ty: &Type) -> bool {
+    if let Type::Path(path) = ty
+        && path.qself.is_none()
+        && let Some(segment) = path.path.segments.last()
+    {
+        return segment.ident == "Option"
+            && matches!(segment.arguments, PathArguments::AngleBracketed(_));
+    }
+    false
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<BondField> {
+    let ident = field.ident.clone().expect("named field");
+    let mut id: Option<u16> = None;
+    let mut kind: Option<Kind> = None;
+    let mut omit_default = false;
+    let mut presence = false;
+    let mut unknown = false;
+    let mut seen = false;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("bond")) {
+        seen = true;
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+            } else if meta.path.is_ident("ty") {
+                kind = Some(Kind::parse(&meta.value()?.parse::<LitStr>()?)?);
+            } else if meta.path.is_ident("omit_default") {
+                omit_default = true;
+            } else if meta.path.is_ident("presence") {
+                presence = true;
+            } else if meta.path.is_ident("unknown") {
+                unknown = true;
+            } else {
+                return Err(
+                    meta.error("expected `id`, `ty`, `omit_default`, `presence` or `unknown`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+
+    if !seen {
+        return Err(Error::new(
+            ident.span(),
+            "missing #[bond(id = .., ty = \"..\")] or #[bond(unknown)]",
+        ));
+    }
+    if unknown {
+        if id.is_some() || kind.is_some() || omit_default || presence {
+            return Err(Error::new(
+                ident.span(),
+                "#[bond(unknown)] can't be combined with other options",
+            ));
+        }
+        return Ok(BondField::Unknown(ident));
+    }
+
+    let id = id.ok_or_else(|| Error::new(ident.span(), "missing `id`"))?;
+    let kind = kind.ok_or_else(|| Error::new(ident.span(), "missing `ty`"))?;
+    let optional = is_option(&field.ty);
+    if optional && (omit_default || presence) {
+        return Err(Error::new(
+            field.ty.span(),
+            "`omit_default` and `presence` don't apply to `Option` fields",
+        ));
+    }
+    if presence && (omit_default || !matches!(kind, Kind::Primitive { .. })) {
+        return Err(Error::new(
+            ident.span(),
+            "`presence` needs a primitive `ty` and can't be combined with `omit_default`",
+        ));
+    }
+
+    Ok(BondField::Known(Box::new(KnownField {
+        ident,
+        ty: field.ty.clone(),
+        id,
+        kind,
+        optional,
+        omit_default,
+        presence,
+    })))
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.ident.span(),
+            "BondStruct can only be derived for structs",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(Error::new(
+            input.ident.span(),
+            "BondStruct needs a struct with named fields",
+        ));
+    };
+
+    let mut known = Vec::new();
+    let mut unknown: Option<Ident> = None;
+    for field in &named.named {
+        match parse_field(field)? {
+            BondField::Known(f) => {
+                if let Some(other) = known.iter().find(|k: &&KnownField| k.id == f.id) {
+                    return Err(Error::new(
+                        f.ident.span(),
+                        format!("field ID {} is already used by `{}`", f.id, other.ident),
+                    ));
+                }
+                known.push(*f);
+            }
+            BondField::Unknown(ident) => {
+                if unknown.is_some() {
+                    return Err(Error::new(
+                        ident.span(),
+                        "only one field can be #[bond(unknown)]",
+                    ));
+                }
+                unknown = Some(ident);
+            }
+        }
+    }
+    known.sort_by_key(|f| f.id);
+
+    let mut lifetimes = Vec::new();
+    for param in &input.generics.params {
+        match param {
+            GenericParam::Lifetime(l) => lifetimes.push(l.lifetime.clone()),
+            other => {
+                return Err(Error::new(
+                    other.span(),
+                    "BondStruct only supports lifetime parameters",
+                ));
+            }
+        }
+    }
+    let name = &input.ident;
+    let (impl_generics, de, self_ty) = match lifetimes.as_slice() {
+        [] => (quote!(<'de>), quote!('de), quote!(#name)),
+        [lt] => (quote!(<#lt>), quote!(#lt), quote!(#name<#lt>)),
+        _ => {
+            return Err(Error::new(
+                input.generics.span(),
+                "BondStruct supports at most one lifetime parameter",
+            ));
+        }
+    };
+
+    let bond = quote!(::win_nightlight_lib::bond);
+    // Mixed-site hygiene keeps these from clashing with the field-named locals
+    let reader = Ident::new("reader", Span::mixed_site());
+    let writer = Ident::new("writer", Span::mixed_site());
+    let pending = Ident::new("pending", Span::mixed_site());
+    let v = Ident::new("v", Span::mixed_site());
+
+    let read_fn = read_fields(&known, unknown.as_ref(), &bond, &de, &reader);
+    let write_fn = write_fields(&known, unknown.as_ref(), &bond, &writer, &pending, &v);
+
+    Ok(quote! {
+        impl #impl_generics #bond::BondSchema<#de> for #self_ty {
+            #read_fn
+            #write_fn
+        }
+    })
+}
+
+fn read_fields(
+    known: &[KnownField],
+    unknown: Option<&Ident>,
+    bond: &TokenStream2,
+    de: &TokenStream2,
+    reader: &Ident,
+) -> TokenStream2 {
+    let id_var = Ident::new("id", Span::mixed_site());
+    let type_var = Ident::new("bond_type", Span::mixed_site());
+
+    let locals = known.iter().map(|f| {
+        let (ident, ty) = (&f.ident, &f.ty);
+        quote!(let mut #ident: #ty = ::core::default::Default::default();)
+    });
+    let unknown_local = unknown.map(|u| quote!(let mut #u = #bond::BondStruct::new();));
+
+    let arms = known.iter().map(|f| {
+        let (ident, id, variant) = (&f.ident, f.id, f.kind.variant());
+        let body = if f.presence {
+            quote! {
+                #reader.skip_value(#bond::BondType::#variant)?;
+                #ident = true;
+            }
+        } else {
+            let value = match &f.kind {
+                Kind::Primitive { method, .. } => {
+                    let read = format_ident!("read_{}", method);
+                    quote!(#reader.#read()?)
+                }
+                Kind::Struct => quote!(#bond::BondSchema::read_fields(#reader)?),
+                Kind::Int8List => quote!(#bond::schema::read_int8_list(#reader, #id)?.into()),
+            };
+            if f.optional {
+                quote!(#ident = ::core::option::Option::Some(#value);)
+            } else {
+                quote!(#ident = #value;)
+            }
+        };
+        quote! {
+            #bond::FieldHeader::Field { id: #id, bond_type: #bond::BondType::#variant } => {
+                #body
+            }
+        }
+    });
+
+    let other = match unknown {
+        Some(u) => quote!(#u.push(#id_var, #reader.read_value(#type_var)?);),
+        None => quote!(#reader.skip_value(#type_var)?;),
+    };
+    let field_names = known.iter().map(|f| &f.ident).chain(unknown);
+
+    quote! {
+        fn read_fields(
+            #reader: &mut #bond::CompactBinaryReader<#de>,
+        ) -> ::core::result::Result<Self, #bond::BondError> {
+            #(#locals)*
+            #unknown_local
+            #reader.read_struct_begin()?;
+            loop {
+                match #reader.read_field_header()? {
+                    #bond::FieldHeader::Stop => break,
+                    #bond::FieldHeader::StopBase => continue,
+                    #(#arms)*
+                    #bond::FieldHeader::Field { id: #id_var, bond_type: #type_var } => {
+                        #other
+                    }
+                }
+            }
+            #reader.read_struct_end()?;
+            ::core::result::Result::Ok(Self { #(#field_names),* })
+        }
+    }
+}
+
+fn write_fields(
+    known: &[KnownField],
+    unknown: Option<&Ident>,
+    bond: &TokenStream2,
+    writer: &Ident,
+    pending: &Ident,
+    v: &Ident,
+) -> TokenStream2 {
+    let start = Ident::new("start", Span::mixed_site());
+    let init = unknown.map(|u| quote!(let mut #pending = self.#u.sorted_fields().peekable();));
+
+    let fields = known.iter().map(|f| {
+        let (ident, ty, id, variant) = (&f.ident, &f.ty, f.id, f.kind.variant());
+        let before = unknown.map(|_| quote!(#writer.write_fields_before(&mut #pending, #id);));
+        // A captured field with the same ID was read in place of this one (it had another
+        // type), so it's written instead of the default value to keep the ID unique
+        let shadowed = match unknown {
+            Some(u) => quote!(self.#u.has(#id)),
+            None => quote!(false),
+        };
+        let header = quote!(#writer.write_field_header(#id, #bond::BondType::#variant););
+
+        if f.presence {
+            let Kind::Primitive { method, .. } = &f.kind else {
+                unreachable!("presence is only allowed on primitives")
+            };
+            let write = format_ident!("write_{}", method);
+            return quote! {
+                #before
+                if self.#ident && !#shadowed {
+                    #header
+                    #writer.#write(::core::default::Default::default());
+                }
+            };
+        }
+
+        let value = match &f.kind {
+            Kind::Primitive { method, by_ref, .. } => {
+                let write = format_ident!("write_{}", method);
+                if *by_ref {
+                    quote!(#writer.#write(#v);)
+                } else {
+                    quote!(#writer.#write(*#v);)
+                }
+            }
+            Kind::Struct => quote!(#bond::BondSchema::write_fields(#v, #writer);),
+            Kind::Int8List => quote!(#bond::schema::write_int8_list(#writer, #v);),
+        };
+        let write = if f.optional {
+            quote! {
+                if let ::core::option::Option::Some(#v) = &self.#ident {
+                    #header
+                    #value
+                }
+            }
+        } else if f.omit_default {
+            quote! {
+                if self.#ident != <#ty as ::core::default::Default>::default() {
+                    let #v = &self.#ident;
+                    #header
+                    #value
+                }
+            }
+        } else {
+            quote! {
+                {
+                    let #v = &self.#ident;
+                    #header
+                    #value
+                }
+            }
+        };
+        quote! {
+            #before
+            if !#shadowed {
+                #write
+            }
+        }
+    });
+
+    let rest =
+        unknown.map(|_| quote!(#pending.for_each(|(id, val)| #writer.write_field(*id, val));));
+
+    quote! {
+        fn write_fields(&self, #writer: &mut #bond::CompactBinaryWriter) {
+            let #start = #writer.write_struct_begin();
+            #init
+            #(#fields)*
+            #rest
+            #writer.write_struct_end(#start);
+        }
+    }
+}
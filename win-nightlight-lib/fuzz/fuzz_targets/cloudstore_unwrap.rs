@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::cloudstore::{cloudstore_unwrap_envelope, cloudstore_wrap_envelope};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok((envelope, timestamp, payload)) = cloudstore_unwrap_envelope(data) else {
+        return;
+    };
+    let rewrapped = cloudstore_wrap_envelope(&envelope, timestamp, payload);
+    let (_, new_timestamp, new_payload) = cloudstore_unwrap_envelope(&rewrapped).unwrap();
+    assert_eq!((new_timestamp, new_payload), (timestamp, payload));
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::dump::{MarshaledStruct, dump};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(dumped) = dump(data) else {
+        return;
+    };
+    // Encoding is canonical, so a dump of the encoded bytes encodes to them again
+    let bytes = dumped.to_bytes();
+    assert_eq!(MarshaledStruct::read(&bytes).unwrap().to_bytes(), bytes);
+});
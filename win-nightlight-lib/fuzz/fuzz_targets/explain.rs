@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::explain::{AnnotationKind, explain};
+
+fuzz_target!(|data: &[u8]| {
+    let explanation = explain(data, None);
+
+    // Every byte is annotated exactly once, in order
+    let mut next = 0;
+    for a in &explanation.annotations {
+        if a.kind != AnnotationKind::Section {
+            assert_eq!(a.offset, next);
+            next += a.len;
+        }
+    }
+    assert_eq!(next, data.len());
+    let _ = explanation.to_string();
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::bond::FastBinaryReader;
+
+fuzz_target!(|data: &[u8]| {
+    let mut reader = FastBinaryReader::new(data);
+    let read = reader.read_struct().map(|_| reader.position());
+    let mut reader = FastBinaryReader::new(data);
+    let skipped = reader.skip_struct().map(|_| reader.position());
+
+    // Skipping walks the same fields, so it ends where reading does
+    if let Ok(end) = read {
+        assert_eq!(skipped.ok(), Some(end));
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::bond::{CompactBinaryReader, CompactBinaryVersion};
+
+fuzz_target!(|data: &[u8]| {
+    for version in [CompactBinaryVersion::V1, CompactBinaryVersion::V2] {
+        let mut reader = CompactBinaryReader::with_version(data, version);
+        let read = reader.read_struct().map(|_| reader.position());
+        let mut reader = CompactBinaryReader::with_version(data, version);
+        let skipped = reader.skip_struct().map(|_| reader.position());
+
+        // v1 skipping walks the same fields, so it ends where reading does. v2 skipping
+        // trusts the length prefix instead.
+        if let (CompactBinaryVersion::V1, Ok(end)) = (version, read) {
+            assert_eq!(skipped.ok(), Some(end));
+        }
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::nightlight_settings::NightlightSettings;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(settings) = NightlightSettings::deserialize_from_bytes(data) {
+        NightlightSettings::deserialize_from_bytes(&settings.serialize_to_bytes()).unwrap();
+    }
+});
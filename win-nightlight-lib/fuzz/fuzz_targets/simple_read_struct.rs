@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::bond::{
+    FastBinaryReader, SimpleBinaryReader, SimpleBinaryVersion, StructSchema,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // A FastBinary struct at the start gives the schema of the SimpleBinary data after it
+    let mut reader = FastBinaryReader::new(data);
+    let Ok(sample) = reader.read_struct() else {
+        return;
+    };
+    let schema = StructSchema::of(&sample);
+    let rest = &data[reader.position()..];
+    for version in [SimpleBinaryVersion::V1, SimpleBinaryVersion::V2] {
+        let _ = SimpleBinaryReader::with_version(rest, version).read_struct(&schema);
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use win_nightlight_lib::nightlight_state::NightlightState;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(state) = NightlightState::deserialize_from_bytes(data) {
+        NightlightState::deserialize_from_bytes(&state.serialize_to_bytes()).unwrap();
+    }
+});
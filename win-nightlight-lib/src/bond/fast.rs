@@ -0,0 +1,728 @@
+use super::BondError;
+use super::reader::{FieldHeader, ReadLimits};
+use super::types::*;
+use super::value::*;
+use super::varint::*;
+
+/// Deserializer for Bond FastBinary payloads.
+///
+/// FastBinary is laid out like CompactBinary v1, but integers are fixed-width little-endian
+/// and every field header is a type byte followed by a 16-bit ID.
+pub struct FastBinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    limits: ReadLimits,
+    depth: usize,
+    elements: u64,
+}
+
+impl<'a> FastBinaryReader<'a> {
+    /// Creates a reader positioned at the start of `data`.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            limits: ReadLimits::default(),
+            depth: 0,
+            elements: 0,
+        }
+    }
+
+    /// Returns the limits the reader enforces.
+    pub fn limits(&self) -> ReadLimits {
+        self.limits
+    }
+
+    /// Replaces the default [ReadLimits].
+    pub fn set_limits(&mut self, limits: ReadLimits) {
+        self.limits = limits;
+    }
+
+    /// Returns the offset of the next byte to be read.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the number of bytes left after the current position.
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], BondError> {
+        if self.remaining() < n {
+            return Err(BondError::UnexpectedEof(self.pos));
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    /// Runs `read` one nesting level deeper, failing if that exceeds the depth limit.
+    fn nested<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, BondError>,
+    ) -> Result<T, BondError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(BondError::DepthLimitExceeded(self.limits.max_depth));
+        }
+        self.depth += 1;
+        let result = read(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Counts `count` container elements against the element limit.
+    fn count_elements(&mut self, count: u32) -> Result<(), BondError> {
+        self.elements = self.elements.saturating_add(count as u64);
+        if self.elements > self.limits.max_elements {
+            return Err(BondError::ElementLimitExceeded(self.limits.max_elements));
+        }
+        Ok(())
+    }
+
+    /// Fails if `count` values of `size` bytes each exceed the allocation limit.
+    fn check_allocation(&self, count: u32, size: usize) -> Result<(), BondError> {
+        let size = count as u64 * size as u64;
+        if size > self.limits.max_allocation as u64 {
+            return Err(BondError::AllocationLimitExceeded {
+                size,
+                limit: self.limits.max_allocation,
+            });
+        }
+        Ok(())
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], BondError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    fn read_type(&mut self) -> Result<BondType, BondError> {
+        let raw = self.read_uint8()?;
+        BondType::try_from(raw).map_err(BondError::InvalidTypeId)
+    }
+
+    fn read_length(&mut self) -> Result<u32, BondError> {
+        let (val, new_pos) = read_varint(self.data, self.pos)?;
+        self.pos = new_pos;
+        Ok(val as u32)
+    }
+
+    // -- Marshaled header --
+
+    /// Reads the 4-byte marshaled header (`MF` magic and version 1) that precedes a
+    /// marshaled payload, failing with [BondError::InvalidHeader] for anything else.
+    pub fn read_marshaled_header(&mut self) -> Result<(), BondError> {
+        let magic = self.read_bytes(2)?;
+        if magic != FAST_BINARY_MAGIC {
+            return Err(BondError::InvalidHeader);
+        }
+        let version = self.read_bytes(2)?;
+        if version != FAST_BINARY_V1 {
+            return Err(BondError::InvalidHeader);
+        }
+        Ok(())
+    }
+
+    // -- Field headers --
+
+    /// Reads a field header: a type byte and, unless it's BT_STOP/BT_STOP_BASE, a uint16 ID.
+    pub fn read_field_header(&mut self) -> Result<FieldHeader, BondError> {
+        match self.read_uint8()? {
+            0 => Ok(FieldHeader::Stop),
+            1 => Ok(FieldHeader::StopBase),
+            raw => {
+                let bond_type = BondType::try_from(raw).map_err(BondError::InvalidTypeId)?;
+                let id = self.read_uint16()?;
+                Ok(FieldHeader::Field { id, bond_type })
+            }
+        }
+    }
+
+    // -- Primitive readers --
+
+    /// Reads a bool (one byte, non-zero is `true`).
+    pub fn read_bool(&mut self) -> Result<bool, BondError> {
+        Ok(self.read_uint8()? != 0)
+    }
+
+    /// Reads a uint8 (one byte).
+    pub fn read_uint8(&mut self) -> Result<u8, BondError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    /// Reads an int8 (one byte, two's complement).
+    pub fn read_int8(&mut self) -> Result<i8, BondError> {
+        Ok(self.read_uint8()? as i8)
+    }
+
+    /// Reads a uint16 (2 bytes, little-endian).
+    pub fn read_uint16(&mut self) -> Result<u16, BondError> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads an int16 (2 bytes, little-endian).
+    pub fn read_int16(&mut self) -> Result<i16, BondError> {
+        Ok(i16::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a uint32 (4 bytes, little-endian).
+    pub fn read_uint32(&mut self) -> Result<u32, BondError> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads an int32 (4 bytes, little-endian).
+    pub fn read_int32(&mut self) -> Result<i32, BondError> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a uint64 (8 bytes, little-endian).
+    pub fn read_uint64(&mut self) -> Result<u64, BondError> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads an int64 (8 bytes, little-endian).
+    pub fn read_int64(&mut self) -> Result<i64, BondError> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a float (4 bytes, little-endian IEEE 754).
+    pub fn read_float(&mut self) -> Result<f32, BondError> {
+        Ok(f32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a double (8 bytes, little-endian IEEE 754).
+    pub fn read_double(&mut self) -> Result<f64, BondError> {
+        Ok(f64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a string: a varint byte length followed by UTF-8.
+    pub fn read_string(&mut self) -> Result<String, BondError> {
+        let len = self.read_length()?;
+        self.check_allocation(len, 1)?;
+        let bytes = self.read_bytes(len as usize)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| BondError::InvalidUtf8)
+    }
+
+    /// Reads a wstring: a varint length in UTF-16 code units followed by UTF-16LE.
+    pub fn read_wstring(&mut self) -> Result<String, BondError> {
+        let len = self.read_length()?;
+        self.check_allocation(len, 2)?;
+        let len = len as usize;
+        let byte_len = len.checked_mul(2).ok_or(BondError::VarintOverflow)?;
+        let utf16: Vec<u16> = self
+            .read_bytes(byte_len)?
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16(&utf16).map_err(|_| BondError::InvalidUtf16)
+    }
+
+    // -- Container headers --
+
+    /// Reads a list or set header. Returns (element_type, count).
+    ///
+    /// The count is added to the elements read so far, failing past
+    /// [ReadLimits::max_elements].
+    pub fn read_container_header(&mut self) -> Result<(BondType, u32), BondError> {
+        let element_type = self.read_type()?;
+        let count = self.read_length()?;
+        self.count_elements(count)?;
+        Ok((element_type, count))
+    }
+
+    /// Reads a map header. Returns (key_type, value_type, count). The count is checked like
+    /// [FastBinaryReader::read_container_header]'s.
+    pub fn read_map_header(&mut self) -> Result<(BondType, BondType, u32), BondError> {
+        let key_type = self.read_type()?;
+        let value_type = self.read_type()?;
+        let count = self.read_length()?;
+        self.count_elements(count)?;
+        Ok((key_type, value_type, count))
+    }
+
+    // -- Skipping --
+
+    /// Advances past a value of the given Bond type without allocating, within the depth and
+    /// element limits.
+    pub fn skip_value(&mut self, bond_type: BondType) -> Result<(), BondError> {
+        match bond_type {
+            BondType::Bool | BondType::UInt8 | BondType::Int8 => {
+                self.read_bytes(1)?;
+            }
+            BondType::UInt16 | BondType::Int16 => {
+                self.read_bytes(2)?;
+            }
+            BondType::UInt32 | BondType::Int32 | BondType::Float => {
+                self.read_bytes(4)?;
+            }
+            BondType::UInt64 | BondType::Int64 | BondType::Double => {
+                self.read_bytes(8)?;
+            }
+            BondType::String => {
+                let len = self.read_length()? as usize;
+                self.read_bytes(len)?;
+            }
+            BondType::WString => {
+                let len = self.read_length()? as usize;
+                let byte_len = len.checked_mul(2).ok_or(BondError::VarintOverflow)?;
+                self.read_bytes(byte_len)?;
+            }
+            BondType::Struct => {
+                self.nested(Self::skip_struct)?;
+            }
+            BondType::List | BondType::Set => self.nested(|r| {
+                let (element_type, count) = r.read_container_header()?;
+                for _ in 0..count {
+                    r.skip_value(element_type)?;
+                }
+                Ok(())
+            })?,
+            BondType::Map => self.nested(|r| {
+                let (key_type, value_type, count) = r.read_map_header()?;
+                for _ in 0..count {
+                    r.skip_value(key_type)?;
+                    r.skip_value(value_type)?;
+                }
+                Ok(())
+            })?,
+        }
+        Ok(())
+    }
+
+    /// Advances past an entire struct (field headers + values) until BT_STOP, without allocating.
+    pub fn skip_struct(&mut self) -> Result<(), BondError> {
+        loop {
+            match self.read_field_header()? {
+                FieldHeader::Stop => return Ok(()),
+                FieldHeader::StopBase => continue,
+                FieldHeader::Field { bond_type, .. } => {
+                    self.skip_value(bond_type)?;
+                }
+            }
+        }
+    }
+
+    // -- High-level readers --
+
+    /// Reads a single value of the given Bond type, within the reader's [ReadLimits].
+    pub fn read_value(&mut self, bond_type: BondType) -> Result<BondValue, BondError> {
+        match bond_type {
+            BondType::Bool => Ok(BondValue::Bool(self.read_bool()?)),
+            BondType::UInt8 => Ok(BondValue::UInt8(self.read_uint8()?)),
+            BondType::Int8 => Ok(BondValue::Int8(self.read_int8()?)),
+            BondType::UInt16 => Ok(BondValue::UInt16(self.read_uint16()?)),
+            BondType::Int16 => Ok(BondValue::Int16(self.read_int16()?)),
+            BondType::UInt32 => Ok(BondValue::UInt32(self.read_uint32()?)),
+            BondType::Int32 => Ok(BondValue::Int32(self.read_int32()?)),
+            BondType::UInt64 => Ok(BondValue::UInt64(self.read_uint64()?)),
+            BondType::Int64 => Ok(BondValue::Int64(self.read_int64()?)),
+            BondType::Float => Ok(BondValue::Float(self.read_float()?)),
+            BondType::Double => Ok(BondValue::Double(self.read_double()?)),
+            BondType::String => Ok(BondValue::String(self.read_string()?)),
+            BondType::WString => Ok(BondValue::WString(self.read_wstring()?)),
+            BondType::Struct => Ok(BondValue::Struct(self.nested(Self::read_struct)?)),
+            BondType::List | BondType::Set => self.nested(|r| {
+                let (element_type, count) = r.read_container_header()?;
+                r.check_allocation(count, size_of::<BondValue>())?;
+                let mut elements = Vec::with_capacity((count as usize).min(r.remaining()));
+                for _ in 0..count {
+                    elements.push(r.read_value(element_type)?);
+                }
+                Ok(if bond_type == BondType::List {
+                    BondValue::List {
+                        element_type,
+                        elements,
+                    }
+                } else {
+                    BondValue::Set {
+                        element_type,
+                        elements,
+                    }
+                })
+            }),
+            BondType::Map => self.nested(|r| {
+                let (key_type, value_type, count) = r.read_map_header()?;
+                r.check_allocation(count, size_of::<(BondValue, BondValue)>())?;
+                let mut entries = Vec::with_capacity((count as usize).min(r.remaining()));
+                for _ in 0..count {
+                    let k = r.read_value(key_type)?;
+                    let v = r.read_value(value_type)?;
+                    entries.push((k, v));
+                }
+                Ok(BondValue::Map {
+                    key_type,
+                    value_type,
+                    entries,
+                })
+            }),
+        }
+    }
+
+    /// Reads all fields of a struct until BT_STOP, returning a BondStruct.
+    pub fn read_struct(&mut self) -> Result<BondStruct, BondError> {
+        let mut fields = Vec::new();
+        loop {
+            match self.read_field_header()? {
+                FieldHeader::Stop => break,
+                FieldHeader::StopBase => continue,
+                FieldHeader::Field { id, bond_type } => {
+                    let value = self.read_value(bond_type)?;
+                    fields.push((id, value));
+                }
+            }
+        }
+        Ok(BondStruct { fields })
+    }
+}
+
+/// Serializer for Bond FastBinary payloads.
+#[derive(Default)]
+pub struct FastBinaryWriter {
+    buf: Vec<u8>,
+}
+
+impl FastBinaryWriter {
+    /// Creates a writer with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the bytes written so far.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    fn write_length(&mut self, len: usize) {
+        write_varint(&mut self.buf, len as u64);
+    }
+
+    // -- Marshaled header --
+
+    /// Writes the 4-byte marshaled header (`MF` magic and version 1).
+    pub fn write_marshaled_header(&mut self) {
+        self.buf.extend_from_slice(&FAST_BINARY_MAGIC);
+        self.buf.extend_from_slice(&FAST_BINARY_V1);
+    }
+
+    // -- Field headers --
+
+    /// Writes a field header: the type byte followed by the ID as a uint16.
+    pub fn write_field_header(&mut self, id: u16, bond_type: BondType) {
+        self.buf.push(bond_type as u8);
+        self.write_uint16(id);
+    }
+
+    /// Writes BT_STOP, ending a struct.
+    pub fn write_stop(&mut self) {
+        self.buf.push(0x00);
+    }
+
+    /// Writes BT_STOP_BASE, ending the base-struct part of a struct.
+    pub fn write_stop_base(&mut self) {
+        self.buf.push(0x01);
+    }
+
+    // -- Primitive writers --
+
+    /// Writes a bool as one byte.
+    pub fn write_bool(&mut self, val: bool) {
+        self.buf.push(val as u8);
+    }
+
+    /// Writes a uint8 as one byte.
+    pub fn write_uint8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    /// Writes an int8 as one byte.
+    pub fn write_int8(&mut self, val: i8) {
+        self.buf.push(val as u8);
+    }
+
+    /// Writes a uint16 as 2 little-endian bytes.
+    pub fn write_uint16(&mut self, val: u16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes an int16 as 2 little-endian bytes.
+    pub fn write_int16(&mut self, val: i16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a uint32 as 4 little-endian bytes.
+    pub fn write_uint32(&mut self, val: u32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes an int32 as 4 little-endian bytes.
+    pub fn write_int32(&mut self, val: i32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a uint64 as 8 little-endian bytes.
+    pub fn write_uint64(&mut self, val: u64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes an int64 as 8 little-endian bytes.
+    pub fn write_int64(&mut self, val: i64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a float as 4 little-endian bytes.
+    pub fn write_float(&mut self, val: f32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a double as 8 little-endian bytes.
+    pub fn write_double(&mut self, val: f64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a string as a varint byte length followed by UTF-8.
+    pub fn write_string(&mut self, val: &str) {
+        self.write_length(val.len());
+        self.buf.extend_from_slice(val.as_bytes());
+    }
+
+    /// Writes a wstring as a varint UTF-16 length followed by UTF-16LE.
+    pub fn write_wstring(&mut self, val: &str) {
+        let utf16: Vec<u16> = val.encode_utf16().collect();
+        self.write_length(utf16.len());
+        for unit in &utf16 {
+            self.buf.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+
+    // -- Container headers --
+
+    /// Writes a list or set header (type byte + varint count).
+    pub fn write_container_header(&mut self, element_type: BondType, count: u32) {
+        self.buf.push(element_type as u8);
+        self.write_length(count as usize);
+    }
+
+    /// Writes a map header (key type + value type + varint count).
+    pub fn write_map_header(&mut self, key_type: BondType, value_type: BondType, count: u32) {
+        self.buf.push(key_type as u8);
+        self.buf.push(value_type as u8);
+        self.write_length(count as usize);
+    }
+
+    // -- High-level writers --
+
+    /// Writes a single BondValue.
+    pub fn write_value(&mut self, val: &BondValue) {
+        match val {
+            BondValue::Bool(v) => self.write_bool(*v),
+            BondValue::UInt8(v) => self.write_uint8(*v),
+            BondValue::Int8(v) => self.write_int8(*v),
+            BondValue::UInt16(v) => self.write_uint16(*v),
+            BondValue::Int16(v) => self.write_int16(*v),
+            BondValue::UInt32(v) => self.write_uint32(*v),
+            BondValue::Int32(v) => self.write_int32(*v),
+            BondValue::UInt64(v) => self.write_uint64(*v),
+            BondValue::Int64(v) => self.write_int64(*v),
+            BondValue::Float(v) => self.write_float(*v),
+            BondValue::Double(v) => self.write_double(*v),
+            BondValue::String(v) => self.write_string(v),
+            BondValue::WString(v) => self.write_wstring(v),
+            BondValue::Struct(s) => self.write_struct(s),
+            BondValue::List {
+                element_type,
+                elements,
+            }
+            | BondValue::Set {
+                element_type,
+                elements,
+            } => {
+                self.write_container_header(*element_type, elements.len() as u32);
+                for elem in elements {
+                    self.write_value(elem);
+                }
+            }
+            BondValue::Map {
+                key_type,
+                value_type,
+                entries,
+            } => {
+                self.write_map_header(*key_type, *value_type, entries.len() as u32);
+                for (k, v) in entries {
+                    self.write_value(k);
+                    self.write_value(v);
+                }
+            }
+        }
+    }
+
+    /// Writes a single struct field (header + value).
+    pub fn write_field(&mut self, id: u16, val: &BondValue) {
+        self.write_field_header(id, val.bond_type());
+        self.write_value(val);
+    }
+
+    /// Writes a BondStruct (fields + BT_STOP).
+    pub fn write_struct(&mut self, s: &BondStruct) {
+        for (id, val) in &s.fields {
+            self.write_field(*id, val);
+        }
+        self.write_stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bond::{CompactBinaryReader, CompactBinaryWriter};
+
+    fn sample() -> BondStruct {
+        BondStruct {
+            fields: vec![
+                (0, BondValue::Bool(true)),
+                (10, BondValue::Int32(-2)),
+                (
+                    20,
+                    BondValue::Struct(BondStruct {
+                        fields: vec![(300, BondValue::UInt64(1742540908))],
+                    }),
+                ),
+                (
+                    30,
+                    BondValue::List {
+                        element_type: BondType::WString,
+                        elements: vec![BondValue::WString("nuit".into())],
+                    },
+                ),
+                (
+                    40,
+                    BondValue::Map {
+                        key_type: BondType::String,
+                        value_type: BondType::Double,
+                        entries: vec![(BondValue::String("k".into()), BondValue::Double(0.5))],
+                    },
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn marshaled_header() {
+        let mut w = FastBinaryWriter::new();
+        w.write_marshaled_header();
+        let bytes = w.into_bytes();
+        assert_eq!(bytes, [0x4D, 0x46, 0x01, 0x00]);
+        FastBinaryReader::new(&bytes)
+            .read_marshaled_header()
+            .unwrap();
+
+        let mut r = FastBinaryReader::new(&[0x43, 0x42, 0x01, 0x00]);
+        assert!(matches!(
+            r.read_marshaled_header(),
+            Err(BondError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn fixed_width_fields() {
+        let mut w = FastBinaryWriter::new();
+        w.write_field(0, &BondValue::Int16(-2));
+        w.write_field(300, &BondValue::UInt32(1));
+        w.write_field(1, &BondValue::String("hi".into()));
+        w.write_stop();
+        assert_eq!(
+            w.into_bytes(),
+            [
+                0x0F, 0x00, 0x00, 0xFE, 0xFF, // field 0, int16 = -2
+                0x05, 0x2C, 0x01, 0x01, 0x00, 0x00, 0x00, // field 300, uint32 = 1
+                0x09, 0x01, 0x00, 0x02, 0x68, 0x69, // field 1, string = "hi"
+                0x00, // BT_STOP
+            ]
+        );
+    }
+
+    #[test]
+    fn roundtrip_matches_compact() {
+        let original = sample();
+
+        let mut w = FastBinaryWriter::new();
+        w.write_struct(&original);
+        let fast = w.into_bytes();
+
+        let mut r = FastBinaryReader::new(&fast);
+        assert_eq!(r.read_struct().unwrap(), original);
+        assert_eq!(r.remaining(), 0);
+
+        let mut r = FastBinaryReader::new(&fast);
+        r.skip_struct().unwrap();
+        assert_eq!(r.remaining(), 0);
+
+        let mut w = CompactBinaryWriter::new();
+        w.write_struct(&original);
+        let compact = w.into_bytes();
+        assert_ne!(compact, fast);
+        assert_eq!(
+            CompactBinaryReader::new(&compact).read_struct().unwrap(),
+            original
+        );
+    }
+
+    #[test]
+    fn truncated_and_invalid() {
+        let mut r = FastBinaryReader::new(&[0x10, 0x00]);
+        assert!(matches!(
+            r.read_field_header(),
+            Err(BondError::UnexpectedEof(1))
+        ));
+
+        let mut r = FastBinaryReader::new(&[0x13, 0x00, 0x00]);
+        assert!(matches!(
+            r.read_field_header(),
+            Err(BondError::InvalidTypeId(0x13))
+        ));
+
+        // Field 0, int32 with only 3 bytes
+        let mut r = FastBinaryReader::new(&[0x10, 0x00, 0x00, 0x01, 0x02, 0x03]);
+        assert!(matches!(r.read_struct(), Err(BondError::UnexpectedEof(3))));
+    }
+
+    #[test]
+    fn depth_limit() {
+        // Struct field 0 headers nested deep enough to overflow the stack without the limit
+        let mut data = [0x0A, 0x00, 0x00].repeat(200_000);
+        data.extend(vec![0x00; 200_001]);
+        assert!(matches!(
+            FastBinaryReader::new(&data).read_struct(),
+            Err(BondError::DepthLimitExceeded(64))
+        ));
+        assert!(matches!(
+            FastBinaryReader::new(&data).skip_struct(),
+            Err(BondError::DepthLimitExceeded(64))
+        ));
+    }
+
+    #[test]
+    fn element_and_allocation_limits() {
+        // A list of 2^32 - 1 empty structs
+        let data = [0x0B, 0x00, 0x00, 0x0A, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00];
+        assert!(matches!(
+            FastBinaryReader::new(&data).read_struct(),
+            Err(BondError::ElementLimitExceeded(_))
+        ));
+        assert!(matches!(
+            FastBinaryReader::new(&data).skip_struct(),
+            Err(BondError::ElementLimitExceeded(_))
+        ));
+
+        let mut r = FastBinaryReader::new(b"\x05hello");
+        r.set_limits(ReadLimits {
+            max_allocation: 4,
+            ..ReadLimits::default()
+        });
+        assert!(matches!(
+            r.read_string(),
+            Err(BondError::AllocationLimitExceeded { size: 5, limit: 4 })
+        ));
+    }
+}
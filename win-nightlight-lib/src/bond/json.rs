@@ -0,0 +1,634 @@
+use ::serde::de::{self, Deserialize, Deserializer};
+use ::serde::ser::{Serialize, Serializer};
+use serde_json::{Map, Value, json};
+use thiserror::Error;
+
+use super::types::BondType;
+use super::value::{BondStruct, BondValue};
+
+/// Errors from converting typed JSON to Bond values.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonError {
+    /// An object lacks a required key.
+    #[error("Missing \"{0}\"")]
+    MissingKey(&'static str),
+    /// A type name isn't a Bond type, or has the wrong number of type arguments.
+    #[error("Invalid type '{0}'")]
+    InvalidType(String),
+    /// A JSON value doesn't match what its position requires.
+    #[error("Expected {expected}, got {found}")]
+    Unexpected {
+        /// What was expected, e.g. `an int16 value`
+        expected: String,
+        /// The JSON found instead
+        found: String,
+    },
+    /// An error inside the field with this ID.
+    #[error("Field {id}: {source}")]
+    Field {
+        /// The field ID
+        id: u16,
+        /// The error in its value
+        source: Box<JsonError>,
+    },
+}
+
+impl JsonError {
+    fn unexpected(expected: impl Into<String>, found: &Value) -> Self {
+        JsonError::Unexpected {
+            expected: expected.into(),
+            found: found.to_string(),
+        }
+    }
+}
+
+/// Converts a value to typed JSON, e.g. `{"type": "int16", "value": 2790}`.
+///
+/// The `value` of a struct is its fields as from [struct_to_json], of a list or set an array
+/// of its elements, and of a map an array of `[key, value]` pairs. The type names the element
+/// types one level deep (`list<int8>`, `map<string,list>`), so elements that are lists, sets
+/// or maps themselves are typed objects again. Everything else is a plain JSON value:
+///
+/// - Integers are JSON numbers, exact over the whole `uint64` and `int64` range.
+/// - Floats are the shortest number that reads back as the same float. Infinities are
+///   `"Infinity"` and `"-Infinity"`, the canonical NaN is `"NaN"`, and any other NaN keeps its
+///   sign and payload bits as e.g. `"NaN:0x7fc00001"`.
+/// - `string` and `wstring` are JSON strings, told apart by the type. Both always hold valid
+///   Unicode: the reader rejects wstrings with unpaired surrogates, and JSON parsers reject
+///   unpaired `\ud800`-style escapes, so characters outside the BMP roundtrip as surrogate
+///   pairs on the wire and as one character in the JSON.
+pub fn value_to_json(value: &BondValue) -> Value {
+    json!({"type": type_name(value), "value": untyped_json(value)})
+}
+
+/// Converts typed JSON from [value_to_json] back to a value.
+pub fn value_from_json(json: &Value) -> Result<BondValue, JsonError> {
+    let object = as_object(json)?;
+    parse_value(&get_type(object)?, get(object, "value")?)
+}
+
+/// Converts a struct to an array of typed fields: `[{"id": 40, "type": "int16", "value":
+/// 2790}, ...]`, in the struct's field order. See [value_to_json] for the values.
+pub fn struct_to_json(s: &BondStruct) -> Value {
+    Value::Array(
+        s.fields
+            .iter()
+            .map(|(id, v)| field_to_json(*id, v))
+            .collect(),
+    )
+}
+
+/// Converts an array of typed fields from [struct_to_json] back to a struct.
+pub fn struct_from_json(json: &Value) -> Result<BondStruct, JsonError> {
+    let fields = fields_from_json(json, |object| {
+        parse_value(&get_type(object)?, get(object, "value")?)
+    })?;
+    Ok(BondStruct { fields })
+}
+
+/// Serializes as typed JSON, see [value_to_json]. Works with any self-describing format.
+impl Serialize for BondValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        value_to_json(self).serialize(serializer)
+    }
+}
+
+/// Deserializes typed JSON, see [value_from_json].
+impl<'de> Deserialize<'de> for BondValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        value_from_json(&Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+/// Serializes as an array of typed fields, see [struct_to_json].
+impl Serialize for BondStruct {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct_to_json(self).serialize(serializer)
+    }
+}
+
+/// Deserializes an array of typed fields, see [struct_from_json].
+impl<'de> Deserialize<'de> for BondStruct {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct_from_json(&Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+/// Converts a struct field to `{"id": .., "type": .., "value": ..}`.
+pub(crate) fn field_to_json(id: u16, value: &BondValue) -> Value {
+    json!({"id": id, "type": type_name(value), "value": untyped_json(value)})
+}
+
+/// Converts an array of field objects, reading each field's value with `parse`.
+pub(crate) fn fields_from_json<T>(
+    json: &Value,
+    mut parse: impl FnMut(&Map<String, Value>) -> Result<T, JsonError>,
+) -> Result<Vec<(u16, T)>, JsonError> {
+    let fields = json
+        .as_array()
+        .ok_or_else(|| JsonError::unexpected("an array of fields", json))?;
+    fields
+        .iter()
+        .map(|field| {
+            let object = as_object(field)?;
+            let id = get(object, "id")?;
+            let id = id
+                .as_u64()
+                .and_then(|id| u16::try_from(id).ok())
+                .ok_or_else(|| JsonError::unexpected("a field ID", id))?;
+            let value = parse(object).map_err(|e| JsonError::Field {
+                id,
+                source: Box::new(e),
+            })?;
+            Ok((id, value))
+        })
+        .collect()
+}
+
+/// Returns the type of a value as written in the JSON, e.g. `map<string,int32>`.
+pub(crate) fn type_name(value: &BondValue) -> String {
+    match value {
+        BondValue::List { element_type, .. } => format!("list<{}>", element_type.name()),
+        BondValue::Set { element_type, .. } => format!("set<{}>", element_type.name()),
+        BondValue::Map {
+            key_type,
+            value_type,
+            ..
+        } => format!("map<{},{}>", key_type.name(), value_type.name()),
+        _ => value.bond_type().name().to_string(),
+    }
+}
+
+fn float_json(value: f32) -> Value {
+    if value.is_nan() {
+        return nan_json(
+            value.to_bits() == f32::NAN.to_bits(),
+            value.to_bits() as u64,
+            8,
+        );
+    }
+    if value.is_infinite() {
+        return infinity_json(value > 0.0);
+    }
+    // The shortest decimal of the f32 reads back as the same f32 through f64, except perhaps
+    // right next to a rounding boundary
+    let shortest: f64 = value.to_string().parse().unwrap_or(value as f64);
+    if (shortest as f32).to_bits() == value.to_bits() {
+        json!(shortest)
+    } else {
+        json!(value as f64)
+    }
+}
+
+fn double_json(value: f64) -> Value {
+    if value.is_nan() {
+        return nan_json(value.to_bits() == f64::NAN.to_bits(), value.to_bits(), 16);
+    }
+    if value.is_infinite() {
+        return infinity_json(value > 0.0);
+    }
+    json!(value)
+}
+
+fn nan_json(canonical: bool, bits: u64, digits: usize) -> Value {
+    if canonical {
+        json!("NaN")
+    } else {
+        json!(format!("NaN:0x{bits:0digits$x}"))
+    }
+}
+
+fn infinity_json(positive: bool) -> Value {
+    json!(if positive { "Infinity" } else { "-Infinity" })
+}
+
+/// Converts a value to JSON without its type.
+fn untyped_json(value: &BondValue) -> Value {
+    match value {
+        BondValue::Bool(v) => json!(v),
+        BondValue::UInt8(v) => json!(v),
+        BondValue::UInt16(v) => json!(v),
+        BondValue::UInt32(v) => json!(v),
+        BondValue::UInt64(v) => json!(v),
+        BondValue::Int8(v) => json!(v),
+        BondValue::Int16(v) => json!(v),
+        BondValue::Int32(v) => json!(v),
+        BondValue::Int64(v) => json!(v),
+        BondValue::Float(v) => float_json(*v),
+        BondValue::Double(v) => double_json(*v),
+        BondValue::String(v) | BondValue::WString(v) => json!(v),
+        BondValue::Struct(s) => struct_to_json(s),
+        BondValue::List { elements, .. } | BondValue::Set { elements, .. } => {
+            Value::Array(elements.iter().map(element_json).collect())
+        }
+        BondValue::Map { entries, .. } => Value::Array(
+            entries
+                .iter()
+                .map(|(k, v)| json!([element_json(k), element_json(v)]))
+                .collect(),
+        ),
+    }
+}
+
+/// Converts a container element, typing it if it's a container itself.
+fn element_json(value: &BondValue) -> Value {
+    match value {
+        BondValue::List { .. } | BondValue::Set { .. } | BondValue::Map { .. } => {
+            value_to_json(value)
+        }
+        _ => untyped_json(value),
+    }
+}
+
+/// A type as written in the JSON: a Bond type with the element, or key and value, types of
+/// containers.
+pub(crate) struct TypeName {
+    pub(crate) bond_type: BondType,
+    pub(crate) args: Vec<BondType>,
+}
+
+fn parse_type_name(name: &str) -> Result<TypeName, JsonError> {
+    let invalid = || JsonError::InvalidType(name.to_string());
+    let (base, args) = match name.split_once('<') {
+        Some((base, rest)) => {
+            let args = rest.strip_suffix('>').ok_or_else(invalid)?;
+            let args = args
+                .split(',')
+                .map(|a| BondType::from_name(a.trim()).ok_or_else(invalid))
+                .collect::<Result<Vec<_>, _>>()?;
+            (base, args)
+        }
+        None => (name, Vec::new()),
+    };
+    let bond_type = BondType::from_name(base.trim()).ok_or_else(invalid)?;
+    let arity = match bond_type {
+        BondType::List | BondType::Set => 1,
+        BondType::Map => 2,
+        _ => 0,
+    };
+    if args.len() != arity {
+        return Err(invalid());
+    }
+    Ok(TypeName { bond_type, args })
+}
+
+fn as_object(json: &Value) -> Result<&Map<String, Value>, JsonError> {
+    json.as_object()
+        .ok_or_else(|| JsonError::unexpected("an object", json))
+}
+
+pub(crate) fn get<'a>(
+    object: &'a Map<String, Value>,
+    key: &'static str,
+) -> Result<&'a Value, JsonError> {
+    object.get(key).ok_or(JsonError::MissingKey(key))
+}
+
+pub(crate) fn get_type(object: &Map<String, Value>) -> Result<TypeName, JsonError> {
+    let name = get(object, "type")?;
+    let name = name
+        .as_str()
+        .ok_or_else(|| JsonError::unexpected("a type name", name))?;
+    parse_type_name(name)
+}
+
+fn parse_int<T: TryFrom<i64> + TryFrom<u64>>(json: &Value) -> Option<T> {
+    match json.as_u64() {
+        Some(v) => T::try_from(v).ok(),
+        None => json.as_i64().and_then(|v| T::try_from(v).ok()),
+    }
+}
+
+/// Parses a float given as a number or one of the strings of [value_to_json], returning
+/// NaN payloads as bits.
+fn parse_float(json: &Value, digits: usize) -> Option<Result<f64, u64>> {
+    let Some(s) = json.as_str() else {
+        return json.as_f64().map(Ok);
+    };
+    match s {
+        "NaN" => Some(Ok(f64::NAN)),
+        "Infinity" => Some(Ok(f64::INFINITY)),
+        "-Infinity" => Some(Ok(f64::NEG_INFINITY)),
+        _ => {
+            let hex = s.strip_prefix("NaN:0x")?;
+            if hex.len() != digits {
+                return None;
+            }
+            u64::from_str_radix(hex, 16).ok().map(Err)
+        }
+    }
+}
+
+fn parse_f32(json: &Value) -> Option<f32> {
+    match parse_float(json, 8)? {
+        Ok(v) => Some(v as f32),
+        Err(bits) => Some(f32::from_bits(bits as u32)).filter(|v| v.is_nan()),
+    }
+}
+
+fn parse_f64(json: &Value) -> Option<f64> {
+    match parse_float(json, 16)? {
+        Ok(v) => Some(v),
+        Err(bits) => Some(f64::from_bits(bits)).filter(|v| v.is_nan()),
+    }
+}
+
+/// Parses an untyped value of the given type.
+pub(crate) fn parse_value(type_name: &TypeName, json: &Value) -> Result<BondValue, JsonError> {
+    let bond_type = type_name.bond_type;
+    let invalid = || JsonError::unexpected(format!("a {} value", bond_type.name()), json);
+    Ok(match bond_type {
+        BondType::Bool => BondValue::Bool(json.as_bool().ok_or_else(invalid)?),
+        BondType::UInt8 => BondValue::UInt8(parse_int(json).ok_or_else(invalid)?),
+        BondType::UInt16 => BondValue::UInt16(parse_int(json).ok_or_else(invalid)?),
+        BondType::UInt32 => BondValue::UInt32(parse_int(json).ok_or_else(invalid)?),
+        BondType::UInt64 => BondValue::UInt64(parse_int(json).ok_or_else(invalid)?),
+        BondType::Int8 => BondValue::Int8(parse_int(json).ok_or_else(invalid)?),
+        BondType::Int16 => BondValue::Int16(parse_int(json).ok_or_else(invalid)?),
+        BondType::Int32 => BondValue::Int32(parse_int(json).ok_or_else(invalid)?),
+        BondType::Int64 => BondValue::Int64(parse_int(json).ok_or_else(invalid)?),
+        BondType::Float => BondValue::Float(parse_f32(json).ok_or_else(invalid)?),
+        BondType::Double => BondValue::Double(parse_f64(json).ok_or_else(invalid)?),
+        BondType::String => BondValue::String(json.as_str().ok_or_else(invalid)?.into()),
+        BondType::WString => BondValue::WString(json.as_str().ok_or_else(invalid)?.into()),
+        BondType::Struct => BondValue::Struct(struct_from_json(json)?),
+        BondType::List | BondType::Set => {
+            let element_type = type_name.args[0];
+            let elements = json
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|e| parse_element(element_type, e))
+                .collect::<Result<Vec<_>, _>>()?;
+            if bond_type == BondType::List {
+                BondValue::List {
+                    element_type,
+                    elements,
+                }
+            } else {
+                BondValue::Set {
+                    element_type,
+                    elements,
+                }
+            }
+        }
+        BondType::Map => {
+            let (key_type, value_type) = (type_name.args[0], type_name.args[1]);
+            let entries = json
+                .as_array()
+                .ok_or_else(invalid)?
+                .iter()
+                .map(|entry| match entry.as_array().map(Vec::as_slice) {
+                    Some([k, v]) => {
+                        Ok((parse_element(key_type, k)?, parse_element(value_type, v)?))
+                    }
+                    _ => Err(JsonError::unexpected("a [key, value] map entry", entry)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            BondValue::Map {
+                key_type,
+                value_type,
+                entries,
+            }
+        }
+    })
+}
+
+fn parse_element(element_type: BondType, json: &Value) -> Result<BondValue, JsonError> {
+    match element_type {
+        BondType::List | BondType::Set | BondType::Map => {
+            let value = value_from_json(json)?;
+            if value.bond_type() != element_type {
+                return Err(JsonError::unexpected(
+                    format!("a {} element", element_type.name()),
+                    json,
+                ));
+            }
+            Ok(value)
+        }
+        _ => parse_value(
+            &TypeName {
+                bond_type: element_type,
+                args: Vec::new(),
+            },
+            json,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bond::{CompactBinaryReader, CompactBinaryWriter};
+
+    fn roundtrip(value: BondValue) -> BondValue {
+        let text = serde_json::to_string(&value).unwrap();
+        serde_json::from_str(&text).unwrap()
+    }
+
+    fn list(element_type: BondType, elements: Vec<BondValue>) -> BondValue {
+        BondValue::List {
+            element_type,
+            elements,
+        }
+    }
+
+    #[test]
+    fn typed_fields() {
+        let s = BondStruct {
+            fields: vec![
+                (40, BondValue::Int16(2790)),
+                (
+                    20,
+                    BondValue::Struct(BondStruct {
+                        fields: vec![(0, BondValue::Int8(1))],
+                    }),
+                ),
+            ],
+        };
+        let json = struct_to_json(&s);
+        assert_eq!(
+            json,
+            json!([
+                {"id": 40, "type": "int16", "value": 2790},
+                {"id": 20, "type": "struct", "value": [{"id": 0, "type": "int8", "value": 1}]},
+            ])
+        );
+        assert_eq!(struct_from_json(&json).unwrap(), s);
+    }
+
+    #[test]
+    fn containers() {
+        let nested = list(
+            BondType::List,
+            vec![
+                list(BondType::Int8, vec![BondValue::Int8(-1)]),
+                // Elements of one list may disagree on their own element types
+                list(BondType::WString, vec![]),
+            ],
+        );
+        assert_eq!(
+            value_to_json(&nested),
+            json!({"type": "list<list>", "value": [
+                {"type": "list<int8>", "value": [-1]},
+                {"type": "list<wstring>", "value": []},
+            ]})
+        );
+        assert_eq!(roundtrip(nested.clone()), nested);
+
+        let map = BondValue::Map {
+            key_type: BondType::String,
+            value_type: BondType::Set,
+            entries: vec![(
+                BondValue::String("k".into()),
+                BondValue::Set {
+                    element_type: BondType::UInt64,
+                    elements: vec![BondValue::UInt64(u64::MAX)],
+                },
+            )],
+        };
+        assert_eq!(
+            value_to_json(&map),
+            json!({"type": "map<string,set>", "value": [
+                ["k", {"type": "set<uint64>", "value": [u64::MAX]}],
+            ]})
+        );
+        assert_eq!(roundtrip(map.clone()), map);
+    }
+
+    #[test]
+    fn integer_limits() {
+        for value in [
+            BondValue::UInt64(u64::MAX),
+            BondValue::Int64(i64::MIN),
+            BondValue::Int64(i64::MAX),
+            BondValue::UInt32(u32::MAX),
+            BondValue::Int8(i8::MIN),
+        ] {
+            assert_eq!(roundtrip(value.clone()), value);
+        }
+        let parse = |json: Value| value_from_json(&json);
+        assert!(parse(json!({"type": "uint8", "value": 256})).is_err());
+        assert!(parse(json!({"type": "int8", "value": -129})).is_err());
+        assert!(parse(json!({"type": "uint64", "value": -1})).is_err());
+        assert!(parse(json!({"type": "int32", "value": 1.5})).is_err());
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(value_to_json(&BondValue::Float(0.1))["value"], json!(0.1));
+        assert_eq!(
+            value_to_json(&BondValue::Double(-0.0))["value"],
+            json!(-0.0)
+        );
+        assert_eq!(
+            value_to_json(&BondValue::Float(f32::NAN))["value"],
+            json!("NaN")
+        );
+        assert_eq!(
+            value_to_json(&BondValue::Double(f64::NEG_INFINITY))["value"],
+            json!("-Infinity")
+        );
+        let payload_nan = f32::from_bits(0xFFC0_0001);
+        assert_eq!(
+            value_to_json(&BondValue::Float(payload_nan))["value"],
+            json!("NaN:0xffc00001")
+        );
+
+        // Compare bits, since NaN != NaN and -0.0 == 0.0
+        let floats = [
+            0.1,
+            -0.0,
+            f32::MIN_POSITIVE,
+            f32::from_bits(1),
+            f32::MAX,
+            16777217.0,
+            f32::INFINITY,
+            f32::NAN,
+            payload_nan,
+        ];
+        for v in floats {
+            let BondValue::Float(back) = roundtrip(BondValue::Float(v)) else {
+                panic!("expected a float");
+            };
+            assert_eq!(back.to_bits(), v.to_bits(), "{v}");
+        }
+        let doubles = [
+            0.1,
+            -0.0,
+            f64::from_bits(1),
+            f64::MAX,
+            f64::INFINITY,
+            f64::NAN,
+            f64::from_bits(0x7FF0_0000_0000_0001),
+        ];
+        for v in doubles {
+            let BondValue::Double(back) = roundtrip(BondValue::Double(v)) else {
+                panic!("expected a double");
+            };
+            assert_eq!(back.to_bits(), v.to_bits(), "{v}");
+        }
+
+        let parse = |json: Value| value_from_json(&json);
+        // Not a NaN, and too few digits for a double
+        assert!(parse(json!({"type": "float", "value": "NaN:0x3f800000"})).is_err());
+        assert!(parse(json!({"type": "double", "value": "NaN:0x7fc00001"})).is_err());
+        assert!(parse(json!({"type": "double", "value": "nan"})).is_err());
+    }
+
+    #[test]
+    fn strings() {
+        // Outside the BMP, so a surrogate pair in the wstring
+        let text = "snow ☃ 𝄞 \0 end";
+        for value in [
+            BondValue::String(text.into()),
+            BondValue::WString(text.into()),
+        ] {
+            let mut writer = CompactBinaryWriter::new();
+            writer.write_value(&value);
+            let bytes = writer.into_bytes();
+            let back = roundtrip(value.clone());
+            assert_eq!(back, value);
+            let mut writer = CompactBinaryWriter::new();
+            writer.write_value(&back);
+            assert_eq!(writer.into_bytes(), bytes);
+        }
+
+        // An unpaired surrogate can neither be read from Bond nor from JSON
+        let mut reader = CompactBinaryReader::new(&[0x01, 0x00, 0xD8]);
+        assert!(reader.read_wstring().is_err());
+        let json = r#"{"type": "wstring", "value": "\ud800"}"#;
+        assert!(serde_json::from_str::<BondValue>(json).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let parse = |json: Value| struct_from_json(&json);
+        assert_eq!(
+            parse(json!([{"id": 3, "type": "int9", "value": 1}])),
+            Err(JsonError::Field {
+                id: 3,
+                source: Box::new(JsonError::InvalidType("int9".into())),
+            })
+        );
+        assert_eq!(
+            parse(json!([{"id": 3, "type": "int8"}])),
+            Err(JsonError::Field {
+                id: 3,
+                source: Box::new(JsonError::MissingKey("value")),
+            })
+        );
+        assert!(parse(json!([{"id": 70000, "type": "int8", "value": 1}])).is_err());
+        assert!(parse(json!([{"id": 0, "type": "list<int8,int8>", "value": []}])).is_err());
+        assert!(parse(json!([{"id": 0, "type": "list", "value": []}])).is_err());
+        assert!(parse(json!([{"id": 0, "type": "map<string,int8>", "value": [["a"]]}])).is_err());
+        assert!(parse(json!([{"id": 0, "type": "list<set>", "value": [[1]]}])).is_err());
+        assert!(
+            parse(json!([{"id": 0, "type": "list<set>", "value": [
+                {"type": "list<int8>", "value": []}
+            ]}]))
+            .is_err()
+        );
+    }
+}
@@ -1,39 +1,94 @@
-#[allow(unused)]
-mod types;
-#[allow(unused)]
-mod varint;
-
-#[allow(unused)]
+/// FastBinary reader and writer.
+pub mod fast;
+/// Lossless typed JSON for values, e.g. `{"type": "int16", "value": 2790}`.
+#[cfg(feature = "json")]
+pub mod json;
+/// Protocol detection from marshaled headers.
+pub mod protocol;
+/// Decoding of CompactBinary bytes.
 pub mod reader;
-#[allow(unused)]
+/// Fixed-schema structs and the `BondStruct` derive's helpers.
+pub mod schema;
+/// serde support, encoding any serde type as a CompactBinary v1 struct.
+#[cfg(feature = "serde")]
+pub mod serde;
+/// SimpleBinary reader and writer, and the schemas they need.
+pub mod simple;
+/// Bond data type identifiers and protocol constants.
+pub mod types;
+/// Self-describing Bond values.
 pub mod value;
-#[allow(unused)]
+/// Varint and ZigZag integer encodings used by CompactBinary.
+pub mod varint;
+/// Encoding of CompactBinary bytes.
 pub mod writer;
 
-pub use reader::{CompactBinaryReader, FieldHeader};
-pub use types::BondType;
-#[allow(unused_imports)]
+pub use fast::{FastBinaryReader, FastBinaryWriter};
+pub use protocol::{Protocol, sniff_protocol};
+pub use reader::{CompactBinaryReader, FieldHeader, ReadLimits};
+pub use schema::BondSchema;
+pub use simple::{SimpleBinaryReader, SimpleBinaryWriter, StructSchema, TypeSchema};
+pub use types::{
+    BondType, COMPACT_BINARY_MAGIC, COMPACT_BINARY_V1, COMPACT_BINARY_V2, CompactBinaryVersion,
+    FAST_BINARY_MAGIC, FAST_BINARY_V1, SIMPLE_BINARY_MAGIC, SIMPLE_BINARY_V1, SIMPLE_BINARY_V2,
+    SimpleBinaryVersion,
+};
 pub use value::{BondStruct, BondValue};
+pub use win_nightlight_derive::BondStruct;
 pub use writer::CompactBinaryWriter;
 
 use thiserror::Error;
 
+/// Errors from reading Bond data.
+///
+/// New variants may be added in minor releases.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum BondError {
+    /// The data ended before a value at this offset was complete.
     #[error("Unexpected end of data at position {0}")]
     UnexpectedEof(usize),
+    /// The marshaled header isn't a supported protocol and version.
     #[error("Invalid marshaled header")]
     InvalidHeader,
+    /// A field or container header has an unknown type ID.
     #[error("Invalid type ID: {0}")]
     InvalidTypeId(u8),
+    /// A varint is longer than its integer type allows.
     #[error("Varint overflow")]
     VarintOverflow,
+    /// A string isn't valid UTF-8.
     #[error("Invalid UTF-8 string")]
     InvalidUtf8,
+    /// A wstring isn't valid UTF-16.
     #[error("Invalid UTF-16 string")]
     InvalidUtf16,
+    /// A required field is absent.
     #[error("Missing required field {0}")]
     MissingField(u16),
+    /// A known field has a different type than its schema.
     #[error("Unexpected field type for field {0}")]
     UnexpectedFieldType(u16),
+    /// Structs and containers are nested deeper than [ReadLimits::max_depth].
+    #[error("Nesting exceeds the depth limit of {0}")]
+    DepthLimitExceeded(usize),
+    /// The containers read hold more elements in total than [ReadLimits::max_elements].
+    #[error("Containers exceed the element limit of {0}")]
+    ElementLimitExceeded(u64),
+    /// A v2 struct's BT_STOP isn't where its length prefix says the struct ends.
+    #[error("Struct ends at position {actual}, but its length says {expected}")]
+    StructLengthMismatch {
+        /// Position after the struct, from its length prefix
+        expected: usize,
+        /// Position after its BT_STOP
+        actual: usize,
+    },
+    /// A string or container needs more memory than [ReadLimits::max_allocation].
+    #[error("Allocation of {size} bytes exceeds the limit of {limit}")]
+    AllocationLimitExceeded {
+        /// Bytes the value needs, from its length or element count
+        size: u64,
+        /// The limit
+        limit: usize,
+    },
 }
@@ -0,0 +1,88 @@
+use super::types::*;
+
+/// A Bond binary protocol and version, as named by a marshaled header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// CompactBinary (`CB`, COMPACT_PROTOCOL = 0x4243)
+    CompactBinary(CompactBinaryVersion),
+    /// FastBinary (`MF`, FAST_PROTOCOL = 0x464D), version 1
+    FastBinary,
+    /// SimpleBinary (`SP`, SIMPLE_PROTOCOL = 0x5053)
+    SimpleBinary(SimpleBinaryVersion),
+}
+
+/// Identifies the protocol of a marshaled payload from its 4-byte header.
+///
+/// Returns `None` if `data` doesn't start with the magic of a supported protocol followed by
+/// one of its versions. The header itself still has to be read by the protocol's reader.
+pub fn sniff_protocol(data: &[u8]) -> Option<Protocol> {
+    let (magic, version) = (data.get(..2)?, data.get(2..4)?);
+    if magic == COMPACT_BINARY_MAGIC {
+        if version == COMPACT_BINARY_V1 {
+            Some(Protocol::CompactBinary(CompactBinaryVersion::V1))
+        } else if version == COMPACT_BINARY_V2 {
+            Some(Protocol::CompactBinary(CompactBinaryVersion::V2))
+        } else {
+            None
+        }
+    } else if magic == FAST_BINARY_MAGIC {
+        (version == FAST_BINARY_V1).then_some(Protocol::FastBinary)
+    } else if magic == SIMPLE_BINARY_MAGIC {
+        if version == SIMPLE_BINARY_V1 {
+            Some(Protocol::SimpleBinary(SimpleBinaryVersion::V1))
+        } else if version == SIMPLE_BINARY_V2 {
+            Some(Protocol::SimpleBinary(SimpleBinaryVersion::V2))
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bond::{CompactBinaryWriter, FastBinaryWriter, SimpleBinaryWriter};
+
+    #[test]
+    fn sniffs_written_headers() {
+        let mut w = CompactBinaryWriter::with_version(CompactBinaryVersion::V2);
+        w.write_marshaled_header();
+        assert_eq!(
+            sniff_protocol(&w.into_bytes()),
+            Some(Protocol::CompactBinary(CompactBinaryVersion::V2))
+        );
+
+        let mut w = FastBinaryWriter::new();
+        w.write_marshaled_header();
+        assert_eq!(sniff_protocol(&w.into_bytes()), Some(Protocol::FastBinary));
+
+        let mut w = SimpleBinaryWriter::new();
+        w.write_marshaled_header();
+        assert_eq!(
+            sniff_protocol(&w.into_bytes()),
+            Some(Protocol::SimpleBinary(SimpleBinaryVersion::V1))
+        );
+    }
+
+    #[test]
+    fn sniffs_settings_payload() {
+        // The start of the settings test bytes from nightlight_settings.rs
+        let data = [0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00];
+        assert_eq!(
+            sniff_protocol(&data),
+            Some(Protocol::CompactBinary(CompactBinaryVersion::V1))
+        );
+    }
+
+    #[test]
+    fn unknown_headers() {
+        assert_eq!(sniff_protocol(&[]), None);
+        assert_eq!(sniff_protocol(&[0x43, 0x42, 0x01]), None);
+        assert_eq!(sniff_protocol(&[0x43, 0x42, 0x03, 0x00]), None);
+        assert_eq!(sniff_protocol(&[0x4D, 0x46, 0x02, 0x00]), None);
+        // Simple JSON (`SJ`) isn't supported
+        assert_eq!(sniff_protocol(&[0x53, 0x4A, 0x01, 0x00]), None);
+    }
+}
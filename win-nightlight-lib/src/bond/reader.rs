@@ -6,32 +6,102 @@ use super::varint::*;
 /// Result of reading a field header: either a field with ID+type, or a struct terminator.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FieldHeader {
-    Field { id: u16, bond_type: BondType },
+    /// A field follows, with the given ID and type
+    Field {
+        /// Field ID
+        id: u16,
+        /// Type of the field's value
+        bond_type: BondType,
+    },
+    /// BT_STOP, the end of a struct
     Stop,
+    /// BT_STOP_BASE, the end of the base-struct part of a struct
     StopBase,
 }
 
-/// Deserializer for Bond CompactBinary v1 payloads.
+/// Limits that keep the readers from exhausting the stack or memory on untrusted data. Each
+/// limit fails reading with its own [BondError] variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// Maximum nesting of structs, lists, sets and maps inside the struct being read
+    pub max_depth: usize,
+    /// Maximum number of list and set elements and map entries in total, counted from the
+    /// container headers as they're read
+    pub max_elements: u64,
+    /// Maximum size in bytes of a single string, or of the values of a single container
+    pub max_allocation: usize,
+}
+
+impl Default for ReadLimits {
+    /// 64 levels, 16 Mi elements and 256 MiB: far beyond any real payload, and well within
+    /// the main thread's stack.
+    fn default() -> Self {
+        Self {
+            max_depth: 64,
+            max_elements: 1 << 24,
+            max_allocation: 1 << 28,
+        }
+    }
+}
+
+/// Deserializer for Bond CompactBinary v1 and v2 payloads.
 pub struct CompactBinaryReader<'a> {
     data: &'a [u8],
     pos: usize,
+    version: CompactBinaryVersion,
+    limits: ReadLimits,
+    depth: usize,
+    elements: u64,
+    /// Where the v2 structs being read end, from their length prefixes
+    struct_ends: Vec<usize>,
 }
 
 impl<'a> CompactBinaryReader<'a> {
+    /// Creates a v1 reader positioned at the start of `data`.
     pub fn new(data: &'a [u8]) -> Self {
-        Self { data, pos: 0 }
+        Self::with_version(data, CompactBinaryVersion::V1)
+    }
+
+    /// Creates a reader for the given protocol version positioned at the start of `data`.
+    pub fn with_version(data: &'a [u8], version: CompactBinaryVersion) -> Self {
+        Self {
+            data,
+            pos: 0,
+            version,
+            limits: ReadLimits::default(),
+            depth: 0,
+            elements: 0,
+            struct_ends: Vec::new(),
+        }
+    }
+
+    /// Returns the limits the reader enforces.
+    pub fn limits(&self) -> ReadLimits {
+        self.limits
+    }
+
+    /// Replaces the default [ReadLimits].
+    pub fn set_limits(&mut self, limits: ReadLimits) {
+        self.limits = limits;
     }
 
+    /// Returns the protocol version being read.
+    pub fn version(&self) -> CompactBinaryVersion {
+        self.version
+    }
+
+    /// Returns the offset of the next byte to be read.
     pub fn position(&self) -> usize {
         self.pos
     }
 
+    /// Returns the number of bytes left after the current position.
     pub fn remaining(&self) -> usize {
         self.data.len().saturating_sub(self.pos)
     }
 
     fn ensure(&self, n: usize) -> Result<(), BondError> {
-        if self.pos + n > self.data.len() {
+        if n > self.remaining() {
             return Err(BondError::UnexpectedEof(self.pos));
         }
         Ok(())
@@ -51,6 +121,41 @@ impl<'a> CompactBinaryReader<'a> {
         Ok(slice)
     }
 
+    /// Runs `read` one nesting level deeper, failing if that exceeds the depth limit.
+    fn nested<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, BondError>,
+    ) -> Result<T, BondError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(BondError::DepthLimitExceeded(self.limits.max_depth));
+        }
+        self.depth += 1;
+        let result = read(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Counts `count` container elements against the element limit.
+    fn count_elements(&mut self, count: u32) -> Result<(), BondError> {
+        self.elements = self.elements.saturating_add(count as u64);
+        if self.elements > self.limits.max_elements {
+            return Err(BondError::ElementLimitExceeded(self.limits.max_elements));
+        }
+        Ok(())
+    }
+
+    /// Fails if `count` values of `size` bytes each exceed the allocation limit.
+    fn check_allocation(&self, count: u32, size: usize) -> Result<(), BondError> {
+        let size = count as u64 * size as u64;
+        if size > self.limits.max_allocation as u64 {
+            return Err(BondError::AllocationLimitExceeded {
+                size,
+                limit: self.limits.max_allocation,
+            });
+        }
+        Ok(())
+    }
+
     /// Reads exactly `n` bytes as a borrowed slice, advancing the cursor.
     pub fn read_bytes_slice(&mut self, n: usize) -> Result<&'a [u8], BondError> {
         self.read_bytes(n)
@@ -58,20 +163,28 @@ impl<'a> CompactBinaryReader<'a> {
 
     // -- Marshaled header --
 
+    /// Reads the 4-byte marshaled header (`CB` magic and version 1 or 2) that precedes a
+    /// marshaled payload, failing with [BondError::InvalidHeader] for anything else.
+    ///
+    /// The rest of the payload is read with the version from the header.
     pub fn read_marshaled_header(&mut self) -> Result<(), BondError> {
         let magic = self.read_bytes(2)?;
         if magic != COMPACT_BINARY_MAGIC {
             return Err(BondError::InvalidHeader);
         }
-        let version = self.read_bytes(2)?;
-        if version != COMPACT_BINARY_V1 {
-            return Err(BondError::InvalidHeader);
-        }
+        self.version = match self.read_bytes(2)? {
+            v if v == COMPACT_BINARY_V1 => CompactBinaryVersion::V1,
+            v if v == COMPACT_BINARY_V2 => CompactBinaryVersion::V2,
+            _ => return Err(BondError::InvalidHeader),
+        };
         Ok(())
     }
 
     // -- Field headers --
 
+    /// Reads a field header: the field's type and ID, or a BT_STOP/BT_STOP_BASE marker.
+    ///
+    /// IDs up to 5 are packed into the type byte; larger IDs follow in one or two bytes.
     pub fn read_field_header(&mut self) -> Result<FieldHeader, BondError> {
         let raw = self.read_byte()?;
 
@@ -108,59 +221,70 @@ impl<'a> CompactBinaryReader<'a> {
 
     // -- Primitive readers --
 
+    /// Reads a bool (one byte, non-zero is `true`).
     pub fn read_bool(&mut self) -> Result<bool, BondError> {
         Ok(self.read_byte()? != 0)
     }
 
+    /// Reads a uint8 (one byte).
     pub fn read_uint8(&mut self) -> Result<u8, BondError> {
         self.read_byte()
     }
 
+    /// Reads an int8 (one byte, two's complement).
     pub fn read_int8(&mut self) -> Result<i8, BondError> {
         Ok(self.read_byte()? as i8)
     }
 
+    /// Reads a uint16 (varint).
     pub fn read_uint16(&mut self) -> Result<u16, BondError> {
         let (val, new_pos) = read_varint(self.data, self.pos)?;
         self.pos = new_pos;
         Ok(val as u16)
     }
 
+    /// Reads an int16 (ZigZag varint).
     pub fn read_int16(&mut self) -> Result<i16, BondError> {
         let (val, new_pos) = read_varint(self.data, self.pos)?;
         self.pos = new_pos;
         Ok(decode_zigzag_i16(val as u16))
     }
 
+    /// Reads a uint32 (varint).
     pub fn read_uint32(&mut self) -> Result<u32, BondError> {
         let (val, new_pos) = read_varint(self.data, self.pos)?;
         self.pos = new_pos;
         Ok(val as u32)
     }
 
+    /// Reads an int32 (ZigZag varint).
     pub fn read_int32(&mut self) -> Result<i32, BondError> {
         let (val, new_pos) = read_varint(self.data, self.pos)?;
         self.pos = new_pos;
         Ok(decode_zigzag_i32(val as u32))
     }
 
+    /// Reads a uint64 (varint).
     pub fn read_uint64(&mut self) -> Result<u64, BondError> {
         let (val, new_pos) = read_varint(self.data, self.pos)?;
         self.pos = new_pos;
         Ok(val)
     }
 
+    /// Reads an int64 (ZigZag varint).
     pub fn read_int64(&mut self) -> Result<i64, BondError> {
         let (val, new_pos) = read_varint(self.data, self.pos)?;
         self.pos = new_pos;
         Ok(decode_zigzag_i64(val))
     }
 
+    /// Reads a float (4 bytes, little-endian IEEE 754).
     pub fn read_float(&mut self) -> Result<f32, BondError> {
         let b = self.read_bytes(4)?;
         Ok(f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
     }
 
+    /// Reads a double (8 bytes, little-endian IEEE 754).
     pub fn read_double(&mut self) -> Result<f64, BondError> {
         let b = self.read_bytes(8)?;
         Ok(f64::from_le_bytes([
@@ -168,14 +292,19 @@ impl<'a> CompactBinaryReader<'a> {
         ]))
     }
 
+    /// Reads a string: a varint byte length followed by UTF-8.
     pub fn read_string(&mut self) -> Result<String, BondError> {
-        let len = self.read_uint32()? as usize;
-        let bytes = self.read_bytes(len)?;
+        let len = self.read_uint32()?;
+        self.check_allocation(len, 1)?;
+        let bytes = self.read_bytes(len as usize)?;
         String::from_utf8(bytes.to_vec()).map_err(|_| BondError::InvalidUtf8)
     }
 
+    /// Reads a wstring: a varint length in UTF-16 code units followed by UTF-16LE.
     pub fn read_wstring(&mut self) -> Result<String, BondError> {
-        let len = self.read_uint32()? as usize; // number of UTF-16 code units
+        let len = self.read_uint32()?; // number of UTF-16 code units
+        self.check_allocation(len, 2)?;
+        let len = len as usize;
         let byte_len = len.checked_mul(2).ok_or(BondError::VarintOverflow)?;
         let bytes = self.read_bytes(byte_len)?;
         let utf16: Vec<u16> = bytes
@@ -188,16 +317,25 @@ impl<'a> CompactBinaryReader<'a> {
     // -- Container headers --
 
     /// Reads a list or set header. Returns (element_type, count).
+    ///
+    /// In v2, counts below 7 are packed into the upper 3 bits of the type byte as count + 1.
+    /// The count is added to the elements read so far, failing past
+    /// [ReadLimits::max_elements].
     pub fn read_container_header(&mut self) -> Result<(BondType, u32), BondError> {
         let raw = self.read_byte()?;
         let type_id = raw & 0x1F;
         let element_type =
             BondType::try_from(type_id).map_err(|_| BondError::InvalidTypeId(type_id))?;
-        let count = self.read_uint32()?;
+        let count = match raw >> 5 {
+            packed @ 1.. if self.version == CompactBinaryVersion::V2 => (packed - 1) as u32,
+            _ => self.read_uint32()?,
+        };
+        self.count_elements(count)?;
         Ok((element_type, count))
     }
 
-    /// Reads a map header. Returns (key_type, value_type, count).
+    /// Reads a map header. Returns (key_type, value_type, count). The count is checked like
+    /// [CompactBinaryReader::read_container_header]'s.
     pub fn read_map_header(&mut self) -> Result<(BondType, BondType, u32), BondError> {
         let key_raw = self.read_byte()?;
         let key_type =
@@ -206,12 +344,14 @@ impl<'a> CompactBinaryReader<'a> {
         let val_type =
             BondType::try_from(val_raw & 0x1F).map_err(|_| BondError::InvalidTypeId(val_raw))?;
         let count = self.read_uint32()?;
+        self.count_elements(count)?;
         Ok((key_type, val_type, count))
     }
 
     // -- Skipping --
 
-    /// Advances past a value of the given Bond type without allocating.
+    /// Advances past a value of the given Bond type without allocating, within the depth and
+    /// element limits.
     pub fn skip_value(&mut self, bond_type: BondType) -> Result<(), BondError> {
         match bond_type {
             BondType::Bool | BondType::UInt8 | BondType::Int8 => {
@@ -242,27 +382,36 @@ impl<'a> CompactBinaryReader<'a> {
                 self.read_bytes(byte_len)?;
             }
             BondType::Struct => {
-                self.skip_struct()?;
+                self.nested(Self::skip_struct)?;
             }
-            BondType::List | BondType::Set => {
-                let (element_type, count) = self.read_container_header()?;
+            BondType::List | BondType::Set => self.nested(|r| {
+                let (element_type, count) = r.read_container_header()?;
                 for _ in 0..count {
-                    self.skip_value(element_type)?;
+                    r.skip_value(element_type)?;
                 }
-            }
-            BondType::Map => {
-                let (key_type, value_type, count) = self.read_map_header()?;
+                Ok(())
+            })?,
+            BondType::Map => self.nested(|r| {
+                let (key_type, value_type, count) = r.read_map_header()?;
                 for _ in 0..count {
-                    self.skip_value(key_type)?;
-                    self.skip_value(value_type)?;
+                    r.skip_value(key_type)?;
+                    r.skip_value(value_type)?;
                 }
-            }
+                Ok(())
+            })?,
         }
         Ok(())
     }
 
     /// Advances past an entire struct (field headers + values) until BT_STOP, without allocating.
+    ///
+    /// In v2 this jumps over the struct using its length prefix, without parsing the fields.
     pub fn skip_struct(&mut self) -> Result<(), BondError> {
+        if self.version == CompactBinaryVersion::V2 {
+            let len = self.read_uint32()? as usize;
+            self.read_bytes(len)?;
+            return Ok(());
+        }
         loop {
             match self.read_field_header()? {
                 FieldHeader::Stop => return Ok(()),
@@ -274,9 +423,36 @@ impl<'a> CompactBinaryReader<'a> {
         }
     }
 
+    // -- Structs --
+
+    /// Starts reading a struct's fields. In v2 this reads the struct's length prefix.
+    pub fn read_struct_begin(&mut self) -> Result<(), BondError> {
+        if self.version == CompactBinaryVersion::V2 {
+            let len = self.read_uint32()? as usize;
+            self.ensure(len)?;
+            self.struct_ends.push(self.pos + len);
+        }
+        Ok(())
+    }
+
+    /// Finishes reading a struct after its BT_STOP. In v2 this checks the struct ended where
+    /// its length prefix said.
+    pub fn read_struct_end(&mut self) -> Result<(), BondError> {
+        if self.version == CompactBinaryVersion::V2
+            && let Some(expected) = self.struct_ends.pop()
+            && expected != self.pos
+        {
+            return Err(BondError::StructLengthMismatch {
+                expected,
+                actual: self.pos,
+            });
+        }
+        Ok(())
+    }
+
     // -- High-level readers --
 
-    /// Reads a single value of the given Bond type.
+    /// Reads a single value of the given Bond type, within the reader's [ReadLimits].
     pub fn read_value(&mut self, bond_type: BondType) -> Result<BondValue, BondError> {
         match bond_type {
             BondType::Bool => Ok(BondValue::Bool(self.read_bool()?)),
@@ -292,35 +468,28 @@ impl<'a> CompactBinaryReader<'a> {
             BondType::Double => Ok(BondValue::Double(self.read_double()?)),
             BondType::String => Ok(BondValue::String(self.read_string()?)),
             BondType::WString => Ok(BondValue::WString(self.read_wstring()?)),
-            BondType::Struct => Ok(BondValue::Struct(self.read_struct()?)),
-            BondType::List => {
-                let (element_type, count) = self.read_container_header()?;
-                let mut elements = Vec::with_capacity((count as usize).min(self.remaining()));
-                for _ in 0..count {
-                    elements.push(self.read_value(element_type)?);
-                }
+            BondType::Struct => Ok(BondValue::Struct(self.nested(Self::read_struct)?)),
+            BondType::List => self.nested(|r| {
+                let (element_type, elements) = r.read_elements()?;
                 Ok(BondValue::List {
                     element_type,
                     elements,
                 })
-            }
-            BondType::Set => {
-                let (element_type, count) = self.read_container_header()?;
-                let mut elements = Vec::with_capacity((count as usize).min(self.remaining()));
-                for _ in 0..count {
-                    elements.push(self.read_value(element_type)?);
-                }
+            }),
+            BondType::Set => self.nested(|r| {
+                let (element_type, elements) = r.read_elements()?;
                 Ok(BondValue::Set {
                     element_type,
                     elements,
                 })
-            }
-            BondType::Map => {
-                let (key_type, value_type, count) = self.read_map_header()?;
-                let mut entries = Vec::with_capacity((count as usize).min(self.remaining()));
+            }),
+            BondType::Map => self.nested(|r| {
+                let (key_type, value_type, count) = r.read_map_header()?;
+                r.check_allocation(count, size_of::<(BondValue, BondValue)>())?;
+                let mut entries = Vec::with_capacity((count as usize).min(r.remaining()));
                 for _ in 0..count {
-                    let k = self.read_value(key_type)?;
-                    let v = self.read_value(value_type)?;
+                    let k = r.read_value(key_type)?;
+                    let v = r.read_value(value_type)?;
                     entries.push((k, v));
                 }
                 Ok(BondValue::Map {
@@ -328,12 +497,24 @@ impl<'a> CompactBinaryReader<'a> {
                     value_type,
                     entries,
                 })
-            }
+            }),
+        }
+    }
+
+    /// Reads a list or set header and its elements.
+    fn read_elements(&mut self) -> Result<(BondType, Vec<BondValue>), BondError> {
+        let (element_type, count) = self.read_container_header()?;
+        self.check_allocation(count, size_of::<BondValue>())?;
+        let mut elements = Vec::with_capacity((count as usize).min(self.remaining()));
+        for _ in 0..count {
+            elements.push(self.read_value(element_type)?);
         }
+        Ok((element_type, elements))
     }
 
     /// Reads all fields of a struct until BT_STOP, returning a BondStruct.
     pub fn read_struct(&mut self) -> Result<BondStruct, BondError> {
+        self.read_struct_begin()?;
         let mut fields = Vec::new();
         loop {
             match self.read_field_header()? {
@@ -345,6 +526,7 @@ impl<'a> CompactBinaryReader<'a> {
                 }
             }
         }
+        self.read_struct_end()?;
         Ok(BondStruct { fields })
     }
 }
@@ -361,6 +543,21 @@ mod tests {
         assert_eq!(reader.remaining(), 0);
     }
 
+    #[test]
+    fn read_marshaled_header_v2() {
+        let data = [0x43, 0x42, 0x02, 0x00];
+        let mut reader = CompactBinaryReader::new(&data);
+        reader.read_marshaled_header().unwrap();
+        assert_eq!(reader.version(), CompactBinaryVersion::V2);
+
+        let data = [0x43, 0x42, 0x03, 0x00];
+        let mut reader = CompactBinaryReader::new(&data);
+        assert!(matches!(
+            reader.read_marshaled_header(),
+            Err(BondError::InvalidHeader)
+        ));
+    }
+
     #[test]
     fn read_marshaled_header_invalid_magic() {
         let data = [0x00, 0x00, 0x01, 0x00];
@@ -576,4 +773,190 @@ mod tests {
         let mut reader = CompactBinaryReader::new(&data);
         assert!(reader.read_struct().is_err());
     }
+
+    #[test]
+    fn v2_packed_container_count() {
+        // Packed count 3, then a varint count of 7
+        let data = [0x8E, 0x01, 0x02, 0x03, 0x0E, 0x07];
+        let mut reader = CompactBinaryReader::with_version(&data, CompactBinaryVersion::V2);
+        assert_eq!(reader.read_container_header().unwrap(), (BondType::Int8, 3));
+        reader.read_bytes_slice(3).unwrap();
+        assert_eq!(reader.read_container_header().unwrap(), (BondType::Int8, 7));
+
+        // v1 ignores the upper bits
+        let mut reader = CompactBinaryReader::new(&[0x8E, 0x05]);
+        assert_eq!(reader.read_container_header().unwrap(), (BondType::Int8, 5));
+    }
+
+    #[test]
+    fn v2_skip_struct_uses_length() {
+        // Length 3, then bytes that aren't valid fields, then a trailing bool field
+        let data = [0x03, 0xFF, 0xFF, 0xFF, 0x02, 0x01];
+        let mut reader = CompactBinaryReader::with_version(&data, CompactBinaryVersion::V2);
+        reader.skip_struct().unwrap();
+        assert_eq!(reader.position(), 4);
+
+        let mut reader = CompactBinaryReader::new(&data);
+        assert!(reader.skip_struct().is_err());
+    }
+
+    #[test]
+    fn v2_struct_length_exceeds_data() {
+        let data = [0x05, 0x00];
+        let mut reader = CompactBinaryReader::with_version(&data, CompactBinaryVersion::V2);
+        assert!(matches!(
+            reader.read_struct(),
+            Err(BondError::UnexpectedEof(1))
+        ));
+        let mut reader = CompactBinaryReader::with_version(&data, CompactBinaryVersion::V2);
+        assert!(matches!(
+            reader.skip_struct(),
+            Err(BondError::UnexpectedEof(1))
+        ));
+    }
+
+    #[test]
+    fn v2_struct_length_must_match_stop() {
+        // Length 4 around a bool field and BT_STOP that take 3 bytes
+        let data = [0x04, 0x02, 0x01, 0x00, 0x00];
+        let mut reader = CompactBinaryReader::with_version(&data, CompactBinaryVersion::V2);
+        assert!(matches!(
+            reader.read_struct(),
+            Err(BondError::StructLengthMismatch {
+                expected: 5,
+                actual: 4
+            })
+        ));
+
+        // Nested: the inner struct's length is off by one
+        let data = [0x06, 0x0A, 0x02, 0x00, 0x00, 0x00, 0x00];
+        let mut reader = CompactBinaryReader::with_version(&data, CompactBinaryVersion::V2);
+        assert!(matches!(
+            reader.read_struct(),
+            Err(BondError::StructLengthMismatch {
+                expected: 5,
+                actual: 4
+            })
+        ));
+
+        let data = [0x04, 0x0A, 0x01, 0x00, 0x00];
+        let mut reader = CompactBinaryReader::with_version(&data, CompactBinaryVersion::V2);
+        assert_eq!(reader.read_struct().unwrap().fields.len(), 1);
+    }
+
+    /// `levels` nested struct fields, each field 0 of the one around it.
+    fn nested_structs(levels: usize) -> Vec<u8> {
+        let mut data = vec![0x0A; levels];
+        data.extend(vec![0x00; levels + 1]);
+        data
+    }
+
+    #[test]
+    fn depth_limit() {
+        let data = nested_structs(64);
+        CompactBinaryReader::new(&data).read_struct().unwrap();
+        CompactBinaryReader::new(&data).skip_struct().unwrap();
+
+        // Deep enough to overflow the stack without the limit
+        let data = nested_structs(1_000_000);
+        assert!(matches!(
+            CompactBinaryReader::new(&data).read_struct(),
+            Err(BondError::DepthLimitExceeded(64))
+        ));
+        assert!(matches!(
+            CompactBinaryReader::new(&data).skip_struct(),
+            Err(BondError::DepthLimitExceeded(64))
+        ));
+
+        // Lists of lists count too
+        let mut data = vec![0x0B];
+        data.extend([0x0B, 0x01].repeat(100));
+        let mut reader = CompactBinaryReader::new(&data);
+        reader.set_limits(ReadLimits {
+            max_depth: 8,
+            ..ReadLimits::default()
+        });
+        assert!(matches!(
+            reader.read_struct(),
+            Err(BondError::DepthLimitExceeded(8))
+        ));
+    }
+
+    #[test]
+    fn element_limit() {
+        let limits = ReadLimits {
+            max_elements: 5,
+            ..ReadLimits::default()
+        };
+        // Two lists of 3 int8s: 6 elements in total
+        let data = [0x0B, 0x0E, 0x03, 1, 2, 3, 0x2B, 0x0E, 0x03, 4, 5, 6, 0x00];
+        let mut reader = CompactBinaryReader::new(&data);
+        reader.set_limits(limits);
+        assert!(matches!(
+            reader.read_struct(),
+            Err(BondError::ElementLimitExceeded(5))
+        ));
+        let mut reader = CompactBinaryReader::new(&data);
+        reader.set_limits(limits);
+        assert!(matches!(
+            reader.skip_struct(),
+            Err(BondError::ElementLimitExceeded(5))
+        ));
+
+        // A map of 6 entries, checked before reading any of them
+        let mut reader = CompactBinaryReader::new(&[0x12, 0x12, 0x06]);
+        reader.set_limits(limits);
+        assert!(matches!(
+            reader.read_map_header(),
+            Err(BondError::ElementLimitExceeded(5))
+        ));
+    }
+
+    #[test]
+    fn allocation_limit() {
+        let limits = ReadLimits {
+            max_allocation: 4,
+            ..ReadLimits::default()
+        };
+        let mut reader = CompactBinaryReader::new(b"\x05hello");
+        reader.set_limits(limits);
+        assert!(matches!(
+            reader.read_string(),
+            Err(BondError::AllocationLimitExceeded { size: 5, limit: 4 })
+        ));
+        let mut reader = CompactBinaryReader::new(&[0x03, b'a', 0, b'b', 0, b'c', 0]);
+        reader.set_limits(limits);
+        assert!(matches!(
+            reader.read_wstring(),
+            Err(BondError::AllocationLimitExceeded { size: 6, limit: 4 })
+        ));
+
+        // Containers are sized by their values
+        let mut reader = CompactBinaryReader::new(&[0x0E, 0x01, 0x07]);
+        reader.set_limits(limits);
+        assert!(matches!(
+            reader.read_value(BondType::List),
+            Err(BondError::AllocationLimitExceeded { size, limit: 4 })
+                if size == size_of::<BondValue>() as u64
+        ));
+
+        // Skipping allocates nothing
+        let mut reader = CompactBinaryReader::new(b"\x05hello");
+        reader.set_limits(limits);
+        reader.skip_value(BondType::String).unwrap();
+    }
+
+    #[test]
+    fn huge_counts_fail_without_reading() {
+        // A list claiming u32::MAX lists
+        let data = [0x0B, 0x0B, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+        assert!(matches!(
+            CompactBinaryReader::new(&data).read_struct(),
+            Err(BondError::ElementLimitExceeded(_))
+        ));
+        assert!(matches!(
+            CompactBinaryReader::new(&data).skip_struct(),
+            Err(BondError::ElementLimitExceeded(_))
+        ));
+    }
 }
@@ -0,0 +1,266 @@
+use super::BondError;
+use super::reader::CompactBinaryReader;
+use super::types::BondType;
+use super::value::BondStruct;
+use super::writer::CompactBinaryWriter;
+
+/// A struct with a fixed Bond schema, read and written field by field.
+///
+/// Usually derived with `#[derive(BondStruct)]`, see [derive@super::BondStruct] for the field
+/// attributes. `'de` is the lifetime of the input, for fields that borrow from it.
+pub trait BondSchema<'de>: Sized {
+    /// Reads the struct's fields up to and including BT_STOP.
+    fn read_fields(reader: &mut CompactBinaryReader<'de>) -> Result<Self, BondError>;
+
+    /// Writes the struct's fields in ascending ID order, followed by BT_STOP.
+    fn write_fields(&self, writer: &mut CompactBinaryWriter);
+}
+
+/// A schema-less struct, so preserved values can be used as `struct` fields.
+impl<'de> BondSchema<'de> for BondStruct {
+    fn read_fields(reader: &mut CompactBinaryReader<'de>) -> Result<Self, BondError> {
+        reader.read_struct()
+    }
+
+    fn write_fields(&self, writer: &mut CompactBinaryWriter) {
+        writer.write_struct(self);
+    }
+}
+
+/// Reads the value of `list<int8>` field `field_id` as a slice borrowed from the input, since
+/// int8 elements are stored as contiguous raw bytes.
+pub fn read_int8_list<'de>(
+    reader: &mut CompactBinaryReader<'de>,
+    field_id: u16,
+) -> Result<&'de [u8], BondError> {
+    let (element_type, count) = reader.read_container_header()?;
+    if element_type != BondType::Int8 {
+        return Err(BondError::UnexpectedFieldType(field_id));
+    }
+    reader.read_bytes_slice(count as usize)
+}
+
+/// Writes a `list<int8>` value from raw bytes.
+pub fn write_int8_list(writer: &mut CompactBinaryWriter, bytes: &[u8]) {
+    writer.write_container_header(BondType::Int8, bytes.len() as u32);
+    writer.write_raw_bytes(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bond::{BondStruct, BondValue, CompactBinaryVersion};
+
+    #[derive(BondStruct, Debug, Default, PartialEq)]
+    struct Inner {
+        #[bond(id = 0, ty = "int8", omit_default)]
+        a: i8,
+        #[bond(id = 1, ty = "wstring")]
+        name: String,
+    }
+
+    #[derive(BondStruct, Debug, Default, PartialEq)]
+    struct Outer<'a> {
+        #[bond(id = 0, ty = "int32", presence)]
+        flag: bool,
+        #[bond(id = 3, ty = "uint64")]
+        count: u64,
+        #[bond(id = 7, ty = "string")]
+        label: Option<String>,
+        #[bond(id = 20, ty = "struct")]
+        inner: Inner,
+        #[bond(id = 300, ty = "list<int8>")]
+        payload: Option<&'a [u8]>,
+        #[bond(unknown)]
+        unknown_fields: BondStruct,
+    }
+
+    fn to_bytes<'a>(value: &impl BondSchema<'a>) -> Vec<u8> {
+        let mut w = CompactBinaryWriter::new();
+        value.write_fields(&mut w);
+        w.into_bytes()
+    }
+
+    #[test]
+    fn writes_fields_in_id_order() {
+        let outer = Outer {
+            flag: true,
+            count: 42,
+            label: Some("hi".into()),
+            inner: Inner {
+                a: 0,
+                name: "x".into(),
+            },
+            payload: Some(&[1, 2]),
+            unknown_fields: BondStruct::new(),
+        };
+        assert_eq!(
+            to_bytes(&outer),
+            [
+                0x10, 0x00, // field 0, int32 = 0 (presence)
+                0x66, 0x2A, // field 3, uint64 = 42
+                0xC9, 0x07, 0x02, 0x68, 0x69, // field 7, string = "hi"
+                0xCA, 0x14, // field 20, struct
+                0x32, 0x01, 0x78, 0x00, // field 1, wstring = "x" (field 0 omitted)
+                0x00, // inner BT_STOP
+                0xEB, 0x2C, 0x01, 0x0E, 0x02, 0x01, 0x02, // field 300, list<int8> = [1, 2]
+                0x00, // BT_STOP
+            ]
+        );
+    }
+
+    #[test]
+    fn absent_fields_take_defaults() {
+        let bytes = to_bytes(&Outer::default());
+        // Only the non-optional count and inner struct are written
+        assert_eq!(bytes, [0x66, 0x00, 0xCA, 0x14, 0x32, 0x00, 0x00, 0x00]);
+
+        let mut r = CompactBinaryReader::new(&[0x00]);
+        assert_eq!(Outer::read_fields(&mut r).unwrap(), Outer::default());
+    }
+
+    #[test]
+    fn roundtrip_borrows_list() {
+        let outer = Outer {
+            flag: false,
+            count: 7,
+            label: None,
+            inner: Inner {
+                a: -3,
+                name: "nuit".into(),
+            },
+            payload: Some(&[0x43, 0x42, 0x01, 0x00]),
+            unknown_fields: BondStruct::new(),
+        };
+        let bytes = to_bytes(&outer);
+        let mut r = CompactBinaryReader::new(&bytes);
+        let decoded = Outer::read_fields(&mut r).unwrap();
+        assert_eq!(r.remaining(), 0);
+        assert_eq!(decoded, outer);
+    }
+
+    #[test]
+    fn unknown_fields_are_captured_and_interleaved() {
+        let mut w = CompactBinaryWriter::new();
+        w.write_field(1, &BondValue::Bool(true));
+        // Known ID with an unexpected type
+        w.write_field(3, &BondValue::Int32(-1));
+        w.write_field(10, &BondValue::UInt8(9));
+        // The inner struct as Inner::default() writes it
+        let inner = BondStruct {
+            fields: vec![(1, BondValue::WString(String::new()))],
+        };
+        w.write_field(20, &BondValue::Struct(inner));
+        w.write_field(400, &BondValue::String("end".into()));
+        w.write_stop();
+        let bytes = w.into_bytes();
+
+        let mut r = CompactBinaryReader::new(&bytes);
+        let outer = Outer::read_fields(&mut r).unwrap();
+        assert_eq!(outer.count, 0);
+        assert_eq!(
+            outer.unknown_fields.fields,
+            vec![
+                (1, BondValue::Bool(true)),
+                (3, BondValue::Int32(-1)),
+                (10, BondValue::UInt8(9)),
+                (400, BondValue::String("end".into())),
+            ]
+        );
+
+        let written = to_bytes(&outer);
+        let types: Vec<_> = CompactBinaryReader::new(&written)
+            .read_struct()
+            .unwrap()
+            .fields
+            .into_iter()
+            .map(|(id, value)| (id, value.bond_type()))
+            .collect();
+        // Field 3 is written once, with its original type
+        assert_eq!(
+            types,
+            [
+                (1, BondType::Bool),
+                (3, BondType::Int32),
+                (10, BondType::UInt8),
+                (20, BondType::Struct),
+                (400, BondType::String),
+            ]
+        );
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn unknown_fields_are_skipped_without_capture() {
+        let mut w = CompactBinaryWriter::new();
+        w.write_field(0, &BondValue::Int8(5));
+        w.write_field(2, &BondValue::Struct(BondStruct::new()));
+        w.write_stop();
+        let bytes = w.into_bytes();
+
+        let mut r = CompactBinaryReader::new(&bytes);
+        let inner = Inner::read_fields(&mut r).unwrap();
+        assert_eq!(r.remaining(), 0);
+        assert_eq!(
+            inner,
+            Inner {
+                a: 5,
+                name: String::new()
+            }
+        );
+    }
+
+    #[test]
+    fn list_with_wrong_element_type() {
+        let mut w = CompactBinaryWriter::new();
+        w.write_field_header(300, BondType::List);
+        w.write_container_header(BondType::Int32, 0);
+        w.write_stop();
+        let bytes = w.into_bytes();
+
+        let mut r = CompactBinaryReader::new(&bytes);
+        assert!(matches!(
+            Outer::read_fields(&mut r),
+            Err(BondError::UnexpectedFieldType(300))
+        ));
+    }
+
+    #[test]
+    fn v2_roundtrip_matches_bond_struct() {
+        let outer = Outer {
+            flag: true,
+            count: 7,
+            label: Some("v2".into()),
+            inner: Inner {
+                a: 1,
+                name: "nuit".into(),
+            },
+            payload: Some(&[1, 2, 3]),
+            unknown_fields: BondStruct::new(),
+        };
+        let mut w = CompactBinaryWriter::with_version(CompactBinaryVersion::V2);
+        outer.write_fields(&mut w);
+        let bytes = w.into_bytes();
+
+        let mut r = CompactBinaryReader::with_version(&bytes, CompactBinaryVersion::V2);
+        assert_eq!(Outer::read_fields(&mut r).unwrap(), outer);
+        assert_eq!(r.remaining(), 0);
+
+        // A length prefix that disagrees with BT_STOP is rejected
+        let mut short = bytes.clone();
+        short[0] -= 1;
+        let mut r = CompactBinaryReader::with_version(&short, CompactBinaryVersion::V2);
+        assert!(matches!(
+            Outer::read_fields(&mut r),
+            Err(BondError::StructLengthMismatch { .. })
+        ));
+
+        // The derived writer nests length prefixes the same way as the generic one
+        let generic = CompactBinaryReader::new(&to_bytes(&outer))
+            .read_struct()
+            .unwrap();
+        let mut w = CompactBinaryWriter::with_version(CompactBinaryVersion::V2);
+        w.write_struct(&generic);
+        assert_eq!(w.into_bytes(), bytes);
+    }
+}
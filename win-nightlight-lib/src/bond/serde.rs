@@ -0,0 +1,1019 @@
+use std::fmt::Display;
+use std::vec;
+
+use ::serde::de::value::{BorrowedStrDeserializer, U16Deserializer, U32Deserializer};
+use ::serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use ::serde::ser::{self, Impossible, Serialize};
+use thiserror::Error;
+
+use super::BondError;
+use super::reader::CompactBinaryReader;
+use super::types::BondType;
+use super::value::{BondStruct, BondValue};
+use super::writer::CompactBinaryWriter;
+
+/// Errors from converting between serde types and Bond.
+#[derive(Error, Debug)]
+pub enum SerdeError {
+    /// The Bond data couldn't be read.
+    #[error(transparent)]
+    Bond(#[from] BondError),
+    /// A custom error from a `Serialize` or `Deserialize` implementation.
+    #[error("{0}")]
+    Message(String),
+    /// The value has no Bond representation, e.g. `None` in a list or an enum variant with data.
+    #[error("{0} can't be represented in Bond")]
+    Unsupported(&'static str),
+    /// A sequence or map mixes element types.
+    #[error("Bond containers need a single element type, found {0:?} and {1:?}")]
+    MixedElementTypes(BondType, BondType),
+    /// `to_vec` was given something other than a struct.
+    #[error("Only structs can be serialized as a top-level Bond payload")]
+    NotAStruct,
+    /// Some fields of a struct have numeric renames and others don't.
+    #[error("Either every field of a struct or none of them needs a numeric rename")]
+    MixedFieldIds,
+    /// Two fields of a struct map to the same field ID.
+    #[error("Field ID {0} is used more than once")]
+    DuplicateFieldId(u16),
+}
+
+impl ser::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
+}
+
+impl de::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
+}
+
+/// Returns the Bond field ID of a struct field: its name if that's a number (e.g. with
+/// `#[serde(rename = "40")]`), otherwise its declaration index.
+///
+/// A struct either renames all its fields or none of them. Fields with `skip_serializing` are
+/// invisible when serializing and would shift the indices, so structs with them need renames.
+fn field_id(name: &str, index: usize) -> u16 {
+    name.parse().unwrap_or(index as u16)
+}
+
+/// Checks that either all or none of `names` are numeric, like [field_id] expects.
+fn check_numbered(names: &[&str]) -> Result<(), SerdeError> {
+    let numbered = names
+        .iter()
+        .filter(|name| name.parse::<u16>().is_ok())
+        .count();
+    if numbered == 0 || numbered == names.len() {
+        Ok(())
+    } else {
+        Err(SerdeError::MixedFieldIds)
+    }
+}
+
+/// Converts a value into a [BondValue].
+///
+/// Rust types map to the Bond type of the same width and signedness, `f32`/`f64` to
+/// float/double, strings and `char` to string, byte buffers to `list<int8>`, sequences to
+/// lists, maps to maps, structs to structs, tuples to structs with field IDs 0, 1, ... and
+/// unit enum variants to their index as int32, like Bond enums. `None` struct fields are
+/// omitted. Empty sequences and maps have no elements to take a type from and are written
+/// as containers of int8.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<BondValue, SerdeError> {
+    required(value.serialize(ValueSerializer)?)
+}
+
+/// Converts a [BondValue] into a value, the reverse of [to_value]. Struct fields whose ID
+/// doesn't match a Rust field are ignored, and missing fields are handled by serde (`None`
+/// for an `Option`, otherwise an error unless the field has `#[serde(default)]`).
+pub fn from_value<T: DeserializeOwned>(value: BondValue) -> Result<T, SerdeError> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+/// Serializes a struct as a marshaled CompactBinary v1 payload, with the same `CB 01 00`
+/// header as the inner payload of a CloudStore blob. See [to_value] for the type mapping.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, SerdeError> {
+    let BondValue::Struct(s) = to_value(value)? else {
+        return Err(SerdeError::NotAStruct);
+    };
+    let mut writer = CompactBinaryWriter::new();
+    writer.write_marshaled_header();
+    writer.write_struct(&s);
+    Ok(writer.into_bytes())
+}
+
+/// Deserializes a marshaled CompactBinary v1 payload, the reverse of [to_vec].
+pub fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, SerdeError> {
+    let mut reader = CompactBinaryReader::new(data);
+    reader.read_marshaled_header()?;
+    from_value(BondValue::Struct(reader.read_struct()?))
+}
+
+/// Serializes into a [BondValue], or `None` for an absent optional value.
+struct ValueSerializer;
+
+fn required(value: Option<BondValue>) -> Result<BondValue, SerdeError> {
+    value.ok_or(SerdeError::Unsupported("None outside a struct field"))
+}
+
+/// Records the element type of a container, checking that all elements share it.
+fn check_element_type(
+    element_type: &mut Option<BondType>,
+    value: &BondValue,
+) -> Result<(), SerdeError> {
+    let found = value.bond_type();
+    match *element_type {
+        None => *element_type = Some(found),
+        Some(expected) if expected != found => {
+            return Err(SerdeError::MixedElementTypes(expected, found));
+        }
+        Some(_) => {}
+    }
+    Ok(())
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<BondValue>;
+    type Error = SerdeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = TupleSerializer;
+    type SerializeTupleStruct = TupleSerializer;
+    type SerializeTupleVariant = Impossible<Option<BondValue>, SerdeError>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = Impossible<Option<BondValue>, SerdeError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Int8(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Int16(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Int32(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Int64(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::UInt8(v)))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::UInt16(v)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::UInt32(v)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::UInt64(v)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Float(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Double(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::String(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::String(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::List {
+            element_type: BondType::Int8,
+            elements: v.iter().map(|b| BondValue::Int8(*b as i8)).collect(),
+        }))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, SerdeError> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Struct(BondStruct::new())))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, SerdeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Int32(variant_index as i32)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, SerdeError> {
+        Err(SerdeError::Unsupported("An enum variant with data"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, SerdeError> {
+        Ok(SeqSerializer {
+            element_type: None,
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<TupleSerializer, SerdeError> {
+        Ok(TupleSerializer {
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<TupleSerializer, SerdeError> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, SerdeError> {
+        Err(SerdeError::Unsupported("An enum variant with data"))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, SerdeError> {
+        Ok(MapSerializer {
+            key_type: None,
+            value_type: None,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<StructSerializer, SerdeError> {
+        Ok(StructSerializer {
+            fields: Vec::with_capacity(len),
+            index: 0,
+            numbered: None,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, SerdeError> {
+        Err(SerdeError::Unsupported("An enum variant with data"))
+    }
+}
+
+struct SeqSerializer {
+    element_type: Option<BondType>,
+    elements: Vec<BondValue>,
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Option<BondValue>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let value = required(value.serialize(ValueSerializer)?)?;
+        check_element_type(&mut self.element_type, &value)?;
+        self.elements.push(value);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::List {
+            element_type: self.element_type.unwrap_or(BondType::Int8),
+            elements: self.elements,
+        }))
+    }
+}
+
+/// Serializes tuples as structs with field IDs by position.
+struct TupleSerializer {
+    fields: Vec<(u16, BondValue)>,
+}
+
+impl ser::SerializeTuple for TupleSerializer {
+    type Ok = Option<BondValue>;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let id = self.fields.len() as u16;
+        self.fields
+            .push((id, required(value.serialize(ValueSerializer)?)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Struct(BondStruct {
+            fields: self.fields,
+        })))
+    }
+}
+
+impl ser::SerializeTupleStruct for TupleSerializer {
+    type Ok = Option<BondValue>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
+struct MapSerializer {
+    key_type: Option<BondType>,
+    value_type: Option<BondType>,
+    entries: Vec<(BondValue, BondValue)>,
+    key: Option<BondValue>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Option<BondValue>;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let key = required(key.serialize(ValueSerializer)?)?;
+        check_element_type(&mut self.key_type, &key)?;
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerdeError::Message("map value without a key".into()))?;
+        let value = required(value.serialize(ValueSerializer)?)?;
+        check_element_type(&mut self.value_type, &value)?;
+        self.entries.push((key, value));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, SerdeError> {
+        Ok(Some(BondValue::Map {
+            key_type: self.key_type.unwrap_or(BondType::Int8),
+            value_type: self.value_type.unwrap_or(BondType::Int8),
+            entries: self.entries,
+        }))
+    }
+}
+
+struct StructSerializer {
+    fields: Vec<(u16, BondValue)>,
+    /// Declaration index of the next field, counting skipped ones
+    index: usize,
+    /// Whether the fields so far had numeric names, once there was one
+    numbered: Option<bool>,
+}
+
+impl StructSerializer {
+    /// Returns the ID of the next field, checking it's named like the ones before.
+    fn next_id(&mut self, key: &str) -> Result<u16, SerdeError> {
+        let numbered = key.parse::<u16>().is_ok();
+        if *self.numbered.get_or_insert(numbered) != numbered {
+            return Err(SerdeError::MixedFieldIds);
+        }
+        let id = field_id(key, self.index);
+        self.index += 1;
+        Ok(id)
+    }
+}
+
+impl ser::SerializeStruct for StructSerializer {
+    type Ok = Option<BondValue>;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        let id = self.next_id(key)?;
+        if let Some(value) = value.serialize(ValueSerializer)? {
+            self.fields.push((id, value));
+        }
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), SerdeError> {
+        self.next_id(key)?;
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<Self::Ok, SerdeError> {
+        self.fields.sort_by_key(|(id, _)| *id);
+        if let Some(pair) = self.fields.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(SerdeError::DuplicateFieldId(pair[0].0));
+        }
+        Ok(Some(BondValue::Struct(BondStruct {
+            fields: self.fields,
+        })))
+    }
+}
+
+struct ValueDeserializer(BondValue);
+
+impl<'de> IntoDeserializer<'de, SerdeError> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            BondValue::Bool(v) => visitor.visit_bool(v),
+            BondValue::UInt8(v) => visitor.visit_u8(v),
+            BondValue::UInt16(v) => visitor.visit_u16(v),
+            BondValue::UInt32(v) => visitor.visit_u32(v),
+            BondValue::UInt64(v) => visitor.visit_u64(v),
+            BondValue::Int8(v) => visitor.visit_i8(v),
+            BondValue::Int16(v) => visitor.visit_i16(v),
+            BondValue::Int32(v) => visitor.visit_i32(v),
+            BondValue::Int64(v) => visitor.visit_i64(v),
+            BondValue::Float(v) => visitor.visit_f32(v),
+            BondValue::Double(v) => visitor.visit_f64(v),
+            BondValue::String(v) | BondValue::WString(v) => visitor.visit_string(v),
+            // Without field names, struct keys are the numeric field IDs
+            BondValue::Struct(s) => visitor.visit_map(StructAccess {
+                fields: s.fields.into_iter(),
+                names: None,
+                value: None,
+            }),
+            BondValue::List { elements, .. } | BondValue::Set { elements, .. } => {
+                visitor.visit_seq(SeqAccess(elements.into_iter()))
+            }
+            BondValue::Map { entries, .. } => visitor.visit_map(MapAccess {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            BondValue::Struct(_) => visitor.visit_unit(),
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            BondValue::List { elements, .. }
+                if elements.iter().all(|e| matches!(e, BondValue::Int8(_))) =>
+            {
+                let bytes = elements
+                    .into_iter()
+                    .map(|e| match e {
+                        BondValue::Int8(b) => b as u8,
+                        _ => unreachable!("checked above"),
+                    })
+                    .collect();
+                visitor.visit_byte_buf(bytes)
+            }
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            BondValue::Struct(s) => {
+                let mut fields = s.fields;
+                fields.sort_by_key(|(id, _)| *id);
+                let elements: Vec<_> = fields.into_iter().map(|(_, v)| v).collect();
+                visitor.visit_seq(SeqAccess(elements.into_iter()))
+            }
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        check_numbered(fields)?;
+        match self.0 {
+            BondValue::Struct(s) => visitor.visit_map(StructAccess {
+                fields: s.fields.into_iter(),
+                names: Some(fields),
+                value: None,
+            }),
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.0 {
+            BondValue::Int32(index) => visitor.visit_enum(UnitVariantAccess(index as u32)),
+            BondValue::String(name) | BondValue::WString(name) => {
+                visitor.visit_enum(name.into_deserializer())
+            }
+            other => ValueDeserializer(other).deserialize_any(visitor),
+        }
+    }
+
+    ::serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq map identifier ignored_any
+    }
+}
+
+struct SeqAccess(vec::IntoIter<BondValue>);
+
+impl<'de> de::SeqAccess<'de> for SeqAccess {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        self.0
+            .next()
+            .map(|v| seed.deserialize(ValueDeserializer(v)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapAccess {
+    entries: vec::IntoIter<(BondValue, BondValue)>,
+    value: Option<BondValue>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(ValueDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| SerdeError::Message("map value without a key".into()))?;
+        seed.deserialize(ValueDeserializer(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// Struct fields as a map, keyed by the Rust field names (skipping unmatched IDs) when `names`
+/// is given, otherwise by field ID.
+struct StructAccess {
+    fields: vec::IntoIter<(u16, BondValue)>,
+    names: Option<&'static [&'static str]>,
+    value: Option<BondValue>,
+}
+
+impl<'de> de::MapAccess<'de> for StructAccess {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        for (id, value) in self.fields.by_ref() {
+            let Some(names) = self.names else {
+                self.value = Some(value);
+                return seed
+                    .deserialize(U16Deserializer::<SerdeError>::new(id))
+                    .map(Some);
+            };
+            let name = names
+                .iter()
+                .enumerate()
+                .find(|(index, name)| field_id(name, *index) == id);
+            if let Some((_, name)) = name {
+                self.value = Some(value);
+                return seed
+                    .deserialize(BorrowedStrDeserializer::new(name))
+                    .map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| SerdeError::Message("struct value without a field".into()))?;
+        seed.deserialize(ValueDeserializer(value))
+    }
+}
+
+/// A unit enum variant given by its index.
+struct UnitVariantAccess(u32);
+
+impl<'de> de::EnumAccess<'de> for UnitVariantAccess {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), SerdeError> {
+        let variant = seed.deserialize(U32Deserializer::<SerdeError>::new(self.0))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for UnitVariantAccess {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        _seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        Err(SerdeError::Unsupported("An enum variant with data"))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        Err(SerdeError::Unsupported("An enum variant with data"))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        Err(SerdeError::Unsupported("An enum variant with data"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ::serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct TimeBlock {
+        #[serde(rename = "0")]
+        hour: Option<i8>,
+        #[serde(rename = "1")]
+        minute: Option<i8>,
+    }
+
+    /// The night light settings payload, with field IDs from renames.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Settings {
+        #[serde(rename = "0")]
+        schedule_enabled: Option<bool>,
+        #[serde(rename = "10")]
+        set_hours_mode: Option<bool>,
+        #[serde(rename = "20")]
+        start: TimeBlock,
+        #[serde(rename = "30")]
+        end: TimeBlock,
+        #[serde(rename = "40")]
+        color_temperature: i16,
+        #[serde(rename = "50")]
+        sunset: TimeBlock,
+        #[serde(rename = "60")]
+        sunrise: TimeBlock,
+    }
+
+    // The inner payload of the settings test bytes from nightlight_settings.rs
+    const SETTINGS_PAYLOAD: [u8; 38] = [
+        0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14, 0x0E, 0x01, 0x2E, 0x0F,
+        0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xCA, 0x32, 0x0E, 0x13, 0x2E, 0x17, 0x00,
+        0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00,
+    ];
+
+    fn time(hour: i8, minute: i8) -> TimeBlock {
+        let non_zero = |v| (v != 0).then_some(v);
+        TimeBlock {
+            hour: non_zero(hour),
+            minute: non_zero(minute),
+        }
+    }
+
+    #[test]
+    fn settings_payload_bytes() {
+        let settings = Settings {
+            schedule_enabled: Some(true),
+            set_hours_mode: Some(false),
+            start: time(1, 15),
+            end: time(0, 0),
+            color_temperature: 2790,
+            sunset: time(19, 23),
+            sunrise: time(7, 12),
+        };
+        assert_eq!(to_vec(&settings).unwrap(), SETTINGS_PAYLOAD);
+        assert_eq!(from_slice::<Settings>(&SETTINGS_PAYLOAD).unwrap(), settings);
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+    enum Mode {
+        Off,
+        Auto,
+    }
+
+    /// Field IDs from declaration order, with one skipped when `None`.
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Mixed {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+        mode: Mode,
+        levels: Vec<u32>,
+        weights: BTreeMap<String, f64>,
+        pair: (i64, bool),
+        letter: char,
+    }
+
+    #[test]
+    fn type_mapping_matches_bond_value() {
+        let mixed = Mixed {
+            name: "nuit".into(),
+            note: None,
+            mode: Mode::Auto,
+            levels: vec![1, 300],
+            weights: BTreeMap::from([("a".to_string(), 0.5)]),
+            pair: (-2, true),
+            letter: 'x',
+        };
+        let expected = BondValue::Struct(BondStruct {
+            fields: vec![
+                (0, BondValue::String("nuit".into())),
+                (2, BondValue::Int32(1)),
+                (
+                    3,
+                    BondValue::List {
+                        element_type: BondType::UInt32,
+                        elements: vec![BondValue::UInt32(1), BondValue::UInt32(300)],
+                    },
+                ),
+                (
+                    4,
+                    BondValue::Map {
+                        key_type: BondType::String,
+                        value_type: BondType::Double,
+                        entries: vec![(BondValue::String("a".into()), BondValue::Double(0.5))],
+                    },
+                ),
+                (
+                    5,
+                    BondValue::Struct(BondStruct {
+                        fields: vec![(0, BondValue::Int64(-2)), (1, BondValue::Bool(true))],
+                    }),
+                ),
+                (6, BondValue::String("x".into())),
+            ],
+        });
+        assert_eq!(to_value(&mixed).unwrap(), expected);
+        assert_eq!(from_value::<Mixed>(expected.clone()).unwrap(), mixed);
+
+        // to_vec writes the same bytes as the BondValue
+        let mut writer = CompactBinaryWriter::new();
+        writer.write_marshaled_header();
+        writer.write_value(&expected);
+        assert_eq!(to_vec(&mixed).unwrap(), writer.into_bytes());
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored() {
+        let mut value = to_value(&time(7, 12)).unwrap();
+        if let BondValue::Struct(s) = &mut value {
+            s.push(9, BondValue::WString("extra".into()));
+        }
+        assert_eq!(from_value::<TimeBlock>(value).unwrap(), time(7, 12));
+    }
+
+    #[test]
+    fn bytes_are_int8_lists() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Blob {
+            #[serde(with = "bytes")]
+            data: Vec<u8>,
+        }
+
+        mod bytes {
+            use ::serde::{Deserializer, Serializer};
+
+            pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+                s.serialize_bytes(v)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+                struct BytesVisitor;
+                impl ::serde::de::Visitor<'_> for BytesVisitor {
+                    type Value = Vec<u8>;
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("bytes")
+                    }
+                    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+                        Ok(v)
+                    }
+                }
+                d.deserialize_byte_buf(BytesVisitor)
+            }
+        }
+
+        let blob = Blob {
+            data: vec![0x43, 0x80, 0xFF],
+        };
+        let bytes = to_vec(&blob).unwrap();
+        assert_eq!(
+            bytes,
+            [
+                0x43, 0x42, 0x01, 0x00, 0x0B, 0x0E, 0x03, 0x43, 0x80, 0xFF, 0x00
+            ]
+        );
+        assert_eq!(from_slice::<Blob>(&bytes).unwrap(), blob);
+    }
+
+    #[test]
+    fn unsupported_values() {
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum Untagged {
+            Number(u8),
+            Text(&'static str),
+        }
+        #[derive(Serialize)]
+        enum WithData {
+            Value(u8),
+        }
+
+        assert!(matches!(to_vec(&5u8), Err(SerdeError::NotAStruct)));
+        assert!(matches!(
+            to_value(&vec![Untagged::Number(1), Untagged::Text("x")]),
+            Err(SerdeError::MixedElementTypes(
+                BondType::UInt8,
+                BondType::String
+            ))
+        ));
+        assert!(matches!(
+            to_value(&WithData::Value(1)),
+            Err(SerdeError::Unsupported(_))
+        ));
+        assert!(matches!(
+            to_value(&vec![Some(1), None]),
+            Err(SerdeError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn field_ids_must_be_unambiguous() {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct Mixed {
+            #[serde(rename = "1")]
+            first: u8,
+            second: u8,
+        }
+        #[derive(Serialize)]
+        struct Duplicate {
+            #[serde(rename = "1")]
+            first: u8,
+            #[serde(rename = "01")]
+            second: u8,
+        }
+
+        assert!(matches!(
+            to_vec(&Mixed {
+                first: 1,
+                second: 2
+            }),
+            Err(SerdeError::MixedFieldIds)
+        ));
+        assert!(matches!(
+            from_slice::<Mixed>(&SETTINGS_PAYLOAD),
+            Err(SerdeError::MixedFieldIds)
+        ));
+        assert!(matches!(
+            to_vec(&Duplicate {
+                first: 1,
+                second: 2
+            }),
+            Err(SerdeError::DuplicateFieldId(1))
+        ));
+    }
+}
@@ -0,0 +1,855 @@
+use super::BondError;
+use super::reader::ReadLimits;
+use super::types::*;
+use super::value::*;
+use super::varint::*;
+
+/// The type of a value as declared in a schema.
+///
+/// SimpleBinary has no field headers or element types on the wire, so reading it needs the
+/// full type, including what's inside structs and containers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeSchema {
+    /// `bool`
+    Bool,
+    /// `uint8`
+    UInt8,
+    /// `uint16`
+    UInt16,
+    /// `uint32`
+    UInt32,
+    /// `uint64`
+    UInt64,
+    /// `int8`
+    Int8,
+    /// `int16`
+    Int16,
+    /// `int32`
+    Int32,
+    /// `int64`
+    Int64,
+    /// `float`
+    Float,
+    /// `double`
+    Double,
+    /// `string`
+    String,
+    /// `wstring`
+    WString,
+    /// A struct with the given fields
+    Struct(StructSchema),
+    /// `list<T>`
+    List(Box<TypeSchema>),
+    /// `set<T>`
+    Set(Box<TypeSchema>),
+    /// `map<K, V>`
+    Map(Box<TypeSchema>, Box<TypeSchema>),
+}
+
+impl TypeSchema {
+    /// Returns the BondType of values with this schema.
+    pub fn bond_type(&self) -> BondType {
+        match self {
+            TypeSchema::Bool => BondType::Bool,
+            TypeSchema::UInt8 => BondType::UInt8,
+            TypeSchema::UInt16 => BondType::UInt16,
+            TypeSchema::UInt32 => BondType::UInt32,
+            TypeSchema::UInt64 => BondType::UInt64,
+            TypeSchema::Int8 => BondType::Int8,
+            TypeSchema::Int16 => BondType::Int16,
+            TypeSchema::Int32 => BondType::Int32,
+            TypeSchema::Int64 => BondType::Int64,
+            TypeSchema::Float => BondType::Float,
+            TypeSchema::Double => BondType::Double,
+            TypeSchema::String => BondType::String,
+            TypeSchema::WString => BondType::WString,
+            TypeSchema::Struct(_) => BondType::Struct,
+            TypeSchema::List(_) => BondType::List,
+            TypeSchema::Set(_) => BondType::Set,
+            TypeSchema::Map(..) => BondType::Map,
+        }
+    }
+
+    /// Infers the schema of a value, e.g. one read from a self-describing protocol.
+    ///
+    /// Element schemas come from the first element of each container. Empty containers only
+    /// know their element type, so nested structs and containers in them are inferred as
+    /// empty structs and lists of bools.
+    pub fn of(value: &BondValue) -> Self {
+        match value {
+            BondValue::Bool(_) => TypeSchema::Bool,
+            BondValue::UInt8(_) => TypeSchema::UInt8,
+            BondValue::UInt16(_) => TypeSchema::UInt16,
+            BondValue::UInt32(_) => TypeSchema::UInt32,
+            BondValue::UInt64(_) => TypeSchema::UInt64,
+            BondValue::Int8(_) => TypeSchema::Int8,
+            BondValue::Int16(_) => TypeSchema::Int16,
+            BondValue::Int32(_) => TypeSchema::Int32,
+            BondValue::Int64(_) => TypeSchema::Int64,
+            BondValue::Float(_) => TypeSchema::Float,
+            BondValue::Double(_) => TypeSchema::Double,
+            BondValue::String(_) => TypeSchema::String,
+            BondValue::WString(_) => TypeSchema::WString,
+            BondValue::Struct(s) => TypeSchema::Struct(StructSchema::of(s)),
+            BondValue::List {
+                element_type,
+                elements,
+            } => TypeSchema::List(Box::new(Self::element(*element_type, elements.first()))),
+            BondValue::Set {
+                element_type,
+                elements,
+            } => TypeSchema::Set(Box::new(Self::element(*element_type, elements.first()))),
+            BondValue::Map {
+                key_type,
+                value_type,
+                entries,
+            } => {
+                let first = entries.first();
+                TypeSchema::Map(
+                    Box::new(Self::element(*key_type, first.map(|(k, _)| k))),
+                    Box::new(Self::element(*value_type, first.map(|(_, v)| v))),
+                )
+            }
+        }
+    }
+
+    fn element(bond_type: BondType, first: Option<&BondValue>) -> Self {
+        if let Some(value) = first {
+            return Self::of(value);
+        }
+        match bond_type {
+            BondType::Bool => TypeSchema::Bool,
+            BondType::UInt8 => TypeSchema::UInt8,
+            BondType::UInt16 => TypeSchema::UInt16,
+            BondType::UInt32 => TypeSchema::UInt32,
+            BondType::UInt64 => TypeSchema::UInt64,
+            BondType::Int8 => TypeSchema::Int8,
+            BondType::Int16 => TypeSchema::Int16,
+            BondType::Int32 => TypeSchema::Int32,
+            BondType::Int64 => TypeSchema::Int64,
+            BondType::Float => TypeSchema::Float,
+            BondType::Double => TypeSchema::Double,
+            BondType::String => TypeSchema::String,
+            BondType::WString => TypeSchema::WString,
+            BondType::Struct => TypeSchema::Struct(StructSchema::default()),
+            BondType::List => TypeSchema::List(Box::new(TypeSchema::Bool)),
+            BondType::Set => TypeSchema::Set(Box::new(TypeSchema::Bool)),
+            BondType::Map => {
+                TypeSchema::Map(Box::new(TypeSchema::Bool), Box::new(TypeSchema::Bool))
+            }
+        }
+    }
+}
+
+/// The fields of a struct in declaration order, which is also their SimpleBinary wire order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StructSchema {
+    /// The (field_id, schema) pairs, base-struct fields first
+    pub fields: Vec<(u16, TypeSchema)>,
+}
+
+impl StructSchema {
+    /// Infers the schema of a struct from its fields, see [TypeSchema::of].
+    pub fn of(s: &BondStruct) -> Self {
+        Self {
+            fields: s
+                .fields
+                .iter()
+                .map(|(id, value)| (*id, TypeSchema::of(value)))
+                .collect(),
+        }
+    }
+}
+
+/// Deserializer for Bond SimpleBinary payloads.
+///
+/// SimpleBinary writes every field of a struct in schema order with no field headers, stop
+/// markers or element types, so values are read against a [TypeSchema]. Integers are
+/// fixed-width little-endian.
+pub struct SimpleBinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    version: SimpleBinaryVersion,
+    limits: ReadLimits,
+    depth: usize,
+    elements: u64,
+}
+
+impl<'a> SimpleBinaryReader<'a> {
+    /// Creates a v1 reader positioned at the start of `data`.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self::with_version(data, SimpleBinaryVersion::V1)
+    }
+
+    /// Creates a reader for the given protocol version positioned at the start of `data`.
+    pub fn with_version(data: &'a [u8], version: SimpleBinaryVersion) -> Self {
+        Self {
+            data,
+            pos: 0,
+            version,
+            limits: ReadLimits::default(),
+            depth: 0,
+            elements: 0,
+        }
+    }
+
+    /// Returns the limits the reader enforces.
+    pub fn limits(&self) -> ReadLimits {
+        self.limits
+    }
+
+    /// Replaces the default [ReadLimits].
+    pub fn set_limits(&mut self, limits: ReadLimits) {
+        self.limits = limits;
+    }
+
+    /// Returns the protocol version being read.
+    pub fn version(&self) -> SimpleBinaryVersion {
+        self.version
+    }
+
+    /// Returns the offset of the next byte to be read.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the number of bytes left after the current position.
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    fn read_bytes(&mut self, n: usize) -> Result<&'a [u8], BondError> {
+        if self.remaining() < n {
+            return Err(BondError::UnexpectedEof(self.pos));
+        }
+        let slice = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(slice)
+    }
+
+    /// Runs `read` one nesting level deeper, failing if that exceeds the depth limit.
+    fn nested<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, BondError>,
+    ) -> Result<T, BondError> {
+        if self.depth >= self.limits.max_depth {
+            return Err(BondError::DepthLimitExceeded(self.limits.max_depth));
+        }
+        self.depth += 1;
+        let result = read(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Checks `count` container elements of `size` bytes each in memory against the limits,
+    /// and that the data left can hold them at `wire_size` bytes each.
+    ///
+    /// Elements that take no bytes at all, e.g. empty structs, still need one byte each, so a
+    /// count can't claim more elements than the data has bytes left.
+    fn check_elements(
+        &mut self,
+        count: u32,
+        size: usize,
+        wire_size: usize,
+    ) -> Result<(), BondError> {
+        self.elements = self.elements.saturating_add(count as u64);
+        if self.elements > self.limits.max_elements {
+            return Err(BondError::ElementLimitExceeded(self.limits.max_elements));
+        }
+        self.check_allocation(count, size)?;
+        if count as u64 * wire_size.max(1) as u64 > self.remaining() as u64 {
+            return Err(BondError::UnexpectedEof(self.data.len()));
+        }
+        Ok(())
+    }
+
+    /// Fails if `count` values of `size` bytes each exceed the allocation limit.
+    fn check_allocation(&self, count: u32, size: usize) -> Result<(), BondError> {
+        let size = count as u64 * size as u64;
+        if size > self.limits.max_allocation as u64 {
+            return Err(BondError::AllocationLimitExceeded {
+                size,
+                limit: self.limits.max_allocation,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the fewest bytes a value with this schema takes on the wire.
+    fn wire_size(&self, schema: &TypeSchema) -> usize {
+        match schema {
+            TypeSchema::Bool | TypeSchema::UInt8 | TypeSchema::Int8 => 1,
+            TypeSchema::UInt16 | TypeSchema::Int16 => 2,
+            TypeSchema::UInt32 | TypeSchema::Int32 | TypeSchema::Float => 4,
+            TypeSchema::UInt64 | TypeSchema::Int64 | TypeSchema::Double => 8,
+            TypeSchema::String
+            | TypeSchema::WString
+            | TypeSchema::List(_)
+            | TypeSchema::Set(_)
+            | TypeSchema::Map(..) => match self.version {
+                SimpleBinaryVersion::V1 => 4,
+                SimpleBinaryVersion::V2 => 1,
+            },
+            TypeSchema::Struct(s) => s.fields.iter().map(|(_, f)| self.wire_size(f)).sum(),
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], BondError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    /// Reads a string length or container count: a uint32 in v1, a varint in v2.
+    fn read_length(&mut self) -> Result<u32, BondError> {
+        match self.version {
+            SimpleBinaryVersion::V1 => self.read_uint32(),
+            SimpleBinaryVersion::V2 => {
+                let (val, new_pos) = read_varint(self.data, self.pos)?;
+                self.pos = new_pos;
+                Ok(val as u32)
+            }
+        }
+    }
+
+    // -- Marshaled header --
+
+    /// Reads the 4-byte marshaled header (`SP` magic and version 1 or 2) that precedes a
+    /// marshaled payload, failing with [BondError::InvalidHeader] for anything else.
+    ///
+    /// The rest of the payload is read with the version from the header.
+    pub fn read_marshaled_header(&mut self) -> Result<(), BondError> {
+        let magic = self.read_bytes(2)?;
+        if magic != SIMPLE_BINARY_MAGIC {
+            return Err(BondError::InvalidHeader);
+        }
+        self.version = match self.read_bytes(2)? {
+            v if v == SIMPLE_BINARY_V1 => SimpleBinaryVersion::V1,
+            v if v == SIMPLE_BINARY_V2 => SimpleBinaryVersion::V2,
+            _ => return Err(BondError::InvalidHeader),
+        };
+        Ok(())
+    }
+
+    // -- Primitive readers --
+
+    /// Reads a bool (one byte, non-zero is `true`).
+    pub fn read_bool(&mut self) -> Result<bool, BondError> {
+        Ok(self.read_uint8()? != 0)
+    }
+
+    /// Reads a uint8 (one byte).
+    pub fn read_uint8(&mut self) -> Result<u8, BondError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    /// Reads an int8 (one byte, two's complement).
+    pub fn read_int8(&mut self) -> Result<i8, BondError> {
+        Ok(self.read_uint8()? as i8)
+    }
+
+    /// Reads a uint16 (2 bytes, little-endian).
+    pub fn read_uint16(&mut self) -> Result<u16, BondError> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads an int16 (2 bytes, little-endian).
+    pub fn read_int16(&mut self) -> Result<i16, BondError> {
+        Ok(i16::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a uint32 (4 bytes, little-endian).
+    pub fn read_uint32(&mut self) -> Result<u32, BondError> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads an int32 (4 bytes, little-endian).
+    pub fn read_int32(&mut self) -> Result<i32, BondError> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a uint64 (8 bytes, little-endian).
+    pub fn read_uint64(&mut self) -> Result<u64, BondError> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads an int64 (8 bytes, little-endian).
+    pub fn read_int64(&mut self) -> Result<i64, BondError> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a float (4 bytes, little-endian IEEE 754).
+    pub fn read_float(&mut self) -> Result<f32, BondError> {
+        Ok(f32::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a double (8 bytes, little-endian IEEE 754).
+    pub fn read_double(&mut self) -> Result<f64, BondError> {
+        Ok(f64::from_le_bytes(self.read_array()?))
+    }
+
+    /// Reads a string: a byte length followed by UTF-8.
+    pub fn read_string(&mut self) -> Result<String, BondError> {
+        let len = self.read_length()?;
+        self.check_allocation(len, 1)?;
+        let bytes = self.read_bytes(len as usize)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| BondError::InvalidUtf8)
+    }
+
+    /// Reads a wstring: a length in UTF-16 code units followed by UTF-16LE.
+    pub fn read_wstring(&mut self) -> Result<String, BondError> {
+        let len = self.read_length()?;
+        self.check_allocation(len, 2)?;
+        let len = len as usize;
+        let byte_len = len.checked_mul(2).ok_or(BondError::VarintOverflow)?;
+        let utf16: Vec<u16> = self
+            .read_bytes(byte_len)?
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16(&utf16).map_err(|_| BondError::InvalidUtf16)
+    }
+
+    // -- High-level readers --
+
+    /// Reads a single value with the given schema, within the reader's [ReadLimits].
+    ///
+    /// A container's count is checked against the limits and the data left before any of its
+    /// elements are read.
+    pub fn read_value(&mut self, schema: &TypeSchema) -> Result<BondValue, BondError> {
+        match schema {
+            TypeSchema::Bool => Ok(BondValue::Bool(self.read_bool()?)),
+            TypeSchema::UInt8 => Ok(BondValue::UInt8(self.read_uint8()?)),
+            TypeSchema::Int8 => Ok(BondValue::Int8(self.read_int8()?)),
+            TypeSchema::UInt16 => Ok(BondValue::UInt16(self.read_uint16()?)),
+            TypeSchema::Int16 => Ok(BondValue::Int16(self.read_int16()?)),
+            TypeSchema::UInt32 => Ok(BondValue::UInt32(self.read_uint32()?)),
+            TypeSchema::Int32 => Ok(BondValue::Int32(self.read_int32()?)),
+            TypeSchema::UInt64 => Ok(BondValue::UInt64(self.read_uint64()?)),
+            TypeSchema::Int64 => Ok(BondValue::Int64(self.read_int64()?)),
+            TypeSchema::Float => Ok(BondValue::Float(self.read_float()?)),
+            TypeSchema::Double => Ok(BondValue::Double(self.read_double()?)),
+            TypeSchema::String => Ok(BondValue::String(self.read_string()?)),
+            TypeSchema::WString => Ok(BondValue::WString(self.read_wstring()?)),
+            TypeSchema::Struct(s) => Ok(BondValue::Struct(self.nested(|r| r.read_struct(s))?)),
+            TypeSchema::List(element) => Ok(BondValue::List {
+                element_type: element.bond_type(),
+                elements: self.nested(|r| r.read_elements(element))?,
+            }),
+            TypeSchema::Set(element) => Ok(BondValue::Set {
+                element_type: element.bond_type(),
+                elements: self.nested(|r| r.read_elements(element))?,
+            }),
+            TypeSchema::Map(key, value) => self.nested(|r| {
+                let count = r.read_length()?;
+                let wire_size = r.wire_size(key) + r.wire_size(value);
+                r.check_elements(count, size_of::<(BondValue, BondValue)>(), wire_size)?;
+                let mut entries = Vec::with_capacity((count as usize).min(r.remaining()));
+                for _ in 0..count {
+                    let k = r.read_value(key)?;
+                    let v = r.read_value(value)?;
+                    entries.push((k, v));
+                }
+                Ok(BondValue::Map {
+                    key_type: key.bond_type(),
+                    value_type: value.bond_type(),
+                    entries,
+                })
+            }),
+        }
+    }
+
+    fn read_elements(&mut self, element: &TypeSchema) -> Result<Vec<BondValue>, BondError> {
+        let count = self.read_length()?;
+        self.check_elements(count, size_of::<BondValue>(), self.wire_size(element))?;
+        let mut elements = Vec::with_capacity((count as usize).min(self.remaining()));
+        for _ in 0..count {
+            elements.push(self.read_value(element)?);
+        }
+        Ok(elements)
+    }
+
+    /// Reads every field of a struct with the given schema, returning a BondStruct.
+    pub fn read_struct(&mut self, schema: &StructSchema) -> Result<BondStruct, BondError> {
+        let mut fields = Vec::with_capacity(schema.fields.len());
+        for (id, field) in &schema.fields {
+            fields.push((*id, self.read_value(field)?));
+        }
+        Ok(BondStruct { fields })
+    }
+}
+
+/// Serializer for Bond SimpleBinary payloads.
+///
+/// Only values are written, so the output can only be read back with the schema of what was
+/// written. Every field of a struct has to be present; IDs are not written.
+#[derive(Default)]
+pub struct SimpleBinaryWriter {
+    buf: Vec<u8>,
+    version: SimpleBinaryVersion,
+}
+
+impl SimpleBinaryWriter {
+    /// Creates a v1 writer with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a writer for the given protocol version with an empty buffer.
+    pub fn with_version(version: SimpleBinaryVersion) -> Self {
+        Self {
+            buf: Vec::new(),
+            version,
+        }
+    }
+
+    /// Returns the protocol version being written.
+    pub fn version(&self) -> SimpleBinaryVersion {
+        self.version
+    }
+
+    /// Returns the bytes written so far.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    /// Writes a string length or container count: a uint32 in v1, a varint in v2.
+    fn write_length(&mut self, len: usize) {
+        match self.version {
+            SimpleBinaryVersion::V1 => self.write_uint32(len as u32),
+            SimpleBinaryVersion::V2 => write_varint(&mut self.buf, len as u64),
+        }
+    }
+
+    // -- Marshaled header --
+
+    /// Writes the 4-byte marshaled header (`SP` magic and the writer's version).
+    pub fn write_marshaled_header(&mut self) {
+        self.buf.extend_from_slice(&SIMPLE_BINARY_MAGIC);
+        self.buf.extend_from_slice(match self.version {
+            SimpleBinaryVersion::V1 => &SIMPLE_BINARY_V1,
+            SimpleBinaryVersion::V2 => &SIMPLE_BINARY_V2,
+        });
+    }
+
+    // -- Primitive writers --
+
+    /// Writes a bool as one byte.
+    pub fn write_bool(&mut self, val: bool) {
+        self.buf.push(val as u8);
+    }
+
+    /// Writes a uint8 as one byte.
+    pub fn write_uint8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    /// Writes an int8 as one byte.
+    pub fn write_int8(&mut self, val: i8) {
+        self.buf.push(val as u8);
+    }
+
+    /// Writes a uint16 as 2 little-endian bytes.
+    pub fn write_uint16(&mut self, val: u16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes an int16 as 2 little-endian bytes.
+    pub fn write_int16(&mut self, val: i16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a uint32 as 4 little-endian bytes.
+    pub fn write_uint32(&mut self, val: u32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes an int32 as 4 little-endian bytes.
+    pub fn write_int32(&mut self, val: i32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a uint64 as 8 little-endian bytes.
+    pub fn write_uint64(&mut self, val: u64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes an int64 as 8 little-endian bytes.
+    pub fn write_int64(&mut self, val: i64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a float as 4 little-endian bytes.
+    pub fn write_float(&mut self, val: f32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a double as 8 little-endian bytes.
+    pub fn write_double(&mut self, val: f64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Writes a string as a byte length followed by UTF-8.
+    pub fn write_string(&mut self, val: &str) {
+        self.write_length(val.len());
+        self.buf.extend_from_slice(val.as_bytes());
+    }
+
+    /// Writes a wstring as a UTF-16 length followed by UTF-16LE.
+    pub fn write_wstring(&mut self, val: &str) {
+        let utf16: Vec<u16> = val.encode_utf16().collect();
+        self.write_length(utf16.len());
+        for unit in &utf16 {
+            self.buf.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+
+    // -- High-level writers --
+
+    /// Writes a single BondValue. Containers are written as a count followed by the elements.
+    pub fn write_value(&mut self, val: &BondValue) {
+        match val {
+            BondValue::Bool(v) => self.write_bool(*v),
+            BondValue::UInt8(v) => self.write_uint8(*v),
+            BondValue::Int8(v) => self.write_int8(*v),
+            BondValue::UInt16(v) => self.write_uint16(*v),
+            BondValue::Int16(v) => self.write_int16(*v),
+            BondValue::UInt32(v) => self.write_uint32(*v),
+            BondValue::Int32(v) => self.write_int32(*v),
+            BondValue::UInt64(v) => self.write_uint64(*v),
+            BondValue::Int64(v) => self.write_int64(*v),
+            BondValue::Float(v) => self.write_float(*v),
+            BondValue::Double(v) => self.write_double(*v),
+            BondValue::String(v) => self.write_string(v),
+            BondValue::WString(v) => self.write_wstring(v),
+            BondValue::Struct(s) => self.write_struct(s),
+            BondValue::List { elements, .. } | BondValue::Set { elements, .. } => {
+                self.write_length(elements.len());
+                for elem in elements {
+                    self.write_value(elem);
+                }
+            }
+            BondValue::Map { entries, .. } => {
+                self.write_length(entries.len());
+                for (k, v) in entries {
+                    self.write_value(k);
+                    self.write_value(v);
+                }
+            }
+        }
+    }
+
+    /// Writes the values of a BondStruct's fields in order.
+    pub fn write_struct(&mut self, s: &BondStruct) {
+        for (_, val) in &s.fields {
+            self.write_value(val);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> BondStruct {
+        BondStruct {
+            fields: vec![
+                (0, BondValue::Bool(true)),
+                (10, BondValue::Int16(-2)),
+                (
+                    20,
+                    BondValue::Struct(BondStruct {
+                        fields: vec![(0, BondValue::String("hi".into()))],
+                    }),
+                ),
+                (
+                    30,
+                    BondValue::List {
+                        element_type: BondType::UInt32,
+                        elements: vec![BondValue::UInt32(1), BondValue::UInt32(2)],
+                    },
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn v1_bytes() {
+        let mut w = SimpleBinaryWriter::new();
+        w.write_marshaled_header();
+        w.write_struct(&sample());
+        assert_eq!(
+            w.into_bytes(),
+            [
+                0x53, 0x50, 0x01, 0x00, // SP v1
+                0x01, // field 0, bool
+                0xFE, 0xFF, // field 10, int16
+                0x02, 0x00, 0x00, 0x00, 0x68, 0x69, // field 20, struct { string "hi" }
+                0x02, 0x00, 0x00, 0x00, // field 30, list count
+                0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // elements
+            ]
+        );
+    }
+
+    #[test]
+    fn v2_bytes() {
+        let mut w = SimpleBinaryWriter::with_version(SimpleBinaryVersion::V2);
+        w.write_marshaled_header();
+        w.write_struct(&sample());
+        assert_eq!(
+            w.into_bytes(),
+            [
+                0x53, 0x50, 0x02, 0x00, // SP v2
+                0x01, // field 0, bool
+                0xFE, 0xFF, // field 10, int16
+                0x02, 0x68, 0x69, // field 20, struct { string "hi" }
+                0x02, // field 30, list count
+                0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // elements
+            ]
+        );
+    }
+
+    #[test]
+    fn roundtrip_with_inferred_schema() {
+        let mut original = sample();
+        original.push(
+            40,
+            BondValue::Map {
+                key_type: BondType::WString,
+                value_type: BondType::Struct,
+                entries: vec![(
+                    BondValue::WString("nuit".into()),
+                    BondValue::Struct(BondStruct {
+                        fields: vec![(1, BondValue::Double(0.25))],
+                    }),
+                )],
+            },
+        );
+        original.push(
+            50,
+            BondValue::Set {
+                element_type: BondType::Int64,
+                elements: vec![],
+            },
+        );
+        let schema = StructSchema::of(&original);
+
+        for version in [SimpleBinaryVersion::V1, SimpleBinaryVersion::V2] {
+            let mut w = SimpleBinaryWriter::with_version(version);
+            w.write_marshaled_header();
+            w.write_struct(&original);
+            let bytes = w.into_bytes();
+
+            let mut r = SimpleBinaryReader::new(&bytes);
+            r.read_marshaled_header().unwrap();
+            assert_eq!(r.version(), version);
+            assert_eq!(r.read_struct(&schema).unwrap(), original);
+            assert_eq!(r.remaining(), 0);
+        }
+    }
+
+    #[test]
+    fn inferred_schema() {
+        assert_eq!(
+            StructSchema::of(&sample()).fields,
+            vec![
+                (0, TypeSchema::Bool),
+                (10, TypeSchema::Int16),
+                (
+                    20,
+                    TypeSchema::Struct(StructSchema {
+                        fields: vec![(0, TypeSchema::String)]
+                    })
+                ),
+                (30, TypeSchema::List(Box::new(TypeSchema::UInt32))),
+            ]
+        );
+        let empty = BondValue::List {
+            element_type: BondType::Struct,
+            elements: vec![],
+        };
+        assert_eq!(
+            TypeSchema::of(&empty),
+            TypeSchema::List(Box::new(TypeSchema::Struct(StructSchema::default())))
+        );
+    }
+
+    #[test]
+    fn truncated_and_invalid() {
+        let schema = StructSchema {
+            fields: vec![(0, TypeSchema::UInt64)],
+        };
+        let mut r = SimpleBinaryReader::new(&[0x01, 0x02, 0x03]);
+        assert!(matches!(
+            r.read_struct(&schema),
+            Err(BondError::UnexpectedEof(0))
+        ));
+
+        // A v1 string length larger than the data
+        let mut r = SimpleBinaryReader::new(&[0x05, 0x00, 0x00, 0x00]);
+        assert!(matches!(
+            r.read_value(&TypeSchema::String),
+            Err(BondError::UnexpectedEof(4))
+        ));
+        let mut r = SimpleBinaryReader::new(&[0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(matches!(
+            r.read_value(&TypeSchema::String),
+            Err(BondError::AllocationLimitExceeded { .. })
+        ));
+
+        let mut r = SimpleBinaryReader::new(&[0x53, 0x50, 0x03, 0x00]);
+        assert!(matches!(
+            r.read_marshaled_header(),
+            Err(BondError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        // A struct with an empty list<struct> infers a schema whose elements take no bytes
+        let empty = BondStruct {
+            fields: vec![(
+                0,
+                BondValue::List {
+                    element_type: BondType::Struct,
+                    elements: vec![],
+                },
+            )],
+        };
+        let schema = StructSchema::of(&empty);
+        let data = [0x53, 0x50, 0x01, 0x00, 0xFF, 0xFF, 0xFF, 0xFF];
+        let mut r = SimpleBinaryReader::new(&data);
+        r.read_marshaled_header().unwrap();
+        assert!(matches!(
+            r.read_struct(&schema),
+            Err(BondError::ElementLimitExceeded(_))
+        ));
+
+        // Even elements that take no bytes can't outnumber the bytes left
+        let data = [0x53, 0x50, 0x01, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00];
+        let mut r = SimpleBinaryReader::new(&data);
+        r.read_marshaled_header().unwrap();
+        assert!(matches!(
+            r.read_struct(&schema),
+            Err(BondError::UnexpectedEof(9))
+        ));
+
+        // Elements that can't fit in the data fail before any are read
+        let mut r = SimpleBinaryReader::new(&[0xE8, 0x03, 0x00, 0x00, 0x01, 0x00]);
+        assert!(matches!(
+            r.read_value(&TypeSchema::List(Box::new(TypeSchema::UInt32))),
+            Err(BondError::UnexpectedEof(6))
+        ));
+
+        let schema = TypeSchema::List(Box::new(TypeSchema::List(Box::new(TypeSchema::Bool))));
+        let mut r = SimpleBinaryReader::new(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        r.set_limits(ReadLimits {
+            max_depth: 1,
+            ..ReadLimits::default()
+        });
+        assert!(matches!(
+            r.read_value(&schema),
+            Err(BondError::DepthLimitExceeded(1))
+        ));
+    }
+}
@@ -4,26 +4,87 @@ pub const COMPACT_BINARY_MAGIC: [u8; 2] = [0x43, 0x42];
 /// CompactBinary version 1 (uint16 LE).
 pub const COMPACT_BINARY_V1: [u8; 2] = [0x01, 0x00];
 
+/// CompactBinary version 2 (uint16 LE).
+pub const COMPACT_BINARY_V2: [u8; 2] = [0x02, 0x00];
+
+/// Bond FastBinary protocol magic bytes (FAST_PROTOCOL = 0x464D, stored as uint16 LE).
+pub const FAST_BINARY_MAGIC: [u8; 2] = [0x4D, 0x46];
+
+/// FastBinary version 1 (uint16 LE), the only version.
+pub const FAST_BINARY_V1: [u8; 2] = [0x01, 0x00];
+
+/// Bond SimpleBinary protocol magic bytes (SIMPLE_PROTOCOL = 0x5053, stored as uint16 LE).
+pub const SIMPLE_BINARY_MAGIC: [u8; 2] = [0x53, 0x50];
+
+/// SimpleBinary version 1 (uint16 LE).
+pub const SIMPLE_BINARY_V1: [u8; 2] = [0x01, 0x00];
+
+/// SimpleBinary version 2 (uint16 LE).
+pub const SIMPLE_BINARY_V2: [u8; 2] = [0x02, 0x00];
+
+/// CompactBinary protocol version.
+///
+/// v2 prefixes every struct with its byte length so it can be skipped without parsing, and
+/// packs list/set counts below 7 into the element type byte. The values themselves are the
+/// same as in v1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompactBinaryVersion {
+    /// Version 1, used by the CloudStore blobs
+    #[default]
+    V1,
+    /// Version 2, with length-prefixed structs
+    V2,
+}
+
+/// SimpleBinary protocol version.
+///
+/// v1 writes string lengths and container counts as fixed uint32, v2 as varints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SimpleBinaryVersion {
+    /// Version 1, with fixed-width lengths
+    #[default]
+    V1,
+    /// Version 2, with varint lengths
+    V2,
+}
+
 /// Bond data type identifiers (5-bit, used in field headers and container headers).
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BondType {
+    /// `bool`
     Bool = 2,
+    /// `uint8`
     UInt8 = 3,
+    /// `uint16`
     UInt16 = 4,
+    /// `uint32`
     UInt32 = 5,
+    /// `uint64`
     UInt64 = 6,
+    /// `float`
     Float = 7,
+    /// `double`
     Double = 8,
+    /// `string`
     String = 9,
+    /// `struct`
     Struct = 10,
+    /// `list`
     List = 11,
+    /// `set`
     Set = 12,
+    /// `map`
     Map = 13,
+    /// `int8`
     Int8 = 14,
+    /// `int16`
     Int16 = 15,
+    /// `int32`
     Int32 = 16,
+    /// `int64`
     Int64 = 17,
+    /// `wstring`
     WString = 18,
 }
 
@@ -53,3 +114,68 @@ impl TryFrom<u8> for BondType {
         }
     }
 }
+
+impl BondType {
+    /// Every type, in type ID order.
+    pub const ALL: [BondType; 17] = [
+        BondType::Bool,
+        BondType::UInt8,
+        BondType::UInt16,
+        BondType::UInt32,
+        BondType::UInt64,
+        BondType::Float,
+        BondType::Double,
+        BondType::String,
+        BondType::Struct,
+        BondType::List,
+        BondType::Set,
+        BondType::Map,
+        BondType::Int8,
+        BondType::Int16,
+        BondType::Int32,
+        BondType::Int64,
+        BondType::WString,
+    ];
+
+    /// Returns the type's name in the Bond IDL, e.g. `int16`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BondType::Bool => "bool",
+            BondType::UInt8 => "uint8",
+            BondType::UInt16 => "uint16",
+            BondType::UInt32 => "uint32",
+            BondType::UInt64 => "uint64",
+            BondType::Float => "float",
+            BondType::Double => "double",
+            BondType::String => "string",
+            BondType::Struct => "struct",
+            BondType::List => "list",
+            BondType::Set => "set",
+            BondType::Map => "map",
+            BondType::Int8 => "int8",
+            BondType::Int16 => "int16",
+            BondType::Int32 => "int32",
+            BondType::Int64 => "int64",
+            BondType::WString => "wstring",
+        }
+    }
+
+    /// Looks up a type by its name in the Bond IDL, the inverse of [BondType::name].
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_roundtrip() {
+        for bond_type in BondType::ALL {
+            assert_eq!(BondType::try_from(bond_type as u8), Ok(bond_type));
+            assert_eq!(BondType::from_name(bond_type.name()), Some(bond_type));
+        }
+        assert_eq!(BondType::from_name("list<int8>"), None);
+    }
+}
@@ -4,31 +4,55 @@ use super::types::BondType;
 /// at compile time, or for preserving fields during roundtrip serialization.
 #[derive(Debug, Clone, PartialEq)]
 pub enum BondValue {
+    /// `bool`
     Bool(bool),
+    /// `uint8`
     UInt8(u8),
+    /// `uint16`
     UInt16(u16),
+    /// `uint32`
     UInt32(u32),
+    /// `uint64`
     UInt64(u64),
+    /// `int8`
     Int8(i8),
+    /// `int16`
     Int16(i16),
+    /// `int32`
     Int32(i32),
+    /// `int64`
     Int64(i64),
+    /// `float`
     Float(f32),
+    /// `double`
     Double(f64),
+    /// `string`, UTF-8 on the wire
     String(String),
+    /// `wstring`, UTF-16LE on the wire
     WString(String),
+    /// A nested struct
     Struct(BondStruct),
+    /// A list whose elements all have `element_type`
     List {
+        /// Type of every element, also written for empty lists
         element_type: BondType,
+        /// The elements in order
         elements: Vec<BondValue>,
     },
+    /// A set whose elements all have `element_type`
     Set {
+        /// Type of every element, also written for empty sets
         element_type: BondType,
+        /// The elements in wire order
         elements: Vec<BondValue>,
     },
+    /// A map whose keys and values all have `key_type` and `value_type`
     Map {
+        /// Type of every key
         key_type: BondType,
+        /// Type of every value
         value_type: BondType,
+        /// The entries in wire order
         entries: Vec<(BondValue, BondValue)>,
     },
 }
@@ -62,10 +86,12 @@ impl BondValue {
 /// and should be sorted by field ID for correct serialization.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct BondStruct {
+    /// The (field_id, value) pairs in wire order
     pub fields: Vec<(u16, BondValue)>,
 }
 
 impl BondStruct {
+    /// Creates a struct with no fields.
     pub fn new() -> Self {
         Self { fields: Vec::new() }
     }
@@ -87,4 +113,12 @@ impl BondStruct {
     pub fn push(&mut self, id: u16, value: BondValue) {
         self.fields.push((id, value));
     }
+
+    /// Iterates over the fields in ascending ID order. Fields sharing an ID keep their
+    /// relative order.
+    pub fn sorted_fields(&self) -> impl Iterator<Item = &(u16, BondValue)> {
+        let mut fields: Vec<_> = self.fields.iter().collect();
+        fields.sort_by_key(|(id, _)| *id);
+        fields.into_iter()
+    }
 }
@@ -1,31 +1,54 @@
+use std::iter::Peekable;
+
 use super::types::*;
 use super::value::*;
 use super::varint::*;
 
-/// Serializer for Bond CompactBinary v1 payloads.
+/// Serializer for Bond CompactBinary v1 and v2 payloads.
 #[derive(Default)]
 pub struct CompactBinaryWriter {
     buf: Vec<u8>,
+    version: CompactBinaryVersion,
 }
 
 impl CompactBinaryWriter {
+    /// Creates a v1 writer with an empty buffer.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Creates a writer for the given protocol version with an empty buffer.
+    pub fn with_version(version: CompactBinaryVersion) -> Self {
+        Self {
+            buf: Vec::new(),
+            version,
+        }
+    }
+
+    /// Returns the protocol version being written.
+    pub fn version(&self) -> CompactBinaryVersion {
+        self.version
+    }
+
+    /// Returns the bytes written so far.
     pub fn into_bytes(self) -> Vec<u8> {
         self.buf
     }
 
     // -- Marshaled header --
 
+    /// Writes the 4-byte marshaled header (`CB` magic and the writer's version).
     pub fn write_marshaled_header(&mut self) {
         self.buf.extend_from_slice(&COMPACT_BINARY_MAGIC);
-        self.buf.extend_from_slice(&COMPACT_BINARY_V1);
+        self.buf.extend_from_slice(match self.version {
+            CompactBinaryVersion::V1 => &COMPACT_BINARY_V1,
+            CompactBinaryVersion::V2 => &COMPACT_BINARY_V2,
+        });
     }
 
     // -- Field headers --
 
+    /// Writes a field header, packing IDs up to 5 into the type byte.
     pub fn write_field_header(&mut self, id: u16, bond_type: BondType) {
         let type_byte = bond_type as u8;
         debug_assert!(type_byte & 0x1F == type_byte);
@@ -42,71 +65,86 @@ impl CompactBinaryWriter {
         }
     }
 
+    /// Writes BT_STOP, ending a struct.
     pub fn write_stop(&mut self) {
         self.buf.push(0x00);
     }
 
+    /// Writes BT_STOP_BASE, ending the base-struct part of a struct.
     pub fn write_stop_base(&mut self) {
         self.buf.push(0x01);
     }
 
     // -- Primitive writers --
 
+    /// Writes a bool as one byte.
     pub fn write_bool(&mut self, val: bool) {
         self.buf.push(val as u8);
     }
 
+    /// Writes a uint8 as one byte.
     pub fn write_uint8(&mut self, val: u8) {
         self.buf.push(val);
     }
 
+    /// Writes an int8 as one byte.
     pub fn write_int8(&mut self, val: i8) {
         self.buf.push(val as u8);
     }
 
     /// Appends raw bytes directly to the output buffer.
-    /// Useful for bulk-writing contiguous fixed-width elements (e.g. list<int8>).
+    /// Useful for bulk-writing contiguous fixed-width elements (e.g. `list<int8>`).
     pub fn write_raw_bytes(&mut self, bytes: &[u8]) {
         self.buf.extend_from_slice(bytes);
     }
 
+    /// Writes a uint16 as a varint.
     pub fn write_uint16(&mut self, val: u16) {
         write_varint(&mut self.buf, val as u64);
     }
 
+    /// Writes an int16 as a ZigZag varint.
     pub fn write_int16(&mut self, val: i16) {
         write_varint(&mut self.buf, encode_zigzag_i16(val) as u64);
     }
 
+    /// Writes a uint32 as a varint.
     pub fn write_uint32(&mut self, val: u32) {
         write_varint(&mut self.buf, val as u64);
     }
 
+    /// Writes an int32 as a ZigZag varint.
     pub fn write_int32(&mut self, val: i32) {
         write_varint(&mut self.buf, encode_zigzag_i32(val) as u64);
     }
 
+    /// Writes a uint64 as a varint.
     pub fn write_uint64(&mut self, val: u64) {
         write_varint(&mut self.buf, val);
     }
 
+    /// Writes an int64 as a ZigZag varint.
     pub fn write_int64(&mut self, val: i64) {
         write_varint(&mut self.buf, encode_zigzag_i64(val));
     }
 
+    /// Writes a float as 4 little-endian bytes.
     pub fn write_float(&mut self, val: f32) {
         self.buf.extend_from_slice(&val.to_le_bytes());
     }
 
+    /// Writes a double as 8 little-endian bytes.
     pub fn write_double(&mut self, val: f64) {
         self.buf.extend_from_slice(&val.to_le_bytes());
     }
 
+    /// Writes a string as a varint byte length followed by UTF-8.
     pub fn write_string(&mut self, val: &str) {
         self.write_uint32(val.len() as u32);
         self.buf.extend_from_slice(val.as_bytes());
     }
 
+    /// Writes a wstring as a varint UTF-16 length followed by UTF-16LE.
     pub fn write_wstring(&mut self, val: &str) {
         let utf16: Vec<u16> = val.encode_utf16().collect();
         self.write_uint32(utf16.len() as u32);
@@ -117,10 +155,15 @@ impl CompactBinaryWriter {
 
     // -- Container headers --
 
-    /// Writes a list or set header (v1 format: type byte + varint count).
+    /// Writes a list or set header: type byte + varint count, or in v2 with counts below 7
+    /// packed into the type byte as count + 1.
     pub fn write_container_header(&mut self, element_type: BondType, count: u32) {
-        self.buf.push(element_type as u8);
-        self.write_uint32(count);
+        if self.version == CompactBinaryVersion::V2 && count < 7 {
+            self.buf.push(element_type as u8 | ((count as u8 + 1) << 5));
+        } else {
+            self.buf.push(element_type as u8);
+            self.write_uint32(count);
+        }
     }
 
     /// Writes a map header (key type + value type + varint count).
@@ -181,13 +224,50 @@ impl CompactBinaryWriter {
         }
     }
 
+    /// Writes a single struct field (header + value).
+    pub fn write_field(&mut self, id: u16, val: &BondValue) {
+        self.write_field_header(id, val.bond_type());
+        self.write_value(val);
+    }
+
+    /// Writes every pending field whose ID is below `before`, advancing `fields` past them.
+    ///
+    /// Used to interleave preserved unknown fields between hand-written known fields so the
+    /// output stays in ascending field-ID order. `fields` must already be sorted by ID.
+    pub fn write_fields_before<'f, I>(&mut self, fields: &mut Peekable<I>, before: u16)
+    where
+        I: Iterator<Item = &'f (u16, BondValue)>,
+    {
+        while let Some((id, val)) = fields.next_if(|(id, _)| *id < before) {
+            self.write_field(*id, val);
+        }
+    }
+
+    // -- Structs --
+
+    /// Starts a struct, returning the position to pass to [Self::write_struct_end].
+    pub fn write_struct_begin(&mut self) -> usize {
+        self.buf.len()
+    }
+
+    /// Ends the struct started at `start` with BT_STOP. In v2 this also inserts the struct's
+    /// byte length before its first field.
+    pub fn write_struct_end(&mut self, start: usize) {
+        self.write_stop();
+        if self.version == CompactBinaryVersion::V2 {
+            let mut len = Vec::new();
+            write_varint(&mut len, (self.buf.len() - start) as u64);
+            self.buf.splice(start..start, len);
+        }
+    }
+
     /// Writes a BondStruct (fields + BT_STOP).
     pub fn write_struct(&mut self, s: &BondStruct) {
+        let start = self.write_struct_begin();
         for (id, val) in &s.fields {
-            self.write_field_header(*id, val.bond_type());
-            self.write_value(val);
+            self.write_field(*id, val);
         }
-        self.write_stop();
+        self.write_struct_end(start);
     }
 }
 
@@ -203,6 +283,13 @@ mod tests {
         assert_eq!(w.into_bytes(), [0x43, 0x42, 0x01, 0x00]);
     }
 
+    #[test]
+    fn write_marshaled_header_v2() {
+        let mut w = CompactBinaryWriter::with_version(CompactBinaryVersion::V2);
+        w.write_marshaled_header();
+        assert_eq!(w.into_bytes(), [0x43, 0x42, 0x02, 0x00]);
+    }
+
     #[test]
     fn field_header_small_ids() {
         let mut w = CompactBinaryWriter::new();
@@ -298,4 +385,102 @@ mod tests {
         let decoded = r.read_struct().unwrap();
         assert_eq!(original, decoded);
     }
+
+    fn nested_sample() -> BondStruct {
+        BondStruct {
+            fields: vec![
+                (0, BondValue::Int8(5)),
+                (
+                    1,
+                    BondValue::Struct(BondStruct {
+                        fields: vec![(0, BondValue::Bool(true))],
+                    }),
+                ),
+                (
+                    2,
+                    BondValue::List {
+                        element_type: BondType::Int8,
+                        elements: vec![BondValue::Int8(1), BondValue::Int8(2)],
+                    },
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn v2_struct_bytes() {
+        let mut w = CompactBinaryWriter::with_version(CompactBinaryVersion::V2);
+        w.write_struct(&nested_sample());
+        assert_eq!(
+            w.into_bytes(),
+            [
+                0x0C, // struct length
+                0x0E, 0x05, // field 0, int8 = 5
+                0x2A, 0x03, 0x02, 0x01,
+                0x00, // field 1, struct of length 3 { field 0 = true }
+                0x4B, 0x6E, 0x01, 0x02, // field 2, list<int8> with packed count 2
+                0x00, // BT_STOP
+            ]
+        );
+    }
+
+    #[test]
+    fn v2_matches_v1() {
+        let values = [
+            BondValue::Struct(nested_sample()),
+            BondValue::Struct(BondStruct::new()),
+            BondValue::List {
+                element_type: BondType::Struct,
+                elements: vec![
+                    BondValue::Struct(nested_sample()),
+                    BondValue::Struct(BondStruct::new()),
+                ],
+            },
+            // 6, 7 and 300 elements straddle the packed count limit
+            BondValue::Set {
+                element_type: BondType::UInt32,
+                elements: (0..6).map(BondValue::UInt32).collect(),
+            },
+            BondValue::List {
+                element_type: BondType::WString,
+                elements: (0..7).map(|i| BondValue::WString(i.to_string())).collect(),
+            },
+            BondValue::List {
+                element_type: BondType::Int8,
+                elements: (0..300).map(|i| BondValue::Int8(i as i8)).collect(),
+            },
+            BondValue::Map {
+                key_type: BondType::String,
+                value_type: BondType::Struct,
+                entries: vec![(
+                    BondValue::String("k".into()),
+                    BondValue::Struct(nested_sample()),
+                )],
+            },
+        ];
+
+        for value in values {
+            let encode = |version| {
+                let mut w = CompactBinaryWriter::with_version(version);
+                w.write_marshaled_header();
+                w.write_value(&value);
+                w.into_bytes()
+            };
+            let v1 = encode(CompactBinaryVersion::V1);
+            let v2 = encode(CompactBinaryVersion::V2);
+            assert_ne!(v1, v2);
+
+            for bytes in [&v1, &v2] {
+                let mut r = CompactBinaryReader::new(bytes);
+                r.read_marshaled_header().unwrap();
+                assert_eq!(r.read_value(value.bond_type()).unwrap(), value);
+                assert_eq!(r.remaining(), 0);
+
+                let mut r = CompactBinaryReader::new(bytes);
+                r.read_marshaled_header().unwrap();
+                r.skip_value(value.bond_type()).unwrap();
+                assert_eq!(r.remaining(), 0);
+            }
+        }
+    }
 }
@@ -1,5 +1,35 @@
 use crate::bond::*;
 
+/// Everything in a CloudStore wrapper other than the timestamp and the inner payload.
+///
+/// Captured by [cloudstore_unwrap_envelope] so that rewriting a blob re-emits any metadata or
+/// extra wrapper fields Windows stored, instead of the fixed `{ 0: true }` metadata struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloudStoreEnvelope {
+    /// Field 0 metadata struct, or `None` if the blob had no metadata field.
+    pub metadata: Option<BondStruct>,
+    /// Unknown fields of the outer struct (IDs other than 0 and 1).
+    pub outer_fields: BondStruct,
+    /// Unknown fields of the payload container struct (IDs other than 0 and 1).
+    pub container_fields: BondStruct,
+    /// Unknown fields of the data wrapper struct (IDs other than 1).
+    pub data_fields: BondStruct,
+}
+
+impl Default for CloudStoreEnvelope {
+    /// The envelope Windows writes for Night Light: metadata `{ 0: true }` and nothing else.
+    fn default() -> Self {
+        Self {
+            metadata: Some(BondStruct {
+                fields: vec![(0, BondValue::Bool(true))],
+            }),
+            outer_fields: BondStruct::new(),
+            container_fields: BondStruct::new(),
+            data_fields: BondStruct::new(),
+        }
+    }
+}
+
 /// Unwraps a CloudStore binary blob, returning (timestamp, inner_payload_slice).
 ///
 /// Zero-copy: the returned `&[u8]` borrows directly from the input buffer,
@@ -16,113 +46,90 @@ use crate::bond::*;
 /// }
 /// ```
 pub fn cloudstore_unwrap(data: &[u8]) -> Result<(u64, &[u8]), BondError> {
-    let mut reader = CompactBinaryReader::new(data);
-    reader.read_marshaled_header()?;
-
-    let mut timestamp: Option<u64> = None;
-    let mut payload: Option<&[u8]> = None;
-
-    // Parse outer struct fields
-    loop {
-        match reader.read_field_header()? {
-            FieldHeader::Stop => break,
-            FieldHeader::StopBase => continue,
-            FieldHeader::Field {
-                id: 0,
-                bond_type: BondType::Struct,
-            } => {
-                // Field 0: metadata struct — skip entirely
-                reader.skip_struct()?;
-            }
-            FieldHeader::Field {
-                id: 1,
-                bond_type: BondType::Struct,
-            } => {
-                // Field 1: payload container struct
-                loop {
-                    match reader.read_field_header()? {
-                        FieldHeader::Stop => break,
-                        FieldHeader::StopBase => continue,
-                        FieldHeader::Field {
-                            id: 0,
-                            bond_type: BondType::UInt64,
-                        } => {
-                            timestamp = Some(reader.read_uint64()?);
-                        }
-                        FieldHeader::Field {
-                            id: 1,
-                            bond_type: BondType::Struct,
-                        } => {
-                            // Field 1.1: data wrapper struct
-                            loop {
-                                match reader.read_field_header()? {
-                                    FieldHeader::Stop => break,
-                                    FieldHeader::StopBase => continue,
-                                    FieldHeader::Field {
-                                        id: 1,
-                                        bond_type: BondType::List,
-                                    } => {
-                                        // list<int8> — read header, then borrow raw bytes
-                                        let (elem_type, count) = reader.read_container_header()?;
-                                        if elem_type != BondType::Int8 {
-                                            return Err(BondError::UnexpectedFieldType(1));
-                                        }
-                                        payload = Some(reader.read_bytes_slice(count as usize)?);
-                                    }
-                                    FieldHeader::Field { bond_type, .. } => {
-                                        reader.skip_value(bond_type)?;
-                                    }
-                                }
-                            }
-                        }
-                        FieldHeader::Field { bond_type, .. } => {
-                            reader.skip_value(bond_type)?;
-                        }
-                    }
-                }
-            }
-            FieldHeader::Field { bond_type, .. } => {
-                reader.skip_value(bond_type)?;
-            }
-        }
-    }
-
-    let ts = timestamp.ok_or(BondError::MissingField(0))?;
-    let bytes = payload.ok_or(BondError::MissingField(1))?;
-    Ok((ts, bytes))
+    let (_, timestamp, payload) = cloudstore_unwrap_envelope(data)?;
+    Ok((timestamp, payload))
 }
 
-/// Wraps an inner payload into a CloudStore binary blob with the given timestamp.
-pub fn cloudstore_wrap(timestamp: u64, inner_payload: &[u8]) -> Vec<u8> {
-    let mut writer = CompactBinaryWriter::new();
-    writer.write_marshaled_header();
-
-    // Field 0: metadata struct { field 0: bool = true }
-    writer.write_field_header(0, BondType::Struct);
-    writer.write_field_header(0, BondType::Bool);
-    writer.write_bool(true);
-    writer.write_stop(); // end metadata struct
+/// Field 1.1: data wrapper struct.
+#[derive(BondStruct, Default)]
+struct DataWrapper<'a> {
+    #[bond(id = 1, ty = "list<int8>")]
+    payload: Option<&'a [u8]>,
+    #[bond(unknown)]
+    fields: BondStruct,
+}
 
-    // Field 1: payload container struct
-    writer.write_field_header(1, BondType::Struct);
+/// Field 1: payload container struct.
+#[derive(BondStruct, Default)]
+struct PayloadContainer<'a> {
+    #[bond(id = 0, ty = "uint64")]
+    timestamp: Option<u64>,
+    #[bond(id = 1, ty = "struct")]
+    data: DataWrapper<'a>,
+    #[bond(unknown)]
+    fields: BondStruct,
+}
 
-    // Field 1.0: uint64 timestamp
-    writer.write_field_header(0, BondType::UInt64);
-    writer.write_uint64(timestamp);
+/// The outer struct of a CloudStore blob.
+#[derive(BondStruct)]
+struct CloudStoreBlob<'a> {
+    #[bond(id = 0, ty = "struct")]
+    metadata: Option<BondStruct>,
+    #[bond(id = 1, ty = "struct")]
+    container: PayloadContainer<'a>,
+    #[bond(unknown)]
+    fields: BondStruct,
+}
 
-    // Field 1.1: data wrapper struct
-    writer.write_field_header(1, BondType::Struct);
+/// Unwraps a CloudStore binary blob like [cloudstore_unwrap], additionally capturing the
+/// metadata struct and any unrecognised wrapper fields as a [CloudStoreEnvelope].
+pub fn cloudstore_unwrap_envelope(
+    data: &[u8],
+) -> Result<(CloudStoreEnvelope, u64, &[u8]), BondError> {
+    let mut reader = CompactBinaryReader::new(data);
+    reader.read_marshaled_header()?;
+    let blob = CloudStoreBlob::read_fields(&mut reader)?;
+
+    let container = blob.container;
+    let ts = container.timestamp.ok_or(BondError::MissingField(0))?;
+    let bytes = container.data.payload.ok_or(BondError::MissingField(1))?;
+    let envelope = CloudStoreEnvelope {
+        metadata: blob.metadata,
+        outer_fields: blob.fields,
+        container_fields: container.fields,
+        data_fields: container.data.fields,
+    };
+    Ok((envelope, ts, bytes))
+}
 
-    // Field 1.1.1: list<int8> = inner payload
-    // Int8 elements are stored as contiguous raw bytes — write them in bulk.
-    writer.write_field_header(1, BondType::List);
-    writer.write_container_header(BondType::Int8, inner_payload.len() as u32);
-    writer.write_raw_bytes(inner_payload);
+/// Wraps an inner payload into a CloudStore binary blob with the given timestamp.
+pub fn cloudstore_wrap(timestamp: u64, inner_payload: &[u8]) -> Vec<u8> {
+    cloudstore_wrap_envelope(&CloudStoreEnvelope::default(), timestamp, inner_payload)
+}
 
-    writer.write_stop(); // end data wrapper struct
-    writer.write_stop(); // end payload container struct
-    writer.write_stop(); // end outer struct
+/// Wraps an inner payload into a CloudStore binary blob, re-emitting the metadata and extra
+/// wrapper fields captured in `envelope`.
+pub fn cloudstore_wrap_envelope(
+    envelope: &CloudStoreEnvelope,
+    timestamp: u64,
+    inner_payload: &[u8],
+) -> Vec<u8> {
+    let blob = CloudStoreBlob {
+        metadata: envelope.metadata.clone(),
+        container: PayloadContainer {
+            timestamp: Some(timestamp),
+            data: DataWrapper {
+                payload: Some(inner_payload),
+                fields: envelope.data_fields.clone(),
+            },
+            fields: envelope.container_fields.clone(),
+        },
+        fields: envelope.outer_fields.clone(),
+    };
 
+    let mut writer = CompactBinaryWriter::new();
+    writer.write_marshaled_header();
+    blob.write_fields(&mut writer);
     writer.into_bytes()
 }
 
@@ -176,4 +183,64 @@ mod tests {
         let rewrapped = cloudstore_wrap(timestamp, inner);
         assert_eq!(rewrapped, STATE_ENABLED_BYTES);
     }
+
+    #[test]
+    fn unwrap_envelope_default_metadata() {
+        let (envelope, timestamp, _) = cloudstore_unwrap_envelope(&SETTINGS_BYTES).unwrap();
+        assert_eq!(timestamp, 1742540908);
+        assert_eq!(envelope, CloudStoreEnvelope::default());
+    }
+
+    #[test]
+    fn wrap_envelope_roundtrip_extra_fields() {
+        let envelope = CloudStoreEnvelope {
+            metadata: Some(BondStruct {
+                fields: vec![
+                    (0, BondValue::Bool(true)),
+                    (1, BondValue::String("sync".into())),
+                ],
+            }),
+            outer_fields: BondStruct {
+                fields: vec![(7, BondValue::UInt32(1))],
+            },
+            container_fields: BondStruct {
+                fields: vec![(2, BondValue::Int64(-5))],
+            },
+            data_fields: BondStruct {
+                fields: vec![(0, BondValue::UInt8(1)), (2, BondValue::Bool(false))],
+            },
+        };
+        let payload = [0x43, 0x42, 0x01, 0x00, 0x00];
+        let bytes = cloudstore_wrap_envelope(&envelope, 1742540908, &payload);
+
+        let (decoded, timestamp, inner) = cloudstore_unwrap_envelope(&bytes).unwrap();
+        assert_eq!(decoded, envelope);
+        assert_eq!(timestamp, 1742540908);
+        assert_eq!(inner, payload);
+        assert_eq!(cloudstore_wrap_envelope(&decoded, timestamp, inner), bytes);
+    }
+
+    #[test]
+    fn wrap_envelope_without_metadata() {
+        let envelope = CloudStoreEnvelope {
+            metadata: None,
+            ..CloudStoreEnvelope::default()
+        };
+        let bytes = cloudstore_wrap_envelope(&envelope, 1, &[]);
+        let (decoded, _, inner) = cloudstore_unwrap_envelope(&bytes).unwrap();
+        assert_eq!(decoded.metadata, None);
+        assert!(inner.is_empty());
+    }
+
+    #[test]
+    fn unwrap_deeply_nested_metadata() {
+        // Metadata nested 100,000 structs deep
+        let mut bytes = vec![0x43, 0x42, 0x01, 0x00];
+        bytes.extend(vec![0x0A; 100_000]);
+        bytes.extend(vec![0x00; 100_001]);
+        assert!(matches!(
+            cloudstore_unwrap(&bytes),
+            Err(BondError::DepthLimitExceeded(64))
+        ));
+    }
 }
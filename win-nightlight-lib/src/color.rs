@@ -0,0 +1,138 @@
+/// Per-channel multipliers (0.0 to 1.0) relative to the display's native white.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhitePoint {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+}
+
+impl WhitePoint {
+    /// Returns the white point as 8-bit sRGB values.
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        [self.red, self.green, self.blue].map(|c| (c * 255.0).round() as u8)
+    }
+
+    /// Returns the white point as a `#rrggbb` string.
+    pub fn to_hex(&self) -> String {
+        let [r, g, b] = self.to_rgb8();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+/// A 256-entry-per-channel gamma ramp, in the 16-bit format used by `SetDeviceGammaRamp` and
+/// X11/Wayland gamma control.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GammaRamp {
+    pub red: [u16; 256],
+    pub green: [u16; 256],
+    pub blue: [u16; 256],
+}
+
+/// Blackbody color at `kelvin` as unnormalized 0-255 channel values (Helland's fit).
+fn blackbody_rgb(kelvin: f64) -> [f64; 3] {
+    let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let green = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+
+    [red, green, blue].map(|c| c.clamp(0.0, 255.0))
+}
+
+/// Returns the white point for a color temperature, relative to 6500K.
+///
+/// The blackbody color comes from Tanner Helland's curve fit to Mitchell Charity's blackbody
+/// table (<https://tannerhelland.com/2012/09/18/convert-temperature-rgb-algorithm-code.html>),
+/// which is accurate to a few 8-bit steps between 1000K and 40000K. Windows treats 6500K as
+/// "no change", so results are normalized to the fitted 6500K color: 6500K is pure white and
+/// lower temperatures scale down green and then blue, the same way Night Light adjusts the
+/// display's gamma ramp.
+pub fn white_point(kelvin: u16) -> WhitePoint {
+    let [r, g, b] = blackbody_rgb(f64::from(kelvin));
+    let [r0, g0, b0] = blackbody_rgb(6500.0);
+    WhitePoint {
+        red: (r / r0).min(1.0),
+        green: (g / g0).min(1.0),
+        blue: (b / b0).min(1.0),
+    }
+}
+
+/// Returns the gamma ramp that tints a display to `kelvin`: each channel is a straight line
+/// from 0 to its [white_point] multiplier of full scale.
+pub fn gamma_ramp(kelvin: u16) -> GammaRamp {
+    let white = white_point(kelvin);
+    let channel = |multiplier: f64| {
+        std::array::from_fn(|i| (i as f64 / 255.0 * multiplier * 65535.0).round() as u16)
+    };
+    GammaRamp {
+        red: channel(white.red),
+        green: channel(white.green),
+        blue: channel(white.blue),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_white_points() {
+        // Helland's fit at these temperatures, normalized to its 6500K value (255, 254, 250)
+        let cases = [
+            (1200, "#ff5600"),
+            (1900, "#ff8400"),
+            (2700, "#ffa759"),
+            (3400, "#ffbe8a"),
+            (4000, "#ffcfa9"),
+            (5000, "#ffe5d2"),
+            (6000, "#fff7f2"),
+            (6500, "#ffffff"),
+        ];
+        for (kelvin, hex) in cases {
+            assert_eq!(white_point(kelvin).to_hex(), hex, "{kelvin}K");
+        }
+    }
+
+    #[test]
+    fn channels_decrease_monotonically() {
+        let mut prev = white_point(6500);
+        for kelvin in (1200..6500).rev().step_by(100) {
+            let w = white_point(kelvin);
+            assert_eq!(w.red, 1.0, "{kelvin}K");
+            assert!(w.green <= prev.green && w.blue <= prev.blue, "{kelvin}K");
+            prev = w;
+        }
+    }
+
+    #[test]
+    fn ramp_scales_each_channel() {
+        let ramp = gamma_ramp(2700);
+        let white = white_point(2700);
+        assert_eq!(ramp.red[0], 0);
+        assert_eq!(ramp.red[255], 65535);
+        assert_eq!(ramp.green[255], (white.green * 65535.0).round() as u16);
+        assert_eq!(
+            ramp.blue[128],
+            (128.0 / 255.0 * white.blue * 65535.0).round() as u16
+        );
+        assert!(ramp.blue.windows(2).all(|w| w[0] <= w[1]));
+
+        let identity = gamma_ramp(6500);
+        assert_eq!(identity.red, identity.blue);
+        assert_eq!(identity.green[255], 65535);
+    }
+}
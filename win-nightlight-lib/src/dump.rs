@@ -0,0 +1,497 @@
+#[cfg(feature = "json")]
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+#[cfg(feature = "json")]
+use serde_json::{Value, json};
+
+#[cfg(feature = "json")]
+use crate::bond::json::{self, JsonError};
+use crate::bond::*;
+use crate::cloudstore;
+
+/// A marshaled CompactBinary struct decoded without a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarshaledStruct {
+    /// Protocol version from the marshaled header
+    pub version: CompactBinaryVersion,
+    /// The fields in wire order
+    pub fields: Vec<(u16, DumpValue)>,
+}
+
+/// A struct field of a [MarshaledStruct], with nested payloads decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DumpValue {
+    /// Any value that isn't a struct or a payload, as read by [CompactBinaryReader::read_value]
+    Value(BondValue),
+    /// A nested struct, whose fields may hold payloads in turn
+    Struct(Vec<(u16, DumpValue)>),
+    /// A `list<int8>` or `list<uint8>` whose bytes are a marshaled struct themselves
+    Payload {
+        /// `Int8` or `UInt8`
+        element_type: BondType,
+        /// The decoded bytes
+        payload: MarshaledStruct,
+    },
+}
+
+/// Decodes any CloudStore blob, including the wrapper, into a tree of its fields.
+///
+/// The inner payload and any other `list<int8>` struct fields holding a marshaled
+/// CompactBinary struct are decoded recursively, as long as they re-encode to the same bytes.
+/// Payloads inside lists, sets and maps are left as bytes, and so are payloads nested deeper
+/// than [ReadLimits::max_depth] or past [PAYLOAD_BUDGET].
+pub fn dump(data: &[u8]) -> Result<MarshaledStruct, BondError> {
+    cloudstore::cloudstore_unwrap(data)?;
+    MarshaledStruct::read(data)
+}
+
+impl MarshaledStruct {
+    /// Reads a marshaled struct, decoding the payloads in its fields like [dump]. Bytes after
+    /// the struct are ignored.
+    pub fn read(data: &[u8]) -> Result<Self, BondError> {
+        Self::read_with_len(data).map(|(s, _)| s)
+    }
+
+    /// Reads a marshaled struct, also returning the number of bytes it took up.
+    fn read_with_len(data: &[u8]) -> Result<(Self, usize), BondError> {
+        let mut decoder = Decoder {
+            budget: PAYLOAD_BUDGET,
+        };
+        decoder
+            .marshaled(data, 0)
+            .map(|(marshaled, len, _)| (marshaled, len))
+    }
+
+    /// Encodes the struct with its marshaled header, re-encoding payloads with their own
+    /// versions.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = CompactBinaryWriter::with_version(self.version);
+        writer.write_marshaled_header();
+        writer.write_struct(&bond_struct(&self.fields));
+        writer.into_bytes()
+    }
+}
+
+impl DumpValue {
+    /// Returns the value with payloads encoded back into byte lists.
+    pub fn to_value(&self) -> BondValue {
+        match self {
+            DumpValue::Value(value) => value.clone(),
+            DumpValue::Struct(fields) => BondValue::Struct(bond_struct(fields)),
+            DumpValue::Payload {
+                element_type,
+                payload,
+            } => BondValue::List {
+                element_type: *element_type,
+                elements: payload
+                    .to_bytes()
+                    .into_iter()
+                    .map(|b| match element_type {
+                        BondType::UInt8 => BondValue::UInt8(b),
+                        _ => BondValue::Int8(b as i8),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl MarshaledStruct {
+    /// Converts the struct to typed JSON: `{"version": 1, "fields": [...]}`.
+    ///
+    /// Fields are written like [json::struct_to_json] writes them, except that decoded
+    /// payloads replace `value` with the payload's JSON: `{"id": 1, "type": "list<int8>",
+    /// "payload": {"version": 1, "fields": [...]}}`.
+    pub fn to_json(&self) -> Value {
+        let version = match self.version {
+            CompactBinaryVersion::V1 => 1,
+            CompactBinaryVersion::V2 => 2,
+        };
+        json!({"version": version, "fields": fields_to_json(&self.fields)})
+    }
+
+    /// Converts typed JSON from [MarshaledStruct::to_json] back to a struct.
+    pub fn from_json(value: &Value) -> Result<Self, JsonError> {
+        let version = match value.get("version").and_then(Value::as_u64) {
+            Some(1) => CompactBinaryVersion::V1,
+            Some(2) => CompactBinaryVersion::V2,
+            _ => {
+                return Err(JsonError::Unexpected {
+                    expected: "an object with \"version\": 1 or 2".into(),
+                    found: value.to_string(),
+                });
+            }
+        };
+        let fields = value.get("fields").ok_or(JsonError::MissingKey("fields"))?;
+        Ok(MarshaledStruct {
+            version,
+            fields: fields_from_json(fields)?,
+        })
+    }
+}
+
+/// Serializes as typed JSON, see [MarshaledStruct::to_json].
+#[cfg(feature = "json")]
+impl Serialize for MarshaledStruct {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+/// Deserializes typed JSON, see [MarshaledStruct::from_json].
+#[cfg(feature = "json")]
+impl<'de> Deserialize<'de> for MarshaledStruct {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_json(&Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(feature = "json")]
+fn fields_to_json(fields: &[(u16, DumpValue)]) -> Value {
+    Value::Array(
+        fields
+            .iter()
+            .map(|(id, value)| match value {
+                DumpValue::Value(value) => json::field_to_json(*id, value),
+                DumpValue::Struct(fields) => {
+                    json!({"id": id, "type": "struct", "value": fields_to_json(fields)})
+                }
+                DumpValue::Payload {
+                    element_type,
+                    payload,
+                } => json!({
+                    "id": id,
+                    "type": format!("list<{}>", element_type.name()),
+                    "payload": payload.to_json(),
+                }),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(feature = "json")]
+fn fields_from_json(value: &Value) -> Result<Vec<(u16, DumpValue)>, JsonError> {
+    json::fields_from_json(value, |field| {
+        let type_name = json::get_type(field)?;
+        if let Some(payload) = field.get("payload") {
+            let element_type = match (type_name.bond_type, type_name.args.as_slice()) {
+                (BondType::List, &[t @ (BondType::Int8 | BondType::UInt8)]) => t,
+                _ => {
+                    return Err(JsonError::Unexpected {
+                        expected: "a list<int8> or list<uint8> payload field".into(),
+                        found: Value::Object(field.clone()).to_string(),
+                    });
+                }
+            };
+            return Ok(DumpValue::Payload {
+                element_type,
+                payload: MarshaledStruct::from_json(payload)?,
+            });
+        }
+        let value = json::get(field, "value")?;
+        if type_name.bond_type == BondType::Struct {
+            return Ok(DumpValue::Struct(fields_from_json(value)?));
+        }
+        json::parse_value(&type_name, value).map(DumpValue::Value)
+    })
+}
+
+fn bond_struct(fields: &[(u16, DumpValue)]) -> BondStruct {
+    BondStruct {
+        fields: fields.iter().map(|(id, v)| (*id, v.to_value())).collect(),
+    }
+}
+
+/// Bytes of nested payloads a dump decodes in total. A payload nested in another counts
+/// toward it again, so deep nesting can't make a dump quadratic in the blob size.
+pub const PAYLOAD_BUDGET: usize = 1 << 24;
+
+/// Decodes payloads once each, writing their canonical encoding alongside so a payload can be
+/// checked against its bytes without encoding the payloads nested in it again.
+struct Decoder {
+    /// Bytes of payloads that can still be decoded
+    budget: usize,
+}
+
+impl Decoder {
+    /// Reads a marshaled struct at nesting `depth`, returning it with its length and whether
+    /// it re-encodes to the same bytes.
+    fn marshaled(
+        &mut self,
+        data: &[u8],
+        depth: usize,
+    ) -> Result<(MarshaledStruct, usize, bool), BondError> {
+        let mut reader = CompactBinaryReader::new(data);
+        reader.read_marshaled_header()?;
+        let mut writer = CompactBinaryWriter::with_version(reader.version());
+        writer.write_marshaled_header();
+        let fields = self.fields(&mut reader, &mut writer, data, depth)?;
+        let len = reader.position();
+        let marshaled = MarshaledStruct {
+            version: reader.version(),
+            fields,
+        };
+        Ok((marshaled, len, writer.into_bytes() == data[..len]))
+    }
+
+    /// Reads the fields of a struct in `data` up to BT_STOP, writing them to `writer`.
+    fn fields(
+        &mut self,
+        reader: &mut CompactBinaryReader,
+        writer: &mut CompactBinaryWriter,
+        data: &[u8],
+        depth: usize,
+    ) -> Result<Vec<(u16, DumpValue)>, BondError> {
+        let max_depth = reader.limits().max_depth;
+        if depth >= max_depth {
+            return Err(BondError::DepthLimitExceeded(max_depth));
+        }
+        reader.read_struct_begin()?;
+        let start = writer.write_struct_begin();
+        let mut fields = Vec::new();
+        loop {
+            let (id, bond_type) = match reader.read_field_header()? {
+                FieldHeader::Stop => break,
+                FieldHeader::StopBase => continue,
+                FieldHeader::Field { id, bond_type } => (id, bond_type),
+            };
+            let value = match bond_type {
+                BondType::Struct => {
+                    writer.write_field_header(id, bond_type);
+                    DumpValue::Struct(self.fields(reader, writer, data, depth + 1)?)
+                }
+                BondType::List => self.list(reader, writer, data, id, depth)?,
+                _ => {
+                    let value = reader.read_value(bond_type)?;
+                    writer.write_field(id, &value);
+                    DumpValue::Value(value)
+                }
+            };
+            fields.push((id, value));
+        }
+        reader.read_struct_end()?;
+        writer.write_struct_end(start);
+        Ok(fields)
+    }
+
+    /// Reads a list field, decoding it as a payload if its bytes are one.
+    fn list(
+        &mut self,
+        reader: &mut CompactBinaryReader,
+        writer: &mut CompactBinaryWriter,
+        data: &[u8],
+        id: u16,
+        depth: usize,
+    ) -> Result<DumpValue, BondError> {
+        let start = reader.position();
+        reader.skip_value(BondType::List)?;
+        let list = &data[start..reader.position()];
+        let mut list_reader = CompactBinaryReader::with_version(list, reader.version());
+        list_reader.set_limits(reader.limits());
+
+        let (element_type, count) = list_reader.read_container_header()?;
+        if matches!(element_type, BondType::Int8 | BondType::UInt8) {
+            let bytes = &list[list_reader.position()..];
+            if let Some(payload) = self.payload(bytes, depth + 1) {
+                // The payload re-encodes to its bytes, so they can be written as they are
+                writer.write_field_header(id, BondType::List);
+                writer.write_container_header(element_type, count);
+                writer.write_raw_bytes(bytes);
+                return Ok(DumpValue::Payload {
+                    element_type,
+                    payload,
+                });
+            }
+        }
+
+        let mut list_reader = CompactBinaryReader::with_version(list, reader.version());
+        list_reader.set_limits(reader.limits());
+        let value = list_reader.read_value(BondType::List)?;
+        writer.write_field(id, &value);
+        Ok(DumpValue::Value(value))
+    }
+
+    /// Decodes the bytes of a `list<int8>` if they are exactly one marshaled CompactBinary
+    /// struct that re-encodes to the same bytes, within the budget.
+    fn payload(&mut self, bytes: &[u8], depth: usize) -> Option<MarshaledStruct> {
+        if bytes.len() > self.budget
+            || !matches!(sniff_protocol(bytes), Some(Protocol::CompactBinary(_)))
+        {
+            return None;
+        }
+        self.budget -= bytes.len();
+        let (marshaled, len, canonical) = self.marshaled(bytes, depth).ok()?;
+        (len == bytes.len() && canonical).then_some(marshaled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The settings test bytes from nightlight_settings.rs
+    const SETTINGS_BYTES: [u8; 60] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x26, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14,
+        0x0E, 0x01, 0x2E, 0x0F, 0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xCA, 0x32, 0x0E,
+        0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    fn field(fields: &[(u16, DumpValue)], id: u16) -> &DumpValue {
+        &fields.iter().find(|(fid, _)| *fid == id).unwrap().1
+    }
+
+    fn int8_list(bytes: &[u8]) -> BondValue {
+        BondValue::List {
+            element_type: BondType::Int8,
+            elements: bytes.iter().map(|b| BondValue::Int8(*b as i8)).collect(),
+        }
+    }
+
+    #[test]
+    fn dump_settings() {
+        let dumped = dump(&SETTINGS_BYTES).unwrap();
+        assert_eq!(dumped.version, CompactBinaryVersion::V1);
+        let DumpValue::Struct(container) = field(&dumped.fields, 1) else {
+            panic!("expected the payload container struct");
+        };
+        assert_eq!(
+            field(container, 0),
+            &DumpValue::Value(BondValue::UInt64(1742540908))
+        );
+        let DumpValue::Struct(data) = field(container, 1) else {
+            panic!("expected the data wrapper struct");
+        };
+        let DumpValue::Payload {
+            element_type: BondType::Int8,
+            payload,
+        } = field(data, 1)
+        else {
+            panic!("expected a decoded payload");
+        };
+        assert_eq!(
+            field(&payload.fields, 40),
+            &DumpValue::Value(BondValue::Int16(2790))
+        );
+        assert_eq!(dumped.to_bytes(), SETTINGS_BYTES);
+    }
+
+    #[test]
+    fn other_byte_lists_stay_bytes() {
+        // Not marshaled, marshaled with trailing bytes, and a truncated payload
+        for bytes in [
+            &[0x01, 0x02, 0x03][..],
+            &[0x43, 0x42, 0x01, 0x00, 0x00, 0x00],
+            &[0x43, 0x42, 0x01, 0x00, 0x02],
+        ] {
+            let mut writer = CompactBinaryWriter::new();
+            writer.write_marshaled_header();
+            writer.write_struct(&BondStruct {
+                fields: vec![(3, int8_list(bytes))],
+            });
+            let data = writer.into_bytes();
+            let dumped = MarshaledStruct::read(&data).unwrap();
+            assert_eq!(dumped.fields, [(3, DumpValue::Value(int8_list(bytes)))]);
+            assert_eq!(dumped.to_bytes(), data);
+        }
+    }
+
+    #[test]
+    fn nested_payload_versions() {
+        let mut inner = CompactBinaryWriter::with_version(CompactBinaryVersion::V2);
+        inner.write_marshaled_header();
+        inner.write_struct(&BondStruct {
+            fields: vec![(0, BondValue::String("hi".into()))],
+        });
+        let inner = inner.into_bytes();
+
+        let mut outer = CompactBinaryWriter::new();
+        outer.write_marshaled_header();
+        outer.write_struct(&BondStruct {
+            fields: vec![(1, int8_list(&inner))],
+        });
+        let data = outer.into_bytes();
+
+        let dumped = MarshaledStruct::read(&data).unwrap();
+        let DumpValue::Payload { payload, .. } = field(&dumped.fields, 1) else {
+            panic!("expected a decoded payload");
+        };
+        assert_eq!(payload.version, CompactBinaryVersion::V2);
+        assert_eq!(dumped.to_bytes(), data);
+    }
+
+    #[test]
+    fn deeply_nested_payloads() {
+        // Each payload is the only field of the next, thousands of levels deep
+        let mut data = vec![0x43, 0x42, 0x01, 0x00, 0x00];
+        for _ in 0..5_000 {
+            let mut outer = vec![0x43, 0x42, 0x01, 0x00, 0x0B, 0x0E];
+            varint::write_varint(&mut outer, data.len() as u64);
+            outer.extend(&data);
+            outer.push(0x00);
+            data = outer;
+        }
+
+        let dumped = MarshaledStruct::read(&data).unwrap();
+        let mut levels = 0;
+        let mut fields = &dumped.fields;
+        while let [(0, DumpValue::Payload { payload, .. })] = fields.as_slice() {
+            levels += 1;
+            fields = &payload.fields;
+        }
+        assert_eq!(levels, ReadLimits::default().max_depth - 1);
+        assert!(matches!(fields.as_slice(), [(0, DumpValue::Value(_))]));
+        assert_eq!(dumped.to_bytes(), data);
+    }
+
+    #[test]
+    fn rejects_other_blobs() {
+        let mut writer = CompactBinaryWriter::new();
+        writer.write_marshaled_header();
+        writer.write_struct(&BondStruct::new());
+        // A marshaled struct, but not a CloudStore blob
+        assert!(matches!(
+            dump(&writer.into_bytes()),
+            Err(BondError::MissingField(0))
+        ));
+        assert!(dump(&SETTINGS_BYTES[..30]).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn settings_json() {
+        let json = dump(&SETTINGS_BYTES).unwrap().to_json();
+        let payload = &json["fields"][1]["value"][1]["value"][0];
+        assert_eq!(payload["type"], "list<int8>");
+        assert_eq!(
+            payload["payload"]["fields"][4],
+            json!({"id": 40, "type": "int16", "value": 2790})
+        );
+        assert_eq!(
+            payload["payload"]["fields"][2],
+            json!({"id": 20, "type": "struct", "value": [
+                {"id": 0, "type": "int8", "value": 1},
+                {"id": 1, "type": "int8", "value": 15},
+            ]})
+        );
+        let text = serde_json::to_string_pretty(&json).unwrap();
+        let parsed: MarshaledStruct = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed.to_bytes(), SETTINGS_BYTES);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn invalid_json() {
+        let parse =
+            |fields: Value| MarshaledStruct::from_json(&json!({"version": 1, "fields": fields}));
+        assert!(parse(json!([{"id": 0, "type": "int8", "value": 1}])).is_ok());
+        assert!(MarshaledStruct::from_json(&json!({"version": 3, "fields": []})).is_err());
+        assert!(MarshaledStruct::from_json(&json!({"version": 1})).is_err());
+        let payload = json!({"version": 1, "fields": []});
+        assert!(parse(json!([{"id": 0, "type": "list<int8>", "payload": payload}])).is_ok());
+        assert!(matches!(
+            parse(json!([{"id": 0, "type": "list<string>", "payload": payload}])),
+            Err(JsonError::Field { id: 0, .. })
+        ));
+    }
+}
@@ -0,0 +1,267 @@
+use std::fmt;
+
+use chrono::{Days, NaiveDateTime, NaiveTime};
+
+use crate::nightlight_settings::{NightlightSettings, ScheduleMode};
+use crate::nightlight_state::NightlightState;
+
+/// Why Night Light is (or isn't) active at a given moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActiveReason {
+    /// The state blob has Night Light toggled on, which overrides the schedule.
+    ManuallyEnabled,
+    /// Not toggled on and no schedule is set.
+    ScheduleOff,
+    /// Inside the `start_time`..`end_time` window of a [ScheduleMode::SetHours] schedule.
+    InsideSetHours,
+    /// Outside the `start_time`..`end_time` window of a [ScheduleMode::SetHours] schedule.
+    OutsideSetHours,
+    /// Between sunset and sunrise with a [ScheduleMode::SunsetToSunrise] schedule.
+    Night,
+    /// Between sunrise and sunset with a [ScheduleMode::SunsetToSunrise] schedule.
+    Day,
+}
+
+impl ActiveReason {
+    /// A stable identifier for machine-readable output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActiveReason::ManuallyEnabled => "manually_enabled",
+            ActiveReason::ScheduleOff => "schedule_off",
+            ActiveReason::InsideSetHours => "inside_set_hours",
+            ActiveReason::OutsideSetHours => "outside_set_hours",
+            ActiveReason::Night => "night",
+            ActiveReason::Day => "day",
+        }
+    }
+}
+
+impl fmt::Display for ActiveReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActiveReason::ManuallyEnabled => write!(f, "turned on manually"),
+            ActiveReason::ScheduleOff => write!(f, "no schedule set"),
+            ActiveReason::InsideSetHours => write!(f, "within the scheduled hours"),
+            ActiveReason::OutsideSetHours => write!(f, "outside the scheduled hours"),
+            ActiveReason::Night => write!(f, "between sunset and sunrise"),
+            ActiveReason::Day => write!(f, "between sunrise and sunset"),
+        }
+    }
+}
+
+/// Whether Night Light is actually active, combining the state toggle and the schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EffectiveStatus {
+    pub active: bool,
+    pub reason: ActiveReason,
+    /// The next local time at which the schedule flips `active`, or `None` if it won't change
+    /// on its own (manually enabled, or no schedule).
+    pub next_transition: Option<NaiveDateTime>,
+}
+
+/// Returns whether `now` falls in the daily window `start..end`, which wraps past midnight
+/// when `end` is before `start`. An empty window (`start == end`) never matches.
+fn in_window(now: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        start <= now && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+/// Returns the first local date and time strictly after `now` whose time of day is `at`.
+fn next_occurrence(now: NaiveDateTime, at: NaiveTime) -> NaiveDateTime {
+    let today = now.date().and_time(at);
+    if today > now {
+        today
+    } else {
+        today + Days::new(1)
+    }
+}
+
+/// Computes the effective Night Light status at the local time `now`.
+///
+/// Being toggled on in the state blob forces Night Light on regardless of the schedule.
+/// Otherwise the schedule decides: [ScheduleMode::SetHours] is active from `start_time` until
+/// `end_time`, and [ScheduleMode::SunsetToSunrise] from `sunset_time` until `sunrise_time`,
+/// both wrapping past midnight when the end is earlier in the day than the start. The sunset
+/// and sunrise times are the ones Windows last stored, so they can be a day or so stale.
+pub fn effective_status(
+    settings: &NightlightSettings,
+    state: &NightlightState,
+    now: NaiveDateTime,
+) -> EffectiveStatus {
+    if state.is_enabled {
+        return EffectiveStatus {
+            active: true,
+            reason: ActiveReason::ManuallyEnabled,
+            next_transition: None,
+        };
+    }
+
+    let (start, end, inside, outside) = match settings.schedule_mode {
+        ScheduleMode::Off => {
+            return EffectiveStatus {
+                active: false,
+                reason: ActiveReason::ScheduleOff,
+                next_transition: None,
+            };
+        }
+        ScheduleMode::SetHours => (
+            settings.start_time,
+            settings.end_time,
+            ActiveReason::InsideSetHours,
+            ActiveReason::OutsideSetHours,
+        ),
+        ScheduleMode::SunsetToSunrise => (
+            settings.sunset_time,
+            settings.sunrise_time,
+            ActiveReason::Night,
+            ActiveReason::Day,
+        ),
+    };
+
+    if start == end {
+        return EffectiveStatus {
+            active: false,
+            reason: outside,
+            next_transition: None,
+        };
+    }
+    if in_window(now.time(), start, end) {
+        EffectiveStatus {
+            active: true,
+            reason: inside,
+            next_transition: Some(next_occurrence(now, end)),
+        }
+    } else {
+        EffectiveStatus {
+            active: false,
+            reason: outside,
+            next_transition: Some(next_occurrence(now, start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::nightlight_settings::SettingsEncoding;
+    use crate::nightlight_state::StateEncoding;
+    use crate::{BondStruct, CloudStoreEnvelope};
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn at(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, day)
+            .unwrap()
+            .and_time(time(h, m))
+    }
+
+    fn settings(
+        schedule_mode: ScheduleMode,
+        start: NaiveTime,
+        end: NaiveTime,
+    ) -> NightlightSettings {
+        NightlightSettings {
+            timestamp: 1742540908,
+            schedule_mode,
+            color_temperature: 2790,
+            start_time: start,
+            end_time: end,
+            sunset_time: time(19, 23),
+            sunrise_time: time(7, 12),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        }
+    }
+
+    fn state(is_enabled: bool) -> NightlightState {
+        NightlightState {
+            timestamp: 1742670473,
+            is_enabled,
+            initialized: 1,
+            last_transition_filetime: 133_871_411_809_270_569,
+            unknown_fields: BondStruct::new(),
+            encoding: StateEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        }
+    }
+
+    #[test]
+    fn manually_enabled_overrides_schedule() {
+        let s = settings(ScheduleMode::SetHours, time(22, 0), time(7, 0));
+        let status = effective_status(&s, &state(true), at(22, 12, 0));
+        assert!(status.active);
+        assert_eq!(status.reason, ActiveReason::ManuallyEnabled);
+        assert_eq!(status.next_transition, None);
+    }
+
+    #[test]
+    fn schedule_off() {
+        let s = settings(ScheduleMode::Off, time(22, 0), time(7, 0));
+        let status = effective_status(&s, &state(false), at(22, 23, 0));
+        assert!(!status.active);
+        assert_eq!(status.reason, ActiveReason::ScheduleOff);
+        assert_eq!(status.next_transition, None);
+    }
+
+    #[test]
+    fn set_hours_across_midnight() {
+        let s = settings(ScheduleMode::SetHours, time(22, 0), time(7, 0));
+        let cases = [
+            (at(22, 21, 59), false, at(22, 22, 0)),
+            (at(22, 22, 0), true, at(23, 7, 0)),
+            (at(22, 23, 30), true, at(23, 7, 0)),
+            (at(23, 6, 59), true, at(23, 7, 0)),
+            (at(23, 7, 0), false, at(23, 22, 0)),
+        ];
+        for (now, active, next) in cases {
+            let status = effective_status(&s, &state(false), now);
+            assert_eq!(status.active, active, "at {now}");
+            assert_eq!(status.next_transition, Some(next), "at {now}");
+        }
+    }
+
+    #[test]
+    fn set_hours_within_day() {
+        let s = settings(ScheduleMode::SetHours, time(1, 15), time(6, 0));
+        let status = effective_status(&s, &state(false), at(22, 3, 0));
+        assert!(status.active);
+        assert_eq!(status.reason, ActiveReason::InsideSetHours);
+        assert_eq!(status.next_transition, Some(at(22, 6, 0)));
+
+        let status = effective_status(&s, &state(false), at(22, 23, 0));
+        assert!(!status.active);
+        assert_eq!(status.reason, ActiveReason::OutsideSetHours);
+        assert_eq!(status.next_transition, Some(at(23, 1, 15)));
+    }
+
+    #[test]
+    fn sunset_to_sunrise() {
+        // Start/end times are ignored in this mode
+        let s = settings(ScheduleMode::SunsetToSunrise, time(1, 15), time(6, 0));
+        let status = effective_status(&s, &state(false), at(22, 20, 0));
+        assert!(status.active);
+        assert_eq!(status.reason, ActiveReason::Night);
+        assert_eq!(status.next_transition, Some(at(23, 7, 12)));
+
+        let status = effective_status(&s, &state(false), at(22, 12, 0));
+        assert!(!status.active);
+        assert_eq!(status.reason, ActiveReason::Day);
+        assert_eq!(status.next_transition, Some(at(22, 19, 23)));
+    }
+
+    #[test]
+    fn empty_window_is_never_active() {
+        let s = settings(ScheduleMode::SetHours, time(0, 0), time(0, 0));
+        let status = effective_status(&s, &state(false), at(22, 0, 0));
+        assert!(!status.active);
+        assert_eq!(status.next_transition, None);
+    }
+}
@@ -0,0 +1,687 @@
+use std::fmt;
+
+use crate::NightlightBlob;
+use crate::bond::*;
+
+/// Name of a field in one of the known schemas.
+struct FieldName {
+    id: u16,
+    name: &'static str,
+    /// Unit or meaning appended to the value
+    note: Option<&'static str>,
+    nested: Nested,
+}
+
+/// What a field's struct or `list<int8>` value contains.
+#[derive(Clone, Copy)]
+enum Nested {
+    None,
+    Struct(&'static [FieldName]),
+    /// The inner marshaled payload of a CloudStore wrapper
+    Payload,
+}
+
+const fn field(id: u16, name: &'static str) -> FieldName {
+    FieldName {
+        id,
+        name,
+        note: None,
+        nested: Nested::None,
+    }
+}
+
+const fn noted(id: u16, name: &'static str, note: &'static str) -> FieldName {
+    FieldName {
+        id,
+        name,
+        note: Some(note),
+        nested: Nested::None,
+    }
+}
+
+const fn nested(id: u16, name: &'static str, nested: Nested) -> FieldName {
+    FieldName {
+        id,
+        name,
+        note: None,
+        nested,
+    }
+}
+
+const ENVELOPE: &[FieldName] = &[
+    field(0, "metadata"),
+    nested(1, "payload container", Nested::Struct(CONTAINER)),
+];
+const CONTAINER: &[FieldName] = &[
+    noted(0, "timestamp", "Unix seconds"),
+    nested(1, "data wrapper", Nested::Struct(DATA_WRAPPER)),
+];
+const DATA_WRAPPER: &[FieldName] = &[nested(1, "payload", Nested::Payload)];
+
+const TIME: &[FieldName] = &[field(0, "hour"), field(1, "minute")];
+const SETTINGS: &[FieldName] = &[
+    field(0, "schedule_enabled"),
+    noted(
+        10,
+        "set_hours_mode",
+        "value irrelevant; presence = set hours",
+    ),
+    nested(20, "schedule_start_time", Nested::Struct(TIME)),
+    nested(30, "schedule_end_time", Nested::Struct(TIME)),
+    noted(40, "color_temperature", "Kelvin"),
+    nested(50, "sunset_time", Nested::Struct(TIME)),
+    nested(60, "sunrise_time", Nested::Struct(TIME)),
+];
+const STATE: &[FieldName] = &[
+    noted(0, "is_enabled", "value irrelevant; presence = enabled"),
+    field(10, "initialized"),
+    noted(20, "last_transition_filetime", "FILETIME"),
+];
+
+/// What a line of an [Explanation] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationKind {
+    /// A heading for the part of the blob that follows; covers no bytes
+    Section,
+    /// Framing: marshaled headers, field headers, container headers and BT_STOP
+    Header,
+    /// A field or element value
+    Value,
+    /// Bytes that couldn't be parsed, or that follow the outer struct
+    Unparsed,
+}
+
+/// A span of a blob and what it means.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Offset of the first byte in the blob
+    pub offset: usize,
+    /// Number of bytes covered
+    pub len: usize,
+    /// How deeply nested the bytes are in structs, containers and inner payloads
+    pub depth: usize,
+    /// What kind of bytes these are
+    pub kind: AnnotationKind,
+    /// The description, e.g. `Field 40, BT_INT16 (color_temperature)`
+    pub text: String,
+}
+
+/// An annotated listing of every byte of a CloudStore blob, in the style of the walkthrough in
+/// `docs/nightlight-registry-format.md`.
+///
+/// Render it with [fmt::Display]; each line shows the offset, the bytes and their meaning.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation<'a> {
+    /// The explained blob
+    pub data: &'a [u8],
+    /// The schema used for the inner payload's field names, if known
+    pub blob: Option<NightlightBlob>,
+    /// Annotations in byte order
+    pub annotations: Vec<Annotation>,
+    /// Why parsing stopped early, if it did. The rest of the blob is annotated as unparsed.
+    pub error: Option<String>,
+}
+
+/// Walks `data` as a CloudStore blob and annotates every byte.
+///
+/// Field names of the inner payload come from the settings or state schema given by `blob`.
+/// With `None`, the schema is guessed from the inner payload's fields, and unrecognized
+/// payloads are explained without names. Malformed blobs are explained up to the first error.
+pub fn explain(data: &[u8], blob: Option<NightlightBlob>) -> Explanation<'_> {
+    let blob = blob.or_else(|| NightlightBlob::detect(data));
+    let mut walker = Walker {
+        payload: match blob {
+            Some(NightlightBlob::Settings) => SETTINGS,
+            Some(NightlightBlob::State) => STATE,
+            None => &[],
+        },
+        payload_label: match blob {
+            Some(NightlightBlob::Settings) => "Settings payload",
+            Some(NightlightBlob::State) => "State payload",
+            None => "Inner payload",
+        },
+        annotations: Vec::new(),
+        max_depth: ReadLimits::default().max_depth,
+    };
+
+    walker.section(0, 0, "CloudStore wrapper".into());
+    let error = walker
+        .marshaled(data, 0, 0, ENVELOPE)
+        .err()
+        .map(|(pos, e)| {
+            walker.unparsed(data, pos, 0, format!("Unparsed: {e}"));
+            e.to_string()
+        });
+
+    Explanation {
+        data,
+        blob,
+        annotations: walker.annotations,
+        error,
+    }
+}
+
+/// Returns the protocol name of a type, e.g. `BT_INT16`.
+fn type_name(bond_type: BondType) -> &'static str {
+    match bond_type {
+        BondType::Bool => "BT_BOOL",
+        BondType::UInt8 => "BT_UINT8",
+        BondType::UInt16 => "BT_UINT16",
+        BondType::UInt32 => "BT_UINT32",
+        BondType::UInt64 => "BT_UINT64",
+        BondType::Float => "BT_FLOAT",
+        BondType::Double => "BT_DOUBLE",
+        BondType::String => "BT_STRING",
+        BondType::Struct => "BT_STRUCT",
+        BondType::List => "BT_LIST",
+        BondType::Set => "BT_SET",
+        BondType::Map => "BT_MAP",
+        BondType::Int8 => "BT_INT8",
+        BondType::Int16 => "BT_INT16",
+        BondType::Int32 => "BT_INT32",
+        BondType::Int64 => "BT_INT64",
+        BondType::WString => "BT_WSTRING",
+    }
+}
+
+/// Describes a primitive value the way it's encoded, e.g. `zigzag varint = 2790`.
+fn describe(value: &BondValue) -> String {
+    match value {
+        BondValue::Bool(v) => v.to_string(),
+        BondValue::UInt8(v) => v.to_string(),
+        BondValue::Int8(v) => v.to_string(),
+        BondValue::UInt16(v) => format!("varint = {v}"),
+        BondValue::UInt32(v) => format!("varint = {v}"),
+        BondValue::UInt64(v) => format!("varint = {v}"),
+        BondValue::Int16(v) => format!("zigzag varint = {v}"),
+        BondValue::Int32(v) => format!("zigzag varint = {v}"),
+        BondValue::Int64(v) => format!("zigzag varint = {v}"),
+        BondValue::Float(v) => v.to_string(),
+        BondValue::Double(v) => v.to_string(),
+        BondValue::String(v) | BondValue::WString(v) => format!("{v:?}"),
+        BondValue::Struct(_)
+        | BondValue::List { .. }
+        | BondValue::Set { .. }
+        | BondValue::Map { .. } => unreachable!("only primitives are described"),
+    }
+}
+
+/// A parse error and the blob offset it happened at.
+type WalkError = (usize, BondError);
+
+struct Walker {
+    payload: &'static [FieldName],
+    payload_label: &'static str,
+    annotations: Vec<Annotation>,
+    /// The walker recurses on its own rather than through [CompactBinaryReader::read_value],
+    /// so it enforces the reader's depth limit itself
+    max_depth: usize,
+}
+
+impl Walker {
+    fn push(
+        &mut self,
+        offset: usize,
+        len: usize,
+        depth: usize,
+        kind: AnnotationKind,
+        text: String,
+    ) {
+        self.annotations.push(Annotation {
+            offset,
+            len,
+            depth,
+            kind,
+            text,
+        });
+    }
+
+    fn section(&mut self, offset: usize, depth: usize, text: String) {
+        self.push(offset, 0, depth, AnnotationKind::Section, text);
+    }
+
+    fn unparsed(&mut self, data: &[u8], offset: usize, depth: usize, text: String) {
+        if offset < data.len() {
+            self.push(
+                offset,
+                data.len() - offset,
+                depth,
+                AnnotationKind::Unparsed,
+                text,
+            );
+        }
+    }
+
+    /// Walks a marshaled struct in `data`, which starts at blob offset `base`.
+    fn marshaled(
+        &mut self,
+        data: &[u8],
+        base: usize,
+        depth: usize,
+        names: &[FieldName],
+    ) -> Result<(), WalkError> {
+        let mut reader = CompactBinaryReader::new(data);
+        reader.read_marshaled_header().map_err(|e| (base, e))?;
+        let version = match reader.version() {
+            CompactBinaryVersion::V1 => 1,
+            CompactBinaryVersion::V2 => 2,
+        };
+        self.push(
+            base,
+            4,
+            depth,
+            AnnotationKind::Header,
+            format!("Marshaled header: CB v{version}"),
+        );
+        self.struct_fields(&mut reader, base, depth, names)?;
+        if reader.remaining() > 0 {
+            let offset = base + reader.position();
+            self.push(
+                offset,
+                reader.remaining(),
+                depth,
+                AnnotationKind::Unparsed,
+                "Trailing bytes after the struct".into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Walks a struct's fields up to and including BT_STOP.
+    fn struct_fields(
+        &mut self,
+        reader: &mut CompactBinaryReader,
+        base: usize,
+        depth: usize,
+        names: &[FieldName],
+    ) -> Result<(), WalkError> {
+        if reader.version() == CompactBinaryVersion::V2 {
+            let start = reader.position();
+            let len = reader.read_uint32().map_err(|e| (base + start, e))?;
+            self.push(
+                base + start,
+                reader.position() - start,
+                depth,
+                AnnotationKind::Header,
+                format!("Struct length = {len}"),
+            );
+        }
+
+        loop {
+            let start = reader.position();
+            let header = reader.read_field_header().map_err(|e| (base + start, e))?;
+            let len = reader.position() - start;
+            match header {
+                FieldHeader::Stop => {
+                    self.push(
+                        base + start,
+                        len,
+                        depth,
+                        AnnotationKind::Header,
+                        "BT_STOP".into(),
+                    );
+                    return Ok(());
+                }
+                FieldHeader::StopBase => {
+                    self.push(
+                        base + start,
+                        len,
+                        depth,
+                        AnnotationKind::Header,
+                        "BT_STOP_BASE".into(),
+                    );
+                }
+                FieldHeader::Field { id, bond_type } => {
+                    let name = names.iter().find(|f| f.id == id);
+                    let text = match name {
+                        Some(f) => format!("Field {id}, {} ({})", type_name(bond_type), f.name),
+                        None => format!("Field {id}, {}", type_name(bond_type)),
+                    };
+                    self.push(base + start, len, depth, AnnotationKind::Header, text);
+                    self.value(reader, base, depth, bond_type, name)?;
+                }
+            }
+        }
+    }
+
+    /// Walks one value of `bond_type`, for the named field if it's known.
+    fn value(
+        &mut self,
+        reader: &mut CompactBinaryReader,
+        base: usize,
+        depth: usize,
+        bond_type: BondType,
+        name: Option<&FieldName>,
+    ) -> Result<(), WalkError> {
+        let nested = name.map_or(Nested::None, |f| f.nested);
+        let container = matches!(
+            bond_type,
+            BondType::Struct | BondType::List | BondType::Set | BondType::Map
+        );
+        if container && depth >= self.max_depth {
+            return Err((
+                base + reader.position(),
+                BondError::DepthLimitExceeded(self.max_depth),
+            ));
+        }
+
+        match bond_type {
+            BondType::Struct => {
+                let names = match nested {
+                    Nested::Struct(names) => names,
+                    _ => &[],
+                };
+                self.struct_fields(reader, base, depth + 1, names)
+            }
+            BondType::List | BondType::Set => {
+                let start = reader.position();
+                let (element_type, count) = reader
+                    .read_container_header()
+                    .map_err(|e| (base + start, e))?;
+                self.push(
+                    base + start,
+                    reader.position() - start,
+                    depth,
+                    AnnotationKind::Header,
+                    format!(
+                        "element type = {}, count = {count}",
+                        type_name(element_type)
+                    ),
+                );
+
+                if matches!(element_type, BondType::Int8 | BondType::UInt8) {
+                    let start = reader.position();
+                    let bytes = reader
+                        .read_bytes_slice(count as usize)
+                        .map_err(|e| (base + start, e))?;
+                    if bytes.starts_with(&COMPACT_BINARY_MAGIC) {
+                        let names = match nested {
+                            Nested::Payload => self.payload,
+                            _ => &[],
+                        };
+                        let label = match nested {
+                            Nested::Payload => self.payload_label,
+                            _ => "Nested payload",
+                        };
+                        self.section(
+                            base + start,
+                            depth + 1,
+                            format!("{label} ({count} bytes, itself a marshaled CB struct)"),
+                        );
+                        // The list's bytes are already consumed, so a broken inner payload
+                        // doesn't stop the outer walk
+                        if let Err((pos, e)) = self.marshaled(bytes, base + start, depth + 1, names)
+                        {
+                            let end = base + start + bytes.len();
+                            if pos < end {
+                                self.push(
+                                    pos,
+                                    end - pos,
+                                    depth + 1,
+                                    AnnotationKind::Unparsed,
+                                    format!("Unparsed: {e}"),
+                                );
+                            }
+                        }
+                        self.section(base + reader.position(), depth, String::new());
+                    } else if count > 0 {
+                        self.push(
+                            base + start,
+                            bytes.len(),
+                            depth + 1,
+                            AnnotationKind::Value,
+                            format!("{count} bytes"),
+                        );
+                    }
+                    return Ok(());
+                }
+
+                for _ in 0..count {
+                    self.value(reader, base, depth + 1, element_type, None)?;
+                }
+                Ok(())
+            }
+            BondType::Map => {
+                let start = reader.position();
+                let (key_type, value_type, count) =
+                    reader.read_map_header().map_err(|e| (base + start, e))?;
+                self.push(
+                    base + start,
+                    reader.position() - start,
+                    depth,
+                    AnnotationKind::Header,
+                    format!(
+                        "key type = {}, value type = {}, count = {count}",
+                        type_name(key_type),
+                        type_name(value_type)
+                    ),
+                );
+                for _ in 0..count {
+                    self.value(reader, base, depth + 1, key_type, None)?;
+                    self.value(reader, base, depth + 1, value_type, None)?;
+                }
+                Ok(())
+            }
+            _ => {
+                let start = reader.position();
+                let value = reader
+                    .read_value(bond_type)
+                    .map_err(|e| (base + start, e))?;
+                let text = match name.and_then(|f| f.note) {
+                    Some(note) => format!("{} ({note})", describe(&value)),
+                    None => describe(&value),
+                };
+                self.push(
+                    base + start,
+                    reader.position() - start,
+                    depth,
+                    AnnotationKind::Value,
+                    text,
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Bytes shown per line; longer spans continue on the following lines.
+const BYTES_PER_LINE: usize = 8;
+/// Width of the hex column, before indentation.
+const HEX_WIDTH: usize = BYTES_PER_LINE * 3 + 1;
+
+impl fmt::Display for Explanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for a in &self.annotations {
+            let indent = "  ".repeat(a.depth);
+            if a.kind == AnnotationKind::Section {
+                if a.text.is_empty() {
+                    writeln!(f)?;
+                } else {
+                    writeln!(f, "{:6}{indent}-- {} --", "", a.text)?;
+                }
+                continue;
+            }
+
+            let bytes = &self.data[a.offset..a.offset + a.len];
+            let text_indent = if a.kind == AnnotationKind::Value {
+                "  "
+            } else {
+                ""
+            };
+            for (i, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+                let hex = chunk
+                    .iter()
+                    .map(|b| format!("{b:02X}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let text = if i == 0 { a.text.as_str() } else { "" };
+                let line = format!(
+                    "{:04X}  {indent}{hex:HEX_WIDTH$}{text_indent}{text}",
+                    a.offset + i * BYTES_PER_LINE
+                );
+                writeln!(f, "{}", line.trim_end())?;
+            }
+        }
+        write!(f, "\nTotal: {} bytes.", self.data.len())?;
+        if let Some(e) = &self.error {
+            write!(f, " Parsing stopped early: {e}")?;
+        }
+        writeln!(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The settings test bytes from nightlight_settings.rs
+    const SETTINGS_BYTES: [u8; 60] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x26, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14,
+        0x0E, 0x01, 0x2E, 0x0F, 0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xCA, 0x32, 0x0E,
+        0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    // The enabled state test bytes from nightlight_state.rs
+    const STATE_BYTES: [u8; 43] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0x89, 0x95, 0xFC, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x15, 0x43, 0x42, 0x01, 0x00, 0x10, 0x00, 0xD0, 0x0A, 0x02, 0xC6, 0x14,
+        0xA9, 0xF6, 0xE2, 0xD3, 0xEF, 0xEA, 0xE6, 0xED, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn settings_walkthrough() {
+        let explanation = explain(&SETTINGS_BYTES, Some(NightlightBlob::Settings));
+        assert_eq!(explanation.error, None);
+        assert_eq!(
+            explanation.to_string().lines().collect::<Vec<_>>(),
+            [
+                "      -- CloudStore wrapper --",
+                "0000  43 42 01 00              Marshaled header: CB v1",
+                "0004  0A                       Field 0, BT_STRUCT (metadata)",
+                "0005    02                       Field 0, BT_BOOL",
+                "0006    01                         true",
+                "0007    00                       BT_STOP",
+                "0008  2A                       Field 1, BT_STRUCT (payload container)",
+                "0009    06                       Field 0, BT_UINT64 (timestamp)",
+                "000A    EC A0 F4 BE 06             varint = 1742540908 (Unix seconds)",
+                "000F    2A                       Field 1, BT_STRUCT (data wrapper)",
+                "0010      2B                       Field 1, BT_LIST (payload)",
+                "0011      0E 26                    element type = BT_INT8, count = 38",
+                "            -- Settings payload (38 bytes, itself a marshaled CB struct) --",
+                "0013        43 42 01 00              Marshaled header: CB v1",
+                "0017        02                       Field 0, BT_BOOL (schedule_enabled)",
+                "0018        01                         true",
+                "0019        C2 0A                    Field 10, BT_BOOL (set_hours_mode)",
+                "001B        00                         false (value irrelevant; presence = set hours)",
+                "001C        CA 14                    Field 20, BT_STRUCT (schedule_start_time)",
+                "001E          0E                       Field 0, BT_INT8 (hour)",
+                "001F          01                         1",
+                "0020          2E                       Field 1, BT_INT8 (minute)",
+                "0021          0F                         15",
+                "0022          00                       BT_STOP",
+                "0023        CA 1E                    Field 30, BT_STRUCT (schedule_end_time)",
+                "0025          00                       BT_STOP",
+                "0026        CF 28                    Field 40, BT_INT16 (color_temperature)",
+                "0028        CC 2B                      zigzag varint = 2790 (Kelvin)",
+                "002A        CA 32                    Field 50, BT_STRUCT (sunset_time)",
+                "002C          0E                       Field 0, BT_INT8 (hour)",
+                "002D          13                         19",
+                "002E          2E                       Field 1, BT_INT8 (minute)",
+                "002F          17                         23",
+                "0030          00                       BT_STOP",
+                "0031        CA 3C                    Field 60, BT_STRUCT (sunrise_time)",
+                "0033          0E                       Field 0, BT_INT8 (hour)",
+                "0034          07                         7",
+                "0035          2E                       Field 1, BT_INT8 (minute)",
+                "0036          0C                         12",
+                "0037          00                       BT_STOP",
+                "0038        00                       BT_STOP",
+                "",
+                "0039      00                       BT_STOP",
+                "003A    00                       BT_STOP",
+                "003B  00                       BT_STOP",
+                "",
+                "Total: 60 bytes.",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_byte_is_annotated_once() {
+        for data in [&SETTINGS_BYTES[..], &STATE_BYTES[..]] {
+            let explanation = explain(data, None);
+            let mut next = 0;
+            for a in &explanation.annotations {
+                if a.kind != AnnotationKind::Section {
+                    assert_eq!(a.offset, next);
+                    next += a.len;
+                }
+            }
+            assert_eq!(next, data.len());
+        }
+    }
+
+    #[test]
+    fn detects_schema() {
+        assert_eq!(
+            explain(&SETTINGS_BYTES, None).blob,
+            Some(NightlightBlob::Settings)
+        );
+        let state = explain(&STATE_BYTES, None);
+        assert_eq!(state.blob, Some(NightlightBlob::State));
+        let text = state.to_string();
+        assert!(text.contains("-- State payload (21 bytes"));
+        assert!(text.contains("Field 0, BT_INT32 (is_enabled)"));
+        assert!(text.contains("Field 20, BT_UINT64 (last_transition_filetime)"));
+    }
+
+    #[test]
+    fn truncated_blob() {
+        let data = &SETTINGS_BYTES[..40];
+        let explanation = explain(data, None);
+        assert_eq!(
+            explanation.error.as_deref(),
+            Some("Unexpected end of data at position 19")
+        );
+        let last = explanation.annotations.last().unwrap();
+        assert_eq!(last.kind, AnnotationKind::Unparsed);
+        assert_eq!((last.offset, last.offset + last.len), (19, 40));
+        assert!(explanation.to_string().ends_with(
+            "Total: 40 bytes. Parsing stopped early: Unexpected end of data at position 19\n"
+        ));
+    }
+
+    #[test]
+    fn broken_inner_payload() {
+        let mut data = SETTINGS_BYTES;
+        // Type ID 31 for the inner payload's first field
+        data[23] = 0x1F;
+        let explanation = explain(&data, Some(NightlightBlob::Settings));
+        assert_eq!(explanation.error, None);
+        assert!(
+            explanation
+                .annotations
+                .iter()
+                .any(|a| { a.kind == AnnotationKind::Unparsed && a.offset == 23 && a.len == 34 })
+        );
+        assert_eq!(explanation.annotations.last().unwrap().text, "BT_STOP");
+    }
+
+    #[test]
+    fn deep_nesting_stops_at_the_depth_limit() {
+        // Deep enough to overflow the stack without the limit
+        let mut data = COMPACT_BINARY_MAGIC.to_vec();
+        data.extend(COMPACT_BINARY_V1);
+        data.extend(vec![0x0A; 100_000]);
+        data.extend(vec![0x00; 100_001]);
+        let explanation = explain(&data, None);
+        assert_eq!(
+            explanation.error.as_deref(),
+            Some("Nesting exceeds the depth limit of 64")
+        );
+        let last = explanation.annotations.last().unwrap();
+        assert_eq!(last.kind, AnnotationKind::Unparsed);
+        assert_eq!((last.offset, last.offset + last.len), (69, data.len()));
+    }
+}
@@ -0,0 +1,401 @@
+use std::ops::ControlFlow;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::nightlight_settings::{MAX_COLOR_TEMPERATURE, MIN_COLOR_TEMPERATURE, SettingsError};
+use crate::{NightlightBackend, NightlightError, NightlightManager};
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Source of time for a [Fade], so the stepping can be tested without real time passing.
+pub trait Clock {
+    /// Monotonic time elapsed since an arbitrary fixed point.
+    fn now(&self) -> Duration;
+    /// Blocks for `duration`.
+    fn sleep(&self, duration: Duration);
+}
+
+/// [Clock] backed by [Instant] and [thread::sleep].
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// How the color temperature moves from the start to the target over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant change in Kelvin per second.
+    #[default]
+    Linear,
+    /// Starts and ends slowly, fastest in the middle (smoothstep).
+    EaseInOut,
+    /// Linear in mireds (1,000,000 / Kelvin), which the eye perceives as a steady change:
+    /// a fade towards warm light spends less time in the barely-noticeable high Kelvin range.
+    Mired,
+}
+
+impl Easing {
+    /// Returns the temperature at `progress` (0.0 to 1.0) of the way from `from` to `to`.
+    pub fn interpolate(&self, from: u16, to: u16, progress: f64) -> u16 {
+        let t = progress.clamp(0.0, 1.0);
+        let (from_k, to_k) = (f64::from(from), f64::from(to));
+        let kelvin = match self {
+            Easing::Linear => from_k + (to_k - from_k) * t,
+            Easing::EaseInOut => from_k + (to_k - from_k) * t * t * (3.0 - 2.0 * t),
+            Easing::Mired => {
+                let (from_m, to_m) = (1e6 / from_k, 1e6 / to_k);
+                1e6 / (from_m + (to_m - from_m) * t)
+            }
+        };
+        kelvin.round() as u16
+    }
+}
+
+/// A single progress report from [Fade::run].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeProgress {
+    /// Time since the fade started.
+    pub elapsed: Duration,
+    /// Fraction of the fade duration that has passed, from 0.0 to 1.0.
+    pub progress: f64,
+    /// The color temperature in effect after this step.
+    pub temperature: u16,
+}
+
+/// How a [Fade::run] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeOutcome {
+    /// The target temperature was reached.
+    Completed,
+    /// The progress callback stopped the fade; `temperature` was left in effect.
+    Cancelled { temperature: u16 },
+}
+
+/// A gradual change of the color temperature to a target over a duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fade {
+    target: u16,
+    duration: Duration,
+    easing: Easing,
+    step: Duration,
+}
+
+impl Fade {
+    /// Creates a linear fade to `target` Kelvin over `duration`, stepping once per second.
+    pub fn new(target: u16, duration: Duration) -> Self {
+        Self {
+            target,
+            duration,
+            easing: Easing::default(),
+            step: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets the curve the temperature follows between the start and the target.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets the time between steps. Steps that don't change the rounded temperature are not
+    /// written, so a short step mostly costs wake-ups, not registry writes.
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.step = step.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Runs the fade from the currently stored color temperature, blocking until it completes.
+    ///
+    /// `on_progress` is called after every step, including the initial one at 0%, and can
+    /// cancel the fade by returning [ControlFlow::Break]. Each change goes through
+    /// [NightlightManager::set_color_temperature], so concurrent edits to other settings are
+    /// respected.
+    pub fn run<B: NightlightBackend>(
+        &self,
+        mgr: &NightlightManager<B>,
+        clock: &impl Clock,
+        mut on_progress: impl FnMut(&FadeProgress) -> ControlFlow<()>,
+    ) -> Result<FadeOutcome, NightlightError> {
+        if !(MIN_COLOR_TEMPERATURE..=MAX_COLOR_TEMPERATURE).contains(&self.target) {
+            return Err(SettingsError::InvalidColorTemperature(self.target).into());
+        }
+        let from = mgr.get_settings()?.color_temperature;
+        let start = clock.now();
+        let mut current = from;
+
+        loop {
+            let elapsed = clock.now().saturating_sub(start);
+            let progress = if elapsed >= self.duration {
+                1.0
+            } else {
+                elapsed.as_secs_f64() / self.duration.as_secs_f64()
+            };
+            let temperature = self.easing.interpolate(from, self.target, progress);
+            if temperature != current {
+                mgr.set_color_temperature(temperature)?;
+                current = temperature;
+            }
+
+            let report = FadeProgress {
+                elapsed,
+                progress,
+                temperature,
+            };
+            if on_progress(&report).is_break() {
+                return Ok(FadeOutcome::Cancelled { temperature });
+            }
+            if progress >= 1.0 {
+                return Ok(FadeOutcome::Completed);
+            }
+
+            // Sleep until the next step boundary so slow writes don't accumulate drift
+            let step = self.step.as_nanos();
+            let next = (elapsed.as_nanos() / step + 1) * step;
+            let next = u64::try_from(next / NANOS_PER_SEC).map_or(self.duration, |secs| {
+                Duration::new(secs, (next % NANOS_PER_SEC) as u32).min(self.duration)
+            });
+            clock.sleep(next - elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use chrono::NaiveTime;
+
+    use super::*;
+    use crate::nightlight_settings::{NightlightSettings, ScheduleMode, SettingsEncoding};
+    use crate::nightlight_state::{NightlightState, StateEncoding};
+    use crate::{BondStruct, CloudStoreEnvelope, MemoryBackend, NightlightBlob};
+
+    /// A clock that only advances when slept on.
+    #[derive(Default)]
+    struct FakeClock {
+        now: Cell<Duration>,
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Duration {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+        }
+    }
+
+    fn backend(color_temperature: u16) -> MemoryBackend {
+        let settings = NightlightSettings {
+            timestamp: 1742540908,
+            schedule_mode: ScheduleMode::Off,
+            color_temperature,
+            start_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            sunset_time: NaiveTime::from_hms_opt(19, 23, 0).unwrap(),
+            sunrise_time: NaiveTime::from_hms_opt(7, 12, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        };
+        let state = NightlightState {
+            timestamp: 1742670473,
+            is_enabled: true,
+            initialized: 1,
+            last_transition_filetime: 133_871_411_809_270_569,
+            unknown_fields: BondStruct::new(),
+            encoding: StateEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        };
+        MemoryBackend::from_values(&settings, &state)
+    }
+
+    /// Returns the color temperature of every settings write, in order.
+    fn written_temperatures(backend: &MemoryBackend) -> Vec<u16> {
+        backend
+            .writes()
+            .iter()
+            .filter(|w| w.blob == NightlightBlob::Settings)
+            .map(|w| {
+                NightlightSettings::deserialize_from_bytes(&w.data)
+                    .unwrap()
+                    .color_temperature
+            })
+            .collect()
+    }
+
+    #[test]
+    fn linear_fade_steps_evenly() {
+        let backend = backend(6500);
+        let mgr = NightlightManager::new(&backend);
+        let clock = FakeClock::default();
+        let mut reports = Vec::new();
+
+        let outcome = Fade::new(3500, Duration::from_secs(10))
+            .run(&mgr, &clock, |p| {
+                reports.push(*p);
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        assert_eq!(outcome, FadeOutcome::Completed);
+        assert_eq!(
+            written_temperatures(&backend),
+            [6200, 5900, 5600, 5300, 5000, 4700, 4400, 4100, 3800, 3500]
+        );
+        assert_eq!(reports.len(), 11);
+        assert_eq!(reports[0].temperature, 6500);
+        assert_eq!(reports[5].elapsed, Duration::from_secs(5));
+        assert_eq!(reports[5].progress, 0.5);
+        assert_eq!(clock.now(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn last_step_is_shortened_to_duration() {
+        let backend = backend(4000);
+        let mgr = NightlightManager::new(&backend);
+        let clock = FakeClock::default();
+        Fade::new(3000, Duration::from_millis(2500))
+            .run(&mgr, &clock, |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert_eq!(written_temperatures(&backend), [3600, 3200, 3000]);
+        assert_eq!(clock.now(), Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn steps_past_u32_max() {
+        let backend = backend(4000);
+        let mgr = NightlightManager::new(&backend);
+        let clock = FakeClock::default();
+        let jump = Duration::from_secs(5_000_000) + Duration::from_micros(500);
+        let mut reports = Vec::new();
+
+        Fade::new(3000, Duration::from_secs(10_000_000))
+            .with_step(Duration::from_millis(1))
+            .run(&mgr, &clock, |p| {
+                reports.push(p.elapsed);
+                if reports.len() == 1 {
+                    clock.sleep(jump);
+                }
+                if reports.len() < 3 {
+                    ControlFlow::Continue(())
+                } else {
+                    ControlFlow::Break(())
+                }
+            })
+            .unwrap();
+        // The first sleep was planned before the jump, the second is back on a step boundary
+        assert_eq!(
+            reports,
+            [
+                Duration::ZERO,
+                jump + Duration::from_millis(1),
+                Duration::from_secs(5_000_000) + Duration::from_millis(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn easing_curves() {
+        assert_eq!(Easing::Linear.interpolate(6500, 2700, 0.5), 4600);
+        assert_eq!(Easing::EaseInOut.interpolate(6500, 2700, 0.5), 4600);
+        // Smoothstep moves less than linear near the ends
+        assert_eq!(Easing::EaseInOut.interpolate(6500, 2700, 0.25), 5906);
+        // Halfway in mireds: (153.8 + 370.4) / 2 = 262.1 mired
+        assert_eq!(Easing::Mired.interpolate(6500, 2700, 0.5), 3815);
+        for easing in [Easing::Linear, Easing::EaseInOut, Easing::Mired] {
+            assert_eq!(easing.interpolate(6500, 2700, 0.0), 6500);
+            assert_eq!(easing.interpolate(6500, 2700, 1.0), 2700);
+            assert_eq!(easing.interpolate(2700, 6500, 2.0), 6500);
+        }
+    }
+
+    #[test]
+    fn cancel_leaves_current_temperature() {
+        let backend = backend(6500);
+        let mgr = NightlightManager::new(&backend);
+        let clock = FakeClock::default();
+
+        let outcome = Fade::new(3500, Duration::from_secs(10))
+            .with_easing(Easing::Mired)
+            .run(&mgr, &clock, |p| {
+                if p.elapsed >= Duration::from_secs(3) {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+
+        let written = written_temperatures(&backend);
+        assert_eq!(written.len(), 3);
+        assert_eq!(
+            outcome,
+            FadeOutcome::Cancelled {
+                temperature: written[2]
+            }
+        );
+        assert_eq!(mgr.get_settings().unwrap().color_temperature, written[2]);
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_target() {
+        let backend = backend(6500);
+        let mgr = NightlightManager::new(&backend);
+        let outcome = Fade::new(2700, Duration::ZERO)
+            .run(&mgr, &FakeClock::default(), |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert_eq!(outcome, FadeOutcome::Completed);
+        assert_eq!(written_temperatures(&backend), [2700]);
+    }
+
+    #[test]
+    fn unchanged_steps_are_not_written() {
+        let backend = backend(3000);
+        let mgr = NightlightManager::new(&backend);
+        Fade::new(2998, Duration::from_secs(60))
+            .run(&mgr, &FakeClock::default(), |_| ControlFlow::Continue(()))
+            .unwrap();
+        assert_eq!(written_temperatures(&backend), [2999, 2998]);
+    }
+
+    #[test]
+    fn invalid_target_writes_nothing() {
+        let backend = backend(6500);
+        let mgr = NightlightManager::new(&backend);
+        let result =
+            Fade::new(800, Duration::from_secs(10))
+                .run(&mgr, &FakeClock::default(), |_| ControlFlow::Continue(()));
+        assert!(matches!(
+            result,
+            Err(NightlightError::InvalidSettings(
+                SettingsError::InvalidColorTemperature(800)
+            ))
+        ));
+        assert!(backend.writes().is_empty());
+    }
+}
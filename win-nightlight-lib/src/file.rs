@@ -0,0 +1,312 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{NightlightBackend, NightlightBlob, NightlightError, hex};
+
+/// On-disk encoding of a blob file used by [FileBackend].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobFormat {
+    /// The raw registry `Data` bytes.
+    Binary,
+    /// The registry `Data` bytes as hex text. Reads accept whitespace, commas and `0x`
+    /// prefixes; writes produce a single line of lowercase hex.
+    Hex,
+}
+
+impl BlobFormat {
+    /// File extension used for the default blob file names.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BlobFormat::Binary => "bin",
+            BlobFormat::Hex => "hex",
+        }
+    }
+}
+
+/// Backend that stores the settings and state `Data` blobs as files.
+///
+/// Useful for working on captured registry blobs offline: load them into a
+/// [crate::NightlightManager], apply changes, and diff the files afterwards.
+///
+/// Writes go to a temporary file in the same directory which is then renamed over the
+/// target, so readers see either the old or the new blob, never a partial one. How much of
+/// a write survives a power loss still depends on the file system.
+#[derive(Debug, Clone)]
+pub struct FileBackend {
+    settings_path: PathBuf,
+    state_path: PathBuf,
+    format: BlobFormat,
+}
+
+impl FileBackend {
+    /// Creates a backend using `settings.<ext>` and `state.<ext>` inside `dir`, where `<ext>`
+    /// is `bin` or `hex` depending on `format`.
+    pub fn new(dir: impl AsRef<Path>, format: BlobFormat) -> Self {
+        let dir = dir.as_ref();
+        Self {
+            settings_path: dir.join(format!("settings.{}", format.extension())),
+            state_path: dir.join(format!("state.{}", format.extension())),
+            format,
+        }
+    }
+
+    /// Creates a backend using explicit file paths for the two blobs.
+    pub fn from_paths(
+        settings_path: impl Into<PathBuf>,
+        state_path: impl Into<PathBuf>,
+        format: BlobFormat,
+    ) -> Self {
+        Self {
+            settings_path: settings_path.into(),
+            state_path: state_path.into(),
+            format,
+        }
+    }
+
+    /// Returns the file path used for the given blob.
+    pub fn path(&self, blob: NightlightBlob) -> &Path {
+        match blob {
+            NightlightBlob::Settings => &self.settings_path,
+            NightlightBlob::State => &self.state_path,
+        }
+    }
+
+    /// Returns the on-disk encoding of the blob files.
+    pub fn format(&self) -> BlobFormat {
+        self.format
+    }
+
+    fn read(&self, blob: NightlightBlob) -> Result<Vec<u8>, NightlightError> {
+        let contents = fs::read(self.path(blob))?;
+        match self.format {
+            BlobFormat::Binary => Ok(contents),
+            BlobFormat::Hex => {
+                let text = String::from_utf8(contents).map_err(|_| {
+                    NightlightError::Backend(format!("{blob} hex file is not valid UTF-8"))
+                })?;
+                Ok(hex::decode(&text)?)
+            }
+        }
+    }
+
+    fn write(&self, blob: NightlightBlob, data: &[u8]) -> Result<(), NightlightError> {
+        let contents = match self.format {
+            BlobFormat::Binary => data.to_vec(),
+            BlobFormat::Hex => format!("{}\n", hex::encode(data)).into_bytes(),
+        };
+        write_atomic(self.path(blob), &contents)?;
+        Ok(())
+    }
+}
+
+/// Numbers the temporary files of concurrent writes within this process.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `contents` to a sibling temporary file, syncs it, then renames it over `path`. On
+/// Unix the directory is synced as well, so the rename itself is durable.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = (|| {
+        let mut file = fs::File::create_new(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result?;
+
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+impl NightlightBackend for FileBackend {
+    fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::Settings)
+    }
+
+    fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        self.write(NightlightBlob::Settings, data)
+    }
+
+    fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::State)
+    }
+
+    fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        self.write(NightlightBlob::State, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NightlightManager;
+    use crate::nightlight_settings::ScheduleMode;
+
+    // The settings test bytes from nightlight_settings.rs
+    const SETTINGS_BYTES: [u8; 60] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x26, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14,
+        0x0E, 0x01, 0x2E, 0x0F, 0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xCA, 0x32, 0x0E,
+        0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    // The state (disabled) test bytes from nightlight_state.rs
+    const STATE_DISABLED_BYTES: [u8; 41] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0x89, 0x95, 0xFC, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x13, 0x43, 0x42, 0x01, 0x00, 0xD0, 0x0A, 0x02, 0xC6, 0x14, 0xA9, 0xF6,
+        0xE2, 0xD3, 0xEF, 0xEA, 0xE6, 0xED, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    /// A fresh, empty directory under the system temp dir, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "wnl-file-backend-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn binary_files_read_verbatim() {
+        let dir = TempDir::new("binary");
+        fs::write(dir.0.join("settings.bin"), SETTINGS_BYTES).unwrap();
+        fs::write(dir.0.join("state.bin"), STATE_DISABLED_BYTES).unwrap();
+
+        let backend = FileBackend::new(&dir.0, BlobFormat::Binary);
+        assert_eq!(backend.read_settings_bytes().unwrap(), SETTINGS_BYTES);
+        assert_eq!(backend.read_state_bytes().unwrap(), STATE_DISABLED_BYTES);
+    }
+
+    #[test]
+    fn hex_files_accept_pasted_dumps() {
+        let dir = TempDir::new("hex-read");
+        let spaced: Vec<String> = SETTINGS_BYTES.iter().map(|b| format!("{b:02X}")).collect();
+        fs::write(dir.0.join("settings.hex"), spaced.join(" ")).unwrap();
+
+        let backend = FileBackend::new(&dir.0, BlobFormat::Hex);
+        assert_eq!(backend.read_settings_bytes().unwrap(), SETTINGS_BYTES);
+    }
+
+    #[test]
+    fn hex_write_roundtrip() {
+        let dir = TempDir::new("hex-write");
+        let backend = FileBackend::new(&dir.0, BlobFormat::Hex);
+        backend.write_state_bytes(&STATE_DISABLED_BYTES).unwrap();
+
+        let text = fs::read_to_string(dir.0.join("state.hex")).unwrap();
+        assert_eq!(text, format!("{}\n", hex::encode(&STATE_DISABLED_BYTES)));
+        assert_eq!(backend.read_state_bytes().unwrap(), STATE_DISABLED_BYTES);
+    }
+
+    #[test]
+    fn write_leaves_no_temp_files() {
+        let dir = TempDir::new("atomic");
+        let backend = FileBackend::new(&dir.0, BlobFormat::Binary);
+        backend.write_settings_bytes(&SETTINGS_BYTES).unwrap();
+        backend.write_settings_bytes(&SETTINGS_BYTES[..10]).unwrap();
+
+        let names: Vec<_> = fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names, ["settings.bin"]);
+        assert_eq!(
+            backend.read_settings_bytes().unwrap(),
+            &SETTINGS_BYTES[..10]
+        );
+    }
+
+    #[test]
+    fn concurrent_writes_dont_collide() {
+        let dir = TempDir::new("concurrent");
+        let backend = FileBackend::new(&dir.0, BlobFormat::Binary);
+        std::thread::scope(|scope| {
+            for len in 1..=8 {
+                let backend = &backend;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        backend
+                            .write_settings_bytes(&SETTINGS_BYTES[..len])
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let names: Vec<_> = fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names, ["settings.bin"]);
+        let written = backend.read_settings_bytes().unwrap();
+        assert!(SETTINGS_BYTES.starts_with(&written) && (1..=8).contains(&written.len()));
+    }
+
+    #[test]
+    fn missing_file_is_io_error() {
+        let dir = TempDir::new("missing");
+        let backend = FileBackend::new(&dir.0, BlobFormat::Binary);
+        assert!(matches!(
+            backend.read_state_bytes(),
+            Err(NightlightError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound
+        ));
+    }
+
+    #[test]
+    fn manager_applies_changes_to_files() {
+        let dir = TempDir::new("manager");
+        let settings_path = dir.0.join("captured-settings.bin");
+        let state_path = dir.0.join("captured-state.bin");
+        fs::write(&settings_path, SETTINGS_BYTES).unwrap();
+        fs::write(&state_path, STATE_DISABLED_BYTES).unwrap();
+
+        let backend = FileBackend::from_paths(&settings_path, &state_path, BlobFormat::Binary);
+        let mgr = NightlightManager::new(&backend);
+        mgr.enable().unwrap();
+        mgr.set_schedule(ScheduleMode::SunsetToSunrise, None, None)
+            .unwrap();
+
+        assert!(mgr.get_state().unwrap().is_enabled);
+        assert_eq!(
+            mgr.get_settings().unwrap().schedule_mode,
+            ScheduleMode::SunsetToSunrise
+        );
+        assert_ne!(fs::read(&settings_path).unwrap(), SETTINGS_BYTES);
+    }
+}
@@ -0,0 +1,81 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HexError {
+    #[error("Invalid hex digit {0:?} at position {1}")]
+    InvalidDigit(char, usize),
+    #[error("Hex string has an odd number of digits")]
+    OddLength,
+}
+
+/// Encodes bytes as a contiguous lowercase hex string.
+pub fn encode(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0x0F) as usize] as char);
+    }
+    out
+}
+
+/// Decodes a hex string into bytes.
+///
+/// Accepts upper- or lowercase digits. ASCII whitespace, commas and `0x` prefixes are ignored,
+/// so both `434201` and pasted dumps like `0x43, 0x42, 0x01` or `43 42 01` decode the same.
+pub fn decode(s: &str) -> Result<Vec<u8>, HexError> {
+    let mut out = Vec::with_capacity(s.len() / 2);
+    let mut high: Option<u8> = None;
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        if c.is_ascii_whitespace() || c == ',' {
+            continue;
+        }
+        if c == '0' && high.is_none() && matches!(chars.peek(), Some((_, 'x' | 'X'))) {
+            chars.next();
+            continue;
+        }
+        let nibble = c.to_digit(16).ok_or(HexError::InvalidDigit(c, pos))? as u8;
+        match high.take() {
+            Some(h) => out.push((h << 4) | nibble),
+            None => high = Some(nibble),
+        }
+    }
+
+    if high.is_some() {
+        return Err(HexError::OddLength);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_known() {
+        assert_eq!(encode(&[0x43, 0x42, 0x01, 0x00, 0xFF]), "43420100ff");
+        assert_eq!(encode(&[]), "");
+    }
+
+    #[test]
+    fn decode_formats() {
+        let expected = vec![0x43, 0x42, 0x01, 0x00, 0xFF];
+        assert_eq!(decode("43420100ff").unwrap(), expected);
+        assert_eq!(decode("43 42 01 00 FF\n").unwrap(), expected);
+        assert_eq!(decode("0x43, 0x42, 0x01, 0x00, 0xFF").unwrap(), expected);
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode("434"), Err(HexError::OddLength));
+        assert_eq!(decode("43 4g"), Err(HexError::InvalidDigit('g', 4)));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(decode(&encode(&bytes)).unwrap(), bytes);
+    }
+}
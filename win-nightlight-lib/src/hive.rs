@@ -0,0 +1,740 @@
+use std::fs;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::{DATA_REG_KEY_NAME, NightlightBackend, NightlightBlob, NightlightError};
+
+/// Size of the base block at the start of a hive file.
+const BASE_BLOCK_SIZE: usize = 4096;
+/// Cell offsets are relative to the first hive bin, which follows the base block.
+const HBINS_START: usize = BASE_BLOCK_SIZE;
+/// Values larger than this are split into "db" big-data segments (hive format 1.4+).
+const BIG_DATA_THRESHOLD: u32 = 16344;
+/// nk flag: the key name is stored as Latin-1 rather than UTF-16LE.
+const KEY_COMP_NAME: u16 = 0x0020;
+/// vk flag: the value name is stored as Latin-1 rather than UTF-16LE.
+const VALUE_COMP_NAME: u16 = 0x0001;
+/// vk data size flag: data of up to 4 bytes is stored in the data offset field itself.
+const DATA_IN_OFFSET: u32 = 0x8000_0000;
+/// Maximum nesting of "ri" index roots; real hives use at most one level.
+const MAX_INDEX_DEPTH: usize = 4;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HiveError {
+    #[error("Missing 'regf' signature")]
+    InvalidSignature,
+    #[error("Unsupported hive format version {0}.{1}")]
+    UnsupportedVersion(u32, u32),
+    #[error("Cell offset {0:#x} is out of bounds")]
+    CellOutOfBounds(u32),
+    #[error("Malformed cell at offset {0:#x}")]
+    InvalidCell(u32),
+    #[error("Subkey index at offset {0:#x} is nested too deeply")]
+    IndexTooDeep(u32),
+}
+
+/// A read-only Windows registry hive file (regf format), e.g. `NTUSER.DAT`.
+///
+/// Only the primary file is read: pending changes in `.LOG1`/`.LOG2` transaction logs are not
+/// replayed, so a hive copied from a running system may lag behind the live registry.
+pub struct Hive {
+    data: Vec<u8>,
+    minor_version: u32,
+    root_offset: u32,
+}
+
+/// A key node (`nk` cell) inside a [Hive].
+#[derive(Clone, Copy)]
+pub struct HiveKey<'a> {
+    hive: &'a Hive,
+    offset: u32,
+}
+
+/// A value (`vk` cell) inside a [Hive], with its data resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HiveValue {
+    pub name: String,
+    /// Registry value type, e.g. 3 for `REG_BINARY`.
+    pub value_type: u32,
+    pub data: Vec<u8>,
+}
+
+fn u16_at(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Decodes a key or value name stored either as Latin-1 (`compressed`) or UTF-16LE.
+fn decode_name(raw: &[u8], compressed: bool) -> String {
+    if compressed {
+        raw.iter().map(|&b| b as char).collect()
+    } else {
+        let units: Vec<u16> = raw
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    }
+}
+
+/// Registry key and value names compare case-insensitively.
+fn names_equal(a: &str, b: &str) -> bool {
+    a.to_uppercase() == b.to_uppercase()
+}
+
+impl Hive {
+    /// Parses the base block of a hive file. Cells are decoded lazily on lookup.
+    pub fn parse(data: Vec<u8>) -> Result<Self, HiveError> {
+        if data.len() < BASE_BLOCK_SIZE || &data[..4] != b"regf" {
+            return Err(HiveError::InvalidSignature);
+        }
+        let major = u32_at(&data, 0x14).ok_or(HiveError::InvalidSignature)?;
+        let minor = u32_at(&data, 0x18).ok_or(HiveError::InvalidSignature)?;
+        if major != 1 || !(3..=6).contains(&minor) {
+            return Err(HiveError::UnsupportedVersion(major, minor));
+        }
+        let root_offset = u32_at(&data, 0x24).ok_or(HiveError::InvalidSignature)?;
+
+        let hive = Self {
+            data,
+            minor_version: minor,
+            root_offset,
+        };
+        hive.root_key().signature_check()?;
+        Ok(hive)
+    }
+
+    /// Returns the hive's root key. For `NTUSER.DAT` this corresponds to `HKEY_CURRENT_USER`.
+    pub fn root_key(&self) -> HiveKey<'_> {
+        HiveKey {
+            hive: self,
+            offset: self.root_offset,
+        }
+    }
+
+    /// Opens a key by a `\`-separated path relative to the root key.
+    pub fn open_key(&self, path: &str) -> Result<Option<HiveKey<'_>>, HiveError> {
+        let mut key = self.root_key();
+        for name in path.split('\\').filter(|n| !n.is_empty()) {
+            match key.subkey(name)? {
+                Some(sub) => key = sub,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(key))
+    }
+
+    /// Returns the data of the cell at `offset` (excluding its 4-byte size header).
+    fn cell(&self, offset: u32) -> Result<&[u8], HiveError> {
+        let start = HBINS_START
+            .checked_add(offset as usize)
+            .ok_or(HiveError::CellOutOfBounds(offset))?;
+        let size = u32_at(&self.data, start).ok_or(HiveError::CellOutOfBounds(offset))?;
+        // Allocated cells store their size negated
+        let len = (size as i32).unsigned_abs() as usize;
+        if len < 4 {
+            return Err(HiveError::InvalidCell(offset));
+        }
+        self.data
+            .get(start + 4..start + len)
+            .ok_or(HiveError::CellOutOfBounds(offset))
+    }
+
+    /// Returns the cell at `offset`, checking its two-byte signature.
+    fn signed_cell(&self, offset: u32, signature: &[u8; 2]) -> Result<&[u8], HiveError> {
+        let cell = self.cell(offset)?;
+        if cell.get(..2) != Some(signature) {
+            return Err(HiveError::InvalidCell(offset));
+        }
+        Ok(cell)
+    }
+
+    /// Appends the nk offsets referenced by a subkey index (lf/lh/li/ri) to `out`.
+    fn collect_subkeys(
+        &self,
+        offset: u32,
+        depth: usize,
+        out: &mut Vec<u32>,
+    ) -> Result<(), HiveError> {
+        if depth > MAX_INDEX_DEPTH {
+            return Err(HiveError::IndexTooDeep(offset));
+        }
+        let cell = self.cell(offset)?;
+        let bad = || HiveError::InvalidCell(offset);
+        let count = u16_at(cell, 2).ok_or_else(bad)? as usize;
+        match cell.get(..2) {
+            // Fast leaf / hash leaf: (offset, hash) pairs
+            Some(b"lf") | Some(b"lh") => {
+                for i in 0..count {
+                    out.push(u32_at(cell, 4 + i * 8).ok_or_else(bad)?);
+                }
+            }
+            // Index leaf: plain offsets
+            Some(b"li") => {
+                for i in 0..count {
+                    out.push(u32_at(cell, 4 + i * 4).ok_or_else(bad)?);
+                }
+            }
+            // Index root: offsets of further leaves
+            Some(b"ri") => {
+                for i in 0..count {
+                    let leaf = u32_at(cell, 4 + i * 4).ok_or_else(bad)?;
+                    self.collect_subkeys(leaf, depth + 1, out)?;
+                }
+            }
+            _ => return Err(bad()),
+        }
+        Ok(())
+    }
+
+    /// Resolves the data of a vk cell, following big-data ("db") records if needed.
+    fn value_data(&self, vk_offset: u32, vk: &[u8]) -> Result<Vec<u8>, HiveError> {
+        let bad = || HiveError::InvalidCell(vk_offset);
+        let raw_size = u32_at(vk, 0x04).ok_or_else(bad)?;
+        let data_offset = u32_at(vk, 0x08).ok_or_else(bad)?;
+
+        if raw_size & DATA_IN_OFFSET != 0 {
+            // The data field holds at most 4 bytes
+            let size = (raw_size & !DATA_IN_OFFSET) as usize;
+            if size > 4 {
+                return Err(bad());
+            }
+            return vk
+                .get(0x08..0x08 + size)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(bad);
+        }
+
+        let size = raw_size as usize;
+        if raw_size > BIG_DATA_THRESHOLD && self.minor_version >= 4 {
+            let db = self.signed_cell(data_offset, b"db")?;
+            let bad_db = || HiveError::InvalidCell(data_offset);
+            let segments = u16_at(db, 2).ok_or_else(bad_db)? as usize;
+            let list_offset = u32_at(db, 4).ok_or_else(bad_db)?;
+            let list = self.cell(list_offset)?;
+
+            let mut data = Vec::with_capacity(size.min(self.data.len()));
+            for i in 0..segments {
+                let segment_offset =
+                    u32_at(list, i * 4).ok_or(HiveError::InvalidCell(list_offset))?;
+                let segment = self.cell(segment_offset)?;
+                let take = segment.len().min(BIG_DATA_THRESHOLD as usize);
+                data.extend_from_slice(&segment[..take]);
+            }
+            if data.len() < size {
+                return Err(bad_db());
+            }
+            data.truncate(size);
+            return Ok(data);
+        }
+
+        let cell = self.cell(data_offset)?;
+        cell.get(..size)
+            .map(<[u8]>::to_vec)
+            .ok_or(HiveError::InvalidCell(data_offset))
+    }
+}
+
+impl<'a> HiveKey<'a> {
+    fn nk(&self) -> Result<&'a [u8], HiveError> {
+        self.hive.signed_cell(self.offset, b"nk")
+    }
+
+    fn signature_check(&self) -> Result<(), HiveError> {
+        self.nk().map(|_| ())
+    }
+
+    /// Returns the key's own name (the last component of its path).
+    pub fn name(&self) -> Result<String, HiveError> {
+        let nk = self.nk()?;
+        let bad = || HiveError::InvalidCell(self.offset);
+        let flags = u16_at(nk, 0x02).ok_or_else(bad)?;
+        let len = u16_at(nk, 0x48).ok_or_else(bad)? as usize;
+        let raw = nk.get(0x4C..0x4C + len).ok_or_else(bad)?;
+        Ok(decode_name(raw, flags & KEY_COMP_NAME != 0))
+    }
+
+    /// Returns the key's subkeys in index order.
+    pub fn subkeys(&self) -> Result<Vec<HiveKey<'a>>, HiveError> {
+        let nk = self.nk()?;
+        let bad = || HiveError::InvalidCell(self.offset);
+        let count = u32_at(nk, 0x14).ok_or_else(bad)?;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let list = u32_at(nk, 0x1C).ok_or_else(bad)?;
+        let mut offsets = Vec::new();
+        self.hive.collect_subkeys(list, 0, &mut offsets)?;
+        Ok(offsets
+            .into_iter()
+            .map(|offset| HiveKey {
+                hive: self.hive,
+                offset,
+            })
+            .collect())
+    }
+
+    /// Looks up a direct subkey by name (case-insensitive).
+    pub fn subkey(&self, name: &str) -> Result<Option<HiveKey<'a>>, HiveError> {
+        for key in self.subkeys()? {
+            if names_equal(&key.name()?, name) {
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns all of the key's values.
+    pub fn values(&self) -> Result<Vec<HiveValue>, HiveError> {
+        let nk = self.nk()?;
+        let bad = || HiveError::InvalidCell(self.offset);
+        let count = u32_at(nk, 0x24).ok_or_else(bad)? as usize;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let list_offset = u32_at(nk, 0x28).ok_or_else(bad)?;
+        let list = self.hive.cell(list_offset)?;
+
+        let mut values = Vec::with_capacity(count.min(list.len() / 4));
+        for i in 0..count {
+            let vk_offset = u32_at(list, i * 4).ok_or(HiveError::InvalidCell(list_offset))?;
+            let vk = self.hive.signed_cell(vk_offset, b"vk")?;
+            let bad_vk = || HiveError::InvalidCell(vk_offset);
+            let name_len = u16_at(vk, 0x02).ok_or_else(bad_vk)? as usize;
+            let value_type = u32_at(vk, 0x0C).ok_or_else(bad_vk)?;
+            let flags = u16_at(vk, 0x10).ok_or_else(bad_vk)?;
+            let raw_name = vk.get(0x14..0x14 + name_len).ok_or_else(bad_vk)?;
+            values.push(HiveValue {
+                name: decode_name(raw_name, flags & VALUE_COMP_NAME != 0),
+                value_type,
+                data: self.hive.value_data(vk_offset, vk)?,
+            });
+        }
+        Ok(values)
+    }
+
+    /// Looks up a value by name (case-insensitive). The default value has an empty name.
+    pub fn value(&self, name: &str) -> Result<Option<HiveValue>, HiveError> {
+        Ok(self
+            .values()?
+            .into_iter()
+            .find(|v| names_equal(&v.name, name)))
+    }
+}
+
+/// Read-only backend over an offline `NTUSER.DAT` hive.
+///
+/// Looks up the Night Light `Data` values by the same paths as [crate::SETTINGS_REG_KEY] and
+/// [crate::STATE_REG_KEY], relative to the hive root. Writes fail with
+/// [NightlightError::ReadOnlyBackend]; to repair a profile, read the blobs here and write the
+/// result out with [crate::RegFileBackend] or [crate::FileBackend].
+pub struct HiveBackend {
+    hive: Hive,
+}
+
+impl HiveBackend {
+    pub fn new(hive: Hive) -> Self {
+        Self { hive }
+    }
+
+    /// Reads and parses a hive file from disk.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, NightlightError> {
+        let data = fs::read(path)?;
+        Ok(Self::new(Hive::parse(data)?))
+    }
+
+    pub fn hive(&self) -> &Hive {
+        &self.hive
+    }
+
+    fn read(&self, blob: NightlightBlob) -> Result<Vec<u8>, NightlightError> {
+        let value = match self.hive.open_key(blob.reg_key())? {
+            Some(key) => key.value(DATA_REG_KEY_NAME)?,
+            None => None,
+        };
+        value
+            .map(|v| v.data)
+            .ok_or(NightlightError::MissingData(blob))
+    }
+}
+
+impl NightlightBackend for HiveBackend {
+    fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::Settings)
+    }
+
+    fn write_settings_bytes(&self, _data: &[u8]) -> Result<(), NightlightError> {
+        Err(NightlightError::ReadOnlyBackend)
+    }
+
+    fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::State)
+    }
+
+    fn write_state_bytes(&self, _data: &[u8]) -> Result<(), NightlightError> {
+        Err(NightlightError::ReadOnlyBackend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NightlightManager;
+
+    // The settings test bytes from nightlight_settings.rs
+    const SETTINGS_BYTES: [u8; 60] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x26, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14,
+        0x0E, 0x01, 0x2E, 0x0F, 0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xCA, 0x32, 0x0E,
+        0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    // The state (enabled) test bytes from nightlight_state.rs
+    const STATE_ENABLED_BYTES: [u8; 43] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0x89, 0x95, 0xFC, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x15, 0x43, 0x42, 0x01, 0x00, 0x10, 0x00, 0xD0, 0x0A, 0x02, 0xC6, 0x14,
+        0xA9, 0xF6, 0xE2, 0xD3, 0xEF, 0xEA, 0xE6, 0xED, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    const REG_BINARY: u32 = 3;
+
+    /// A key to be laid out by [build_hive].
+    struct TestKey {
+        name: String,
+        values: Vec<(String, u32, Vec<u8>)>,
+        children: Vec<TestKey>,
+    }
+
+    impl TestKey {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                values: Vec::new(),
+                children: Vec::new(),
+            }
+        }
+
+        fn value(mut self, name: &str, value_type: u32, data: &[u8]) -> Self {
+            self.values
+                .push((name.to_string(), value_type, data.to_vec()));
+            self
+        }
+
+        fn child(mut self, child: TestKey) -> Self {
+            self.children.push(child);
+            self
+        }
+
+        /// Inserts a value at a `\`-separated path below this key, creating keys as needed.
+        fn insert(&mut self, path: &str, name: &str, data: &[u8]) {
+            let mut key = self;
+            for part in path.split('\\') {
+                let idx = match key.children.iter().position(|c| c.name == part) {
+                    Some(idx) => idx,
+                    None => {
+                        key.children.push(TestKey::new(part));
+                        key.children.len() - 1
+                    }
+                };
+                key = &mut key.children[idx];
+            }
+            key.values
+                .push((name.to_string(), REG_BINARY, data.to_vec()));
+        }
+    }
+
+    /// Lays out cells in a single hive bin, children before parents so offsets are known.
+    struct HiveBuilder {
+        /// Hive bin contents, starting with the 32-byte hbin header.
+        bins: Vec<u8>,
+        /// Emit subkey lists as an "ri" root over "li" leaves instead of a single "lh".
+        use_index_root: bool,
+    }
+
+    impl HiveBuilder {
+        fn alloc(&mut self, data: &[u8]) -> u32 {
+            let offset = self.bins.len() as u32;
+            let len = (data.len() + 4).div_ceil(8) * 8;
+            self.bins.extend_from_slice(&(-(len as i32)).to_le_bytes());
+            self.bins.extend_from_slice(data);
+            self.bins.resize(offset as usize + len, 0);
+            offset
+        }
+
+        fn offsets_cell(&mut self, signature: &[u8; 2], offsets: &[u32]) -> u32 {
+            let mut cell = signature.to_vec();
+            cell.extend_from_slice(&(offsets.len() as u16).to_le_bytes());
+            for o in offsets {
+                cell.extend_from_slice(&o.to_le_bytes());
+            }
+            self.alloc(&cell)
+        }
+
+        fn value(&mut self, name: &str, value_type: u32, data: &[u8]) -> u32 {
+            let (size, data_offset) = if data.len() <= 4 {
+                let mut inline = [0u8; 4];
+                inline[..data.len()].copy_from_slice(data);
+                (
+                    data.len() as u32 | DATA_IN_OFFSET,
+                    u32::from_le_bytes(inline),
+                )
+            } else if data.len() > BIG_DATA_THRESHOLD as usize {
+                let segments: Vec<u32> = data
+                    .chunks(BIG_DATA_THRESHOLD as usize)
+                    .map(|chunk| self.alloc(chunk))
+                    .collect();
+                let mut list = Vec::new();
+                for s in &segments {
+                    list.extend_from_slice(&s.to_le_bytes());
+                }
+                let list_offset = self.alloc(&list);
+                let mut db = b"db".to_vec();
+                db.extend_from_slice(&(segments.len() as u16).to_le_bytes());
+                db.extend_from_slice(&list_offset.to_le_bytes());
+                (data.len() as u32, self.alloc(&db))
+            } else {
+                (data.len() as u32, self.alloc(data))
+            };
+
+            let mut vk = b"vk".to_vec();
+            vk.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            vk.extend_from_slice(&size.to_le_bytes());
+            vk.extend_from_slice(&data_offset.to_le_bytes());
+            vk.extend_from_slice(&value_type.to_le_bytes());
+            vk.extend_from_slice(&VALUE_COMP_NAME.to_le_bytes());
+            vk.extend_from_slice(&[0, 0]);
+            vk.extend_from_slice(name.as_bytes());
+            self.alloc(&vk)
+        }
+
+        fn key(&mut self, key: &TestKey, flags: u16) -> u32 {
+            let value_offsets: Vec<u32> = key
+                .values
+                .iter()
+                .map(|(name, ty, data)| self.value(name, *ty, data))
+                .collect();
+            let value_list = if value_offsets.is_empty() {
+                u32::MAX
+            } else {
+                let mut list = Vec::new();
+                for o in &value_offsets {
+                    list.extend_from_slice(&o.to_le_bytes());
+                }
+                self.alloc(&list)
+            };
+
+            let child_offsets: Vec<u32> = key
+                .children
+                .iter()
+                .map(|c| self.key(c, KEY_COMP_NAME))
+                .collect();
+            let subkey_list = if child_offsets.is_empty() {
+                u32::MAX
+            } else if self.use_index_root {
+                let leaves: Vec<u32> = child_offsets
+                    .chunks(1)
+                    .map(|chunk| self.offsets_cell(b"li", chunk))
+                    .collect();
+                self.offsets_cell(b"ri", &leaves)
+            } else {
+                // "lh" entries are (offset, name hash) pairs; the reader ignores the hash
+                let mut cell = b"lh".to_vec();
+                cell.extend_from_slice(&(child_offsets.len() as u16).to_le_bytes());
+                for o in &child_offsets {
+                    cell.extend_from_slice(&o.to_le_bytes());
+                    cell.extend_from_slice(&[0; 4]);
+                }
+                self.alloc(&cell)
+            };
+
+            let mut nk = b"nk".to_vec();
+            nk.extend_from_slice(&flags.to_le_bytes());
+            nk.extend_from_slice(&[0; 8]); // last written
+            nk.extend_from_slice(&[0; 4]); // access bits
+            nk.extend_from_slice(&[0; 4]); // parent
+            nk.extend_from_slice(&(child_offsets.len() as u32).to_le_bytes());
+            nk.extend_from_slice(&[0; 4]); // volatile subkey count
+            nk.extend_from_slice(&subkey_list.to_le_bytes());
+            nk.extend_from_slice(&u32::MAX.to_le_bytes()); // volatile subkey list
+            nk.extend_from_slice(&(value_offsets.len() as u32).to_le_bytes());
+            nk.extend_from_slice(&value_list.to_le_bytes());
+            nk.extend_from_slice(&u32::MAX.to_le_bytes()); // security
+            nk.extend_from_slice(&u32::MAX.to_le_bytes()); // class name
+            nk.extend_from_slice(&[0; 20]); // max name/class/value lengths, work var
+            nk.extend_from_slice(&(key.name.len() as u16).to_le_bytes());
+            nk.extend_from_slice(&[0, 0]); // class name length
+            nk.extend_from_slice(key.name.as_bytes());
+            self.alloc(&nk)
+        }
+    }
+
+    /// Builds a minimal format 1.5 hive with `root` as its root key.
+    fn build_hive(root: &TestKey, use_index_root: bool) -> Vec<u8> {
+        let mut builder = HiveBuilder {
+            bins: vec![0; 32],
+            use_index_root,
+        };
+        let root_offset = builder.key(root, 0x2C);
+        let bins_len = builder.bins.len().div_ceil(4096) * 4096;
+        builder.bins.resize(bins_len, 0);
+        builder.bins[..4].copy_from_slice(b"hbin");
+        builder.bins[8..12].copy_from_slice(&(bins_len as u32).to_le_bytes());
+
+        let mut base = vec![0u8; BASE_BLOCK_SIZE];
+        base[..4].copy_from_slice(b"regf");
+        base[0x04..0x08].copy_from_slice(&1u32.to_le_bytes());
+        base[0x08..0x0C].copy_from_slice(&1u32.to_le_bytes());
+        base[0x14..0x18].copy_from_slice(&1u32.to_le_bytes());
+        base[0x18..0x1C].copy_from_slice(&5u32.to_le_bytes());
+        base[0x20..0x24].copy_from_slice(&1u32.to_le_bytes());
+        base[0x24..0x28].copy_from_slice(&root_offset.to_le_bytes());
+        base[0x28..0x2C].copy_from_slice(&(bins_len as u32).to_le_bytes());
+        base.extend_from_slice(&builder.bins);
+        base
+    }
+
+    /// An NTUSER.DAT-like tree holding both Night Light blobs plus some unrelated keys.
+    fn ntuser() -> TestKey {
+        let mut root = TestKey::new("ROOT")
+            .child(TestKey::new("Console").value("FontSize", 4, &[0x00, 0x00, 0x10, 0x00]))
+            .child(TestKey::new("Environment"));
+        root.insert(NightlightBlob::Settings.reg_key(), "Data", &SETTINGS_BYTES);
+        root.insert(
+            NightlightBlob::State.reg_key(),
+            "Data",
+            &STATE_ENABLED_BYTES,
+        );
+        root
+    }
+
+    #[test]
+    fn backend_reads_nightlight_blobs() {
+        let backend = HiveBackend::new(Hive::parse(build_hive(&ntuser(), false)).unwrap());
+        assert_eq!(backend.read_settings_bytes().unwrap(), SETTINGS_BYTES);
+        assert_eq!(backend.read_state_bytes().unwrap(), STATE_ENABLED_BYTES);
+
+        let mgr = NightlightManager::new(&backend);
+        assert_eq!(mgr.get_settings().unwrap().color_temperature, 2790);
+        assert!(mgr.get_state().unwrap().is_enabled);
+    }
+
+    #[test]
+    fn backend_is_read_only() {
+        let backend = HiveBackend::new(Hive::parse(build_hive(&ntuser(), false)).unwrap());
+        assert!(matches!(
+            backend.write_settings_bytes(&SETTINGS_BYTES),
+            Err(NightlightError::ReadOnlyBackend)
+        ));
+        assert!(matches!(
+            NightlightManager::new(&backend).disable(),
+            Err(NightlightError::ReadOnlyBackend)
+        ));
+    }
+
+    #[test]
+    fn missing_key_is_missing_data() {
+        let mut root = TestKey::new("ROOT");
+        root.insert(NightlightBlob::Settings.reg_key(), "Data", &SETTINGS_BYTES);
+        let backend = HiveBackend::new(Hive::parse(build_hive(&root, false)).unwrap());
+        assert!(matches!(
+            backend.read_state_bytes(),
+            Err(NightlightError::MissingData(NightlightBlob::State))
+        ));
+    }
+
+    #[test]
+    fn lookups_are_case_insensitive() {
+        let hive = Hive::parse(build_hive(&ntuser(), false)).unwrap();
+        let key = hive
+            .open_key(&NightlightBlob::State.reg_key().to_uppercase())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            key.name().unwrap(),
+            "windows.data.bluelightreduction.bluelightreductionstate"
+        );
+        assert_eq!(
+            key.value("DATA").unwrap().unwrap().data,
+            STATE_ENABLED_BYTES
+        );
+        assert!(hive.open_key(r"Software\Nope").unwrap().is_none());
+    }
+
+    #[test]
+    fn index_root_subkey_lists() {
+        let hive = Hive::parse(build_hive(&ntuser(), true)).unwrap();
+        let names: Vec<String> = hive
+            .root_key()
+            .subkeys()
+            .unwrap()
+            .iter()
+            .map(|k| k.name().unwrap())
+            .collect();
+        assert_eq!(names, ["Console", "Environment", "Software"]);
+        assert_eq!(
+            HiveBackend::new(hive).read_settings_bytes().unwrap(),
+            SETTINGS_BYTES
+        );
+    }
+
+    #[test]
+    fn inline_and_big_data_values() {
+        let big: Vec<u8> = (0..40_000u32).map(|i| i as u8).collect();
+        let root = TestKey::new("ROOT").child(
+            TestKey::new("Blobs")
+                .value("Tiny", REG_BINARY, &[0xAB, 0xCD])
+                .value("Big", REG_BINARY, &big),
+        );
+        let hive = Hive::parse(build_hive(&root, false)).unwrap();
+        let key = hive.open_key("Blobs").unwrap().unwrap();
+
+        let tiny = key.value("Tiny").unwrap().unwrap();
+        assert_eq!(tiny.data, [0xAB, 0xCD]);
+        assert_eq!(tiny.value_type, REG_BINARY);
+        assert_eq!(key.value("Big").unwrap().unwrap().data, big);
+    }
+
+    #[test]
+    fn rejects_oversized_inline_data() {
+        let root = TestKey::new("ROOT").child(TestKey::new("Blobs").value(
+            "Tiny",
+            REG_BINARY,
+            &[0xAB, 0xCD],
+        ));
+        let mut hive = build_hive(&root, false);
+        // The vk cell of "Tiny": signature, name length, then the inline size of 2
+        let vk = b"vk\x04\x00\x02\x00\x00\x80";
+        let pos = hive.windows(vk.len()).position(|w| w == vk).unwrap();
+        hive[pos + 4..pos + 8].copy_from_slice(&(5 | DATA_IN_OFFSET).to_le_bytes());
+
+        let hive = Hive::parse(hive).unwrap();
+        let key = hive.open_key("Blobs").unwrap().unwrap();
+        assert!(matches!(key.value("Tiny"), Err(HiveError::InvalidCell(_))));
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert_eq!(
+            Hive::parse(vec![0; 8192]).err(),
+            Some(HiveError::InvalidSignature)
+        );
+
+        let mut hive = build_hive(&ntuser(), false);
+        hive[0x18..0x1C].copy_from_slice(&9u32.to_le_bytes());
+        assert_eq!(
+            Hive::parse(hive).err(),
+            Some(HiveError::UnsupportedVersion(1, 9))
+        );
+
+        let mut hive = build_hive(&ntuser(), false);
+        hive[0x24..0x28].copy_from_slice(&0x00FF_FFFFu32.to_le_bytes());
+        assert_eq!(
+            Hive::parse(hive).err(),
+            Some(HiveError::CellOutOfBounds(0x00FF_FFFF))
+        );
+    }
+}
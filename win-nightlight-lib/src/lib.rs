@@ -1,33 +1,138 @@
-pub(crate) mod bond;
-mod cloudstore;
+// Lets `#[derive(BondStruct)]` output refer to `::win_nightlight_lib` from inside this crate
+extern crate self as win_nightlight_lib;
+
+/// Microsoft Bond CompactBinary v1 codec, usable on its own for other Bond payloads.
+///
+/// This module is a stable public API: its items only change incompatibly in a major
+/// release (a minor one while the crate is 0.x). Every public item is documented.
+#[warn(missing_docs)]
+pub mod bond;
+pub mod cloudstore;
+pub mod color;
+pub mod dump;
+pub mod effective;
+pub mod explain;
+pub mod fade;
+mod file;
+pub mod hex;
+pub mod hive;
+mod memory;
 pub mod nightlight_settings;
 pub mod nightlight_state;
-
-pub use bond::BondError;
-
-use chrono::NaiveTime;
+pub mod reg;
+#[cfg(all(windows, feature = "registry"))]
+mod registry;
+pub mod solar;
+pub mod temperature;
+
+pub use bond::{BondError, BondStruct, BondType, BondValue};
+pub use cloudstore::CloudStoreEnvelope;
+pub use effective::{ActiveReason, EffectiveStatus};
+pub use file::{BlobFormat, FileBackend};
+pub use hive::HiveBackend;
+pub use memory::{MemoryBackend, MemoryWrite};
+pub use reg::RegFileBackend;
+#[cfg(all(windows, feature = "registry"))]
+pub use registry::RegistryBackend;
+
+use std::fmt;
+
+use chrono::{NaiveDateTime, NaiveTime};
 use nightlight_settings::{NightlightSettings, ScheduleMode, SettingsError};
 use nightlight_state::NightlightState;
+use temperature::TemperatureTarget;
 use thiserror::Error;
-use windows_registry::{CURRENT_USER, Value};
+#[cfg(all(windows, feature = "registry"))]
 use windows_result::Error as WindowsError;
 
-const SETTINGS_REG_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.settings\windows.data.bluelightreduction.settings";
-const STATE_REG_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.bluelightreductionstate\windows.data.bluelightreduction.bluelightreductionstate";
-const DATA_REG_KEY_NAME: &str = "Data";
+/// Registry key (under `HKEY_CURRENT_USER`) holding the Night Light settings blob.
+pub const SETTINGS_REG_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.settings\windows.data.bluelightreduction.settings";
+/// Registry key (under `HKEY_CURRENT_USER`) holding the Night Light state blob.
+pub const STATE_REG_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.bluelightreductionstate\windows.data.bluelightreduction.bluelightreductionstate";
+/// Name of the `REG_BINARY` value under both keys.
+pub const DATA_REG_KEY_NAME: &str = "Data";
 
 #[derive(Error, Debug)]
 pub enum NightlightError {
+    #[cfg(all(windows, feature = "registry"))]
     #[error("Failed to open registry key")]
     OpenRegistryKey(WindowsError),
+    #[cfg(all(windows, feature = "registry"))]
     #[error("Failed to read registry value")]
     ReadRegistryValue(WindowsError),
+    #[cfg(all(windows, feature = "registry"))]
     #[error("Failed to write registry value")]
     WriteRegistryValue(WindowsError),
+    #[error("Backend I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Backend error: {0}")]
+    Backend(String),
+    #[error("Invalid hex data: {0}")]
+    InvalidHex(#[from] hex::HexError),
+    #[error("Invalid .reg file: {0}")]
+    InvalidRegFile(#[from] reg::RegError),
+    #[error("Invalid registry hive: {0}")]
+    InvalidHive(#[from] hive::HiveError),
+    #[error("Backend is read-only")]
+    ReadOnlyBackend,
+    #[error("No {0} data stored in backend")]
+    MissingData(NightlightBlob),
+    #[error("The {0} data was modified by another writer since it was read")]
+    ConcurrentModification(NightlightBlob),
+    #[error("{error}; restoring the original data also failed: {rollback}")]
+    RollbackFailed {
+        /// The failure that aborted the transaction.
+        error: Box<NightlightError>,
+        /// The failure while restoring the blobs written before it.
+        rollback: Box<NightlightError>,
+    },
     #[error("Failed to deserialize data: {0}")]
     DeserializeData(BondError),
     #[error("{0}")]
     InvalidSettings(#[from] SettingsError),
+    #[error("{0}")]
+    InvalidTemperature(#[from] temperature::TemperatureError),
+}
+
+/// Identifies one of the two Night Light registry blobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NightlightBlob {
+    Settings,
+    State,
+}
+
+impl NightlightBlob {
+    /// Registry key (under `HKEY_CURRENT_USER`) holding this blob's `Data` value.
+    pub fn reg_key(&self) -> &'static str {
+        match self {
+            NightlightBlob::Settings => SETTINGS_REG_KEY,
+            NightlightBlob::State => STATE_REG_KEY,
+        }
+    }
+
+    /// Guesses whether a CloudStore blob holds settings or state from the types of its inner
+    /// payload's fields. Returns `None` for malformed blobs and unrecognized payloads.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let (_, payload) = cloudstore::cloudstore_unwrap(data).ok()?;
+        let mut reader = bond::CompactBinaryReader::new(payload);
+        reader.read_marshaled_header().ok()?;
+        let inner = reader.read_struct().ok()?;
+        match inner.get(20).map(BondValue::bond_type) {
+            Some(BondType::Struct) => Some(NightlightBlob::Settings),
+            Some(BondType::UInt64) => Some(NightlightBlob::State),
+            _ if inner.has(40) => Some(NightlightBlob::Settings),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for NightlightBlob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NightlightBlob::Settings => write!(f, "settings"),
+            NightlightBlob::State => write!(f, "state"),
+        }
+    }
 }
 
 /// Abstraction over the registry backend for reading/writing nightlight data.
@@ -38,66 +143,79 @@ pub trait NightlightBackend {
     fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError>;
 }
 
-/// Windows Registry backend implementation.
-pub struct RegistryBackend;
-
-impl RegistryBackend {
-    fn read_registry_data(reg_key: &str) -> Result<Vec<u8>, NightlightError> {
-        let key = CURRENT_USER
-            .options()
-            .read()
-            .open(reg_key)
-            .map_err(NightlightError::OpenRegistryKey)?;
-        let data: Value = key
-            .get_value(DATA_REG_KEY_NAME)
-            .map_err(NightlightError::ReadRegistryValue)?;
-        Ok(data.to_vec())
-    }
-
-    fn write_registry_data(reg_key: &str, bytes: &[u8]) -> Result<(), NightlightError> {
-        let key = CURRENT_USER
-            .options()
-            .write()
-            .open(reg_key)
-            .map_err(NightlightError::OpenRegistryKey)?;
-        key.set_value(DATA_REG_KEY_NAME, &Value::from(bytes))
-            .map_err(NightlightError::WriteRegistryValue)
+impl<B: NightlightBackend + ?Sized> NightlightBackend for &B {
+    fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        (**self).read_settings_bytes()
+    }
+
+    fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        (**self).write_settings_bytes(data)
+    }
+
+    fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        (**self).read_state_bytes()
+    }
+
+    fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        (**self).write_state_bytes(data)
     }
 }
 
-impl NightlightBackend for RegistryBackend {
+impl<B: NightlightBackend + ?Sized> NightlightBackend for Box<B> {
     fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
-        Self::read_registry_data(SETTINGS_REG_KEY)
+        (**self).read_settings_bytes()
     }
 
     fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
-        Self::write_registry_data(SETTINGS_REG_KEY, data)
+        (**self).write_settings_bytes(data)
     }
 
     fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
-        Self::read_registry_data(STATE_REG_KEY)
+        (**self).read_state_bytes()
     }
 
     fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
-        Self::write_registry_data(STATE_REG_KEY, data)
+        (**self).write_state_bytes(data)
     }
 }
 
 /// High-level interface for reading/writing Night Light settings and state.
+///
+/// The composite operations (`enable`, `disable`, `set_schedule`, `set_color_temperature`)
+/// are read-modify-write cycles. Before each write the blob is read again and compared with
+/// the bytes the change was based on (which include the CloudStore timestamp); if Windows or
+/// another tool wrote in between, the write is refused with
+/// [NightlightError::ConcurrentModification] instead of silently overwriting that change. Use
+/// [NightlightManager::with_retries] to re-run the whole operation on fresh data instead.
 pub struct NightlightManager<B: NightlightBackend> {
     backend: B,
+    retries: u32,
 }
 
 impl<B: NightlightBackend> NightlightManager<B> {
     pub fn new(backend: B) -> Self {
-        Self { backend }
+        Self {
+            backend,
+            retries: 0,
+        }
+    }
+
+    /// Sets how many times a composite operation is retried after a concurrent modification
+    /// before the error is returned. Defaults to 0.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Returns the underlying backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
     }
 
     // -- Primitive operations --
 
     pub fn get_settings(&self) -> Result<NightlightSettings, NightlightError> {
-        let bytes = self.backend.read_settings_bytes()?;
-        NightlightSettings::deserialize_from_bytes(&bytes).map_err(NightlightError::DeserializeData)
+        self.read_settings_snapshot().map(|(_, settings)| settings)
     }
 
     pub fn set_settings(&self, settings: &NightlightSettings) -> Result<(), NightlightError> {
@@ -106,38 +224,169 @@ impl<B: NightlightBackend> NightlightManager<B> {
     }
 
     pub fn get_state(&self) -> Result<NightlightState, NightlightError> {
-        let bytes = self.backend.read_state_bytes()?;
-        NightlightState::deserialize_from_bytes(&bytes).map_err(NightlightError::DeserializeData)
+        self.read_state_snapshot().map(|(_, state)| state)
     }
 
     pub fn set_state(&self, state: &NightlightState) -> Result<(), NightlightError> {
         self.backend.write_state_bytes(&state.serialize_to_bytes())
     }
 
-    // -- Composite operations --
+    /// Reads both blobs and computes whether Night Light is actually active at the local time
+    /// `now`. See [effective::effective_status].
+    pub fn get_effective_status(
+        &self,
+        now: NaiveDateTime,
+    ) -> Result<EffectiveStatus, NightlightError> {
+        Ok(effective::effective_status(
+            &self.get_settings()?,
+            &self.get_state()?,
+            now,
+        ))
+    }
 
-    /// Enables nightlight (force-on), ignoring schedule mode.
-    pub fn enable(&self) -> Result<(), NightlightError> {
-        let mut state = self.get_state()?;
+    // -- Compare-and-swap helpers --
+
+    fn read_blob(&self, blob: NightlightBlob) -> Result<Vec<u8>, NightlightError> {
+        match blob {
+            NightlightBlob::Settings => self.backend.read_settings_bytes(),
+            NightlightBlob::State => self.backend.read_state_bytes(),
+        }
+    }
+
+    fn write_blob(&self, blob: NightlightBlob, data: &[u8]) -> Result<(), NightlightError> {
+        match blob {
+            NightlightBlob::Settings => self.backend.write_settings_bytes(data),
+            NightlightBlob::State => self.backend.write_state_bytes(data),
+        }
+    }
+
+    /// Writes `data` only if the stored blob still equals `expected`.
+    ///
+    /// The backend trait has no atomic primitive, so a writer can still slip in between the
+    /// re-read and the write; this narrows the window to a single backend round trip.
+    fn compare_and_swap(
+        &self,
+        blob: NightlightBlob,
+        expected: &[u8],
+        data: &[u8],
+    ) -> Result<(), NightlightError> {
+        if self.read_blob(blob)? != expected {
+            return Err(NightlightError::ConcurrentModification(blob));
+        }
+        self.write_blob(blob, data)
+    }
+
+    fn read_settings_snapshot(&self) -> Result<(Vec<u8>, NightlightSettings), NightlightError> {
+        let bytes = self.backend.read_settings_bytes()?;
+        let settings = NightlightSettings::deserialize_from_bytes(&bytes)
+            .map_err(NightlightError::DeserializeData)?;
+        Ok((bytes, settings))
+    }
+
+    fn read_state_snapshot(&self) -> Result<(Vec<u8>, NightlightState), NightlightError> {
+        let bytes = self.backend.read_state_bytes()?;
+        let state = NightlightState::deserialize_from_bytes(&bytes)
+            .map_err(NightlightError::DeserializeData)?;
+        Ok((bytes, state))
+    }
+
+    /// Runs `op`, re-running it from scratch on a concurrent modification up to `retries` times.
+    fn retrying<T>(
+        &self,
+        mut op: impl FnMut() -> Result<T, NightlightError>,
+    ) -> Result<T, NightlightError> {
+        let mut attempts = 0;
+        loop {
+            match op() {
+                Err(NightlightError::ConcurrentModification(_)) if attempts < self.retries => {
+                    attempts += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Enables the state blob if needed, based on a fresh read.
+    fn enable_state(&self) -> Result<(), NightlightError> {
+        let (bytes, mut state) = self.read_state_snapshot()?;
         if state.enable() {
-            self.set_state(&state)?;
+            self.compare_and_swap(NightlightBlob::State, &bytes, &state.serialize_to_bytes())?;
         }
         Ok(())
     }
 
-    /// Disables nightlight and turns off any schedule.
-    pub fn disable(&self) -> Result<(), NightlightError> {
-        let mut settings = self.get_settings()?;
-        if settings.set_mode(ScheduleMode::Off) {
-            self.set_settings(&settings)?;
-        }
-        let mut state = self.get_state()?;
-        if state.disable() {
-            self.set_state(&state)?;
+    /// Applies the staged writes in order, restoring the already-written blobs on failure.
+    fn commit(&self, writes: &[(NightlightBlob, &[u8], Vec<u8>)]) -> Result<(), NightlightError> {
+        for (i, (blob, original, data)) in writes.iter().enumerate() {
+            if let Err(error) = self.compare_and_swap(*blob, original, data) {
+                // Undo in reverse order, attempting every blob even if one restore fails
+                let mut rollback = Ok(());
+                for (blob, original, _) in writes[..i].iter().rev() {
+                    let restored = self.write_blob(*blob, original);
+                    rollback = rollback.and(restored);
+                }
+                return Err(match rollback {
+                    Ok(()) => error,
+                    Err(rollback) => NightlightError::RollbackFailed {
+                        error: Box::new(error),
+                        rollback: Box::new(rollback),
+                    },
+                });
+            }
         }
         Ok(())
     }
 
+    // -- Composite operations --
+
+    /// Changes settings and state together, all-or-nothing.
+    ///
+    /// Both blobs are read first and `f` edits copies of them. Blobs whose serialized form
+    /// changed are then written, state before settings. If a write fails, the blobs written
+    /// before it are restored to their original bytes and the failure is returned; if restoring
+    /// fails too, [NightlightError::RollbackFailed] reports both errors. On a concurrent
+    /// modification the whole transaction, including `f`, is retried as configured with
+    /// [NightlightManager::with_retries].
+    pub fn transaction<F>(&self, mut f: F) -> Result<(), NightlightError>
+    where
+        F: FnMut(&mut NightlightSettings, &mut NightlightState) -> Result<(), NightlightError>,
+    {
+        self.retrying(|| {
+            let (settings_bytes, mut settings) = self.read_settings_snapshot()?;
+            let (state_bytes, mut state) = self.read_state_snapshot()?;
+            f(&mut settings, &mut state)?;
+
+            let mut writes = Vec::with_capacity(2);
+            let new_state = state.serialize_to_bytes();
+            if new_state != state_bytes {
+                writes.push((NightlightBlob::State, state_bytes.as_slice(), new_state));
+            }
+            let new_settings = settings.serialize_to_bytes();
+            if new_settings != settings_bytes {
+                writes.push((
+                    NightlightBlob::Settings,
+                    settings_bytes.as_slice(),
+                    new_settings,
+                ));
+            }
+            self.commit(&writes)
+        })
+    }
+
+    /// Enables nightlight (force-on), ignoring schedule mode.
+    pub fn enable(&self) -> Result<(), NightlightError> {
+        self.retrying(|| self.enable_state())
+    }
+
+    /// Disables nightlight and turns off any schedule.
+    pub fn disable(&self) -> Result<(), NightlightError> {
+        self.transaction(|settings, state| {
+            settings.set_mode(ScheduleMode::Off);
+            state.disable();
+            Ok(())
+        })
+    }
+
     /// Sets the schedule mode, optionally overriding start/end times for manual mode.
     pub fn set_schedule(
         &self,
@@ -149,52 +398,398 @@ impl<B: NightlightBackend> NightlightManager<B> {
             return Err(SettingsError::InvalidScheduleTimeOverride.into());
         }
 
-        let mut settings = self.get_settings()?;
-        let mut changed = settings.set_mode(mode);
+        self.transaction(|settings, state| {
+            let mut changed = settings.set_mode(mode);
 
-        if let Some(t) = start {
-            changed |= settings.set_start_time(t);
-        }
-        if let Some(t) = end {
-            changed |= settings.set_end_time(t);
-        }
+            if let Some(t) = start {
+                changed |= settings.set_start_time(t);
+            }
+            if let Some(t) = end {
+                changed |= settings.set_end_time(t);
+            }
 
-        if changed {
-            if mode != ScheduleMode::Off {
-                let mut state = self.get_state()?;
-                if state.enable() {
-                    self.set_state(&state)?;
-                }
+            if changed && mode != ScheduleMode::Off {
+                state.enable();
             }
-            self.set_settings(&settings)?;
-        }
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Sets the color temperature (1200-6500 Kelvin).
     pub fn set_color_temperature(&self, temperature: u16) -> Result<(), NightlightError> {
-        let mut settings = self.get_settings()?;
-        if settings.set_color_temperature(temperature)? {
-            self.set_settings(&settings)?;
-        }
-        Ok(())
+        self.retrying(|| {
+            let (bytes, mut settings) = self.read_settings_snapshot()?;
+            if settings.set_color_temperature(temperature)? {
+                self.compare_and_swap(
+                    NightlightBlob::Settings,
+                    &bytes,
+                    &settings.serialize_to_bytes(),
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Sets the color temperature from a [TemperatureTarget] resolved against the stored one,
+    /// e.g. `+300`, `-10%` or `warm`. Returns the temperature that is now set.
+    pub fn adjust_color_temperature(
+        &self,
+        target: TemperatureTarget,
+    ) -> Result<u16, NightlightError> {
+        self.retrying(|| {
+            let (bytes, mut settings) = self.read_settings_snapshot()?;
+            let temperature = target.resolve(settings.color_temperature)?;
+            if settings.set_color_temperature(temperature)? {
+                self.compare_and_swap(
+                    NightlightBlob::Settings,
+                    &bytes,
+                    &settings.serialize_to_bytes(),
+                )?;
+            }
+            Ok(temperature)
+        })
     }
 }
 
 // -- Convenience free functions (backward compatibility) --
+// These always talk to the Windows Registry, so they are only available with the `registry`
+// feature on Windows targets.
 
+#[cfg(all(windows, feature = "registry"))]
 pub fn get_nightlight_settings() -> Result<NightlightSettings, NightlightError> {
     NightlightManager::new(RegistryBackend).get_settings()
 }
 
+#[cfg(all(windows, feature = "registry"))]
 pub fn set_nightlight_settings(settings: &NightlightSettings) -> Result<(), NightlightError> {
     NightlightManager::new(RegistryBackend).set_settings(settings)
 }
 
+#[cfg(all(windows, feature = "registry"))]
 pub fn get_nightlight_state() -> Result<NightlightState, NightlightError> {
     NightlightManager::new(RegistryBackend).get_state()
 }
 
+#[cfg(all(windows, feature = "registry"))]
 pub fn set_nightlight_state(state: &NightlightState) -> Result<(), NightlightError> {
     NightlightManager::new(RegistryBackend).set_state(state)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use chrono::NaiveTime;
+
+    use super::*;
+    use crate::nightlight_settings::SettingsEncoding;
+    use crate::nightlight_state::StateEncoding;
+
+    fn settings() -> NightlightSettings {
+        NightlightSettings {
+            timestamp: 1742540908,
+            schedule_mode: ScheduleMode::Off,
+            color_temperature: 2790,
+            start_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            sunset_time: NaiveTime::from_hms_opt(19, 23, 0).unwrap(),
+            sunrise_time: NaiveTime::from_hms_opt(7, 12, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        }
+    }
+
+    fn state(is_enabled: bool) -> NightlightState {
+        NightlightState {
+            timestamp: 1742670473,
+            is_enabled,
+            initialized: 1,
+            last_transition_filetime: 133_871_411_809_270_569,
+            unknown_fields: BondStruct::new(),
+            encoding: StateEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        }
+    }
+
+    /// Wraps a [MemoryBackend] and simulates another writer: after each read of `blob`, the
+    /// next queued blob (if any) is stored behind the reader's back.
+    struct Interfering {
+        inner: MemoryBackend,
+        blob: NightlightBlob,
+        queued: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl Interfering {
+        fn new(inner: MemoryBackend, blob: NightlightBlob, mut queued: Vec<Vec<u8>>) -> Self {
+            queued.reverse();
+            Self {
+                inner,
+                blob,
+                queued: Mutex::new(queued),
+            }
+        }
+
+        fn read(&self, blob: NightlightBlob) -> Result<Vec<u8>, NightlightError> {
+            let data = match blob {
+                NightlightBlob::Settings => self.inner.read_settings_bytes()?,
+                NightlightBlob::State => self.inner.read_state_bytes()?,
+            };
+            if blob == self.blob
+                && let Some(next) = self.queued.lock().unwrap().pop()
+            {
+                self.inner.seed(blob, next);
+            }
+            Ok(data)
+        }
+    }
+
+    impl NightlightBackend for Interfering {
+        fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+            self.read(NightlightBlob::Settings)
+        }
+
+        fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+            self.inner.write_settings_bytes(data)
+        }
+
+        fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+            self.read(NightlightBlob::State)
+        }
+
+        fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+            self.inner.write_state_bytes(data)
+        }
+    }
+
+    /// Wraps a [MemoryBackend] and fails writes according to a script: the n-th write fails
+    /// if the n-th entry is `true`. Writes beyond the script succeed.
+    struct Failing {
+        inner: MemoryBackend,
+        script: Mutex<Vec<bool>>,
+    }
+
+    impl Failing {
+        fn new(inner: MemoryBackend, mut script: Vec<bool>) -> Self {
+            script.reverse();
+            Self {
+                inner,
+                script: Mutex::new(script),
+            }
+        }
+
+        fn write(&self, blob: NightlightBlob, data: &[u8]) -> Result<(), NightlightError> {
+            if self.script.lock().unwrap().pop() == Some(true) {
+                return Err(NightlightError::Backend(format!("{blob} write failed")));
+            }
+            match blob {
+                NightlightBlob::Settings => self.inner.write_settings_bytes(data),
+                NightlightBlob::State => self.inner.write_state_bytes(data),
+            }
+        }
+    }
+
+    impl NightlightBackend for Failing {
+        fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+            self.inner.read_settings_bytes()
+        }
+
+        fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+            self.write(NightlightBlob::Settings, data)
+        }
+
+        fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+            self.inner.read_state_bytes()
+        }
+
+        fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+            self.write(NightlightBlob::State, data)
+        }
+    }
+
+    /// Settings as written by another tool: a different start time and a newer timestamp.
+    fn settings_from_other_writer(n: u64) -> Vec<u8> {
+        let mut other = settings();
+        other.timestamp += n;
+        other.start_time = NaiveTime::from_hms_opt(20, n as u32, 0).unwrap();
+        other.serialize_to_bytes()
+    }
+
+    #[test]
+    fn concurrent_write_is_not_overwritten() {
+        let other = settings_from_other_writer(1);
+        let backend = Interfering::new(
+            MemoryBackend::from_values(&settings(), &state(false)),
+            NightlightBlob::Settings,
+            vec![other.clone()],
+        );
+        let mgr = NightlightManager::new(&backend);
+        assert!(matches!(
+            mgr.set_color_temperature(3400),
+            Err(NightlightError::ConcurrentModification(
+                NightlightBlob::Settings
+            ))
+        ));
+        assert!(backend.inner.writes().is_empty());
+        assert_eq!(backend.inner.bytes(NightlightBlob::Settings), Some(other));
+    }
+
+    #[test]
+    fn retry_applies_change_on_top_of_concurrent_write() {
+        let backend = Interfering::new(
+            MemoryBackend::from_values(&settings(), &state(false)),
+            NightlightBlob::Settings,
+            vec![settings_from_other_writer(1)],
+        );
+        let mgr = NightlightManager::new(&backend).with_retries(1);
+        mgr.set_color_temperature(3400).unwrap();
+
+        let stored = mgr.get_settings().unwrap();
+        assert_eq!(stored.color_temperature, 3400);
+        assert_eq!(
+            stored.start_time,
+            NaiveTime::from_hms_opt(20, 1, 0).unwrap()
+        );
+        assert_eq!(backend.inner.writes().len(), 1);
+    }
+
+    #[test]
+    fn retries_are_bounded() {
+        let backend = Interfering::new(
+            MemoryBackend::from_values(&settings(), &state(false)),
+            NightlightBlob::Settings,
+            (1..=10).map(settings_from_other_writer).collect(),
+        );
+        let mgr = NightlightManager::new(&backend).with_retries(2);
+        assert!(matches!(
+            mgr.set_schedule(ScheduleMode::SunsetToSunrise, None, None),
+            Err(NightlightError::ConcurrentModification(
+                NightlightBlob::Settings
+            ))
+        ));
+        // Three attempts of two settings reads each
+        assert_eq!(backend.queued.lock().unwrap().len(), 4);
+        assert!(
+            backend
+                .inner
+                .writes()
+                .iter()
+                .all(|w| w.blob == NightlightBlob::State)
+        );
+    }
+
+    #[test]
+    fn transaction_writes_both_blobs() {
+        let backend = MemoryBackend::from_values(&settings(), &state(false));
+        let mgr = NightlightManager::new(&backend);
+        mgr.transaction(|settings, state| {
+            settings.set_color_temperature(4000)?;
+            state.enable();
+            Ok(())
+        })
+        .unwrap();
+
+        let order: Vec<_> = backend.writes().iter().map(|w| w.blob).collect();
+        assert_eq!(order, [NightlightBlob::State, NightlightBlob::Settings]);
+        assert_eq!(mgr.get_settings().unwrap().color_temperature, 4000);
+        assert!(mgr.get_state().unwrap().is_enabled);
+    }
+
+    #[test]
+    fn transaction_skips_unchanged_blobs() {
+        let backend = MemoryBackend::from_values(&settings(), &state(true));
+        let mgr = NightlightManager::new(&backend);
+        mgr.transaction(|_, state| {
+            state.enable();
+            Ok(())
+        })
+        .unwrap();
+        assert!(backend.writes().is_empty());
+    }
+
+    #[test]
+    fn transaction_closure_error_writes_nothing() {
+        let backend = MemoryBackend::from_values(&settings(), &state(false));
+        let mgr = NightlightManager::new(&backend);
+        let result = mgr.transaction(|settings, state| {
+            state.enable();
+            settings.set_color_temperature(100)?;
+            Ok(())
+        });
+        assert!(matches!(result, Err(NightlightError::InvalidSettings(_))));
+        assert!(backend.writes().is_empty());
+    }
+
+    #[test]
+    fn failed_second_write_restores_first() {
+        let original_state = state(false).serialize_to_bytes();
+        let backend = Failing::new(
+            MemoryBackend::from_values(&settings(), &state(false)),
+            vec![false, true],
+        );
+        let mgr = NightlightManager::new(&backend);
+        let result = mgr.set_schedule(ScheduleMode::SunsetToSunrise, None, None);
+        assert!(matches!(
+            result,
+            Err(NightlightError::Backend(msg)) if msg == "settings write failed"
+        ));
+
+        // The state write went through, then was undone
+        let writes = backend.inner.writes();
+        assert_eq!(writes.len(), 2);
+        assert!(writes.iter().all(|w| w.blob == NightlightBlob::State));
+        assert_eq!(writes[1].data, original_state);
+        assert_eq!(
+            backend.inner.bytes(NightlightBlob::State),
+            Some(original_state)
+        );
+    }
+
+    #[test]
+    fn failed_rollback_reports_both_errors() {
+        let backend = Failing::new(
+            MemoryBackend::from_values(&settings(), &state(false)),
+            vec![false, true, true],
+        );
+        let mgr = NightlightManager::new(&backend);
+        let err = mgr
+            .set_schedule(ScheduleMode::SunsetToSunrise, None, None)
+            .unwrap_err();
+        match &err {
+            NightlightError::RollbackFailed { error, rollback } => {
+                assert!(
+                    matches!(&**error, NightlightError::Backend(m) if m == "settings write failed")
+                );
+                assert!(
+                    matches!(&**rollback, NightlightError::Backend(m) if m == "state write failed")
+                );
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert_eq!(
+            err.to_string(),
+            "Backend error: settings write failed; restoring the original data also failed: \
+             Backend error: state write failed"
+        );
+    }
+
+    #[test]
+    fn concurrent_modification_rolls_back_transaction() {
+        let backend = Interfering::new(
+            MemoryBackend::from_values(&settings(), &state(false)),
+            NightlightBlob::Settings,
+            vec![settings_from_other_writer(1)],
+        );
+        let mgr = NightlightManager::new(&backend);
+        assert!(matches!(
+            mgr.set_schedule(ScheduleMode::SunsetToSunrise, None, None),
+            Err(NightlightError::ConcurrentModification(
+                NightlightBlob::Settings
+            ))
+        ));
+        assert_eq!(
+            backend.inner.bytes(NightlightBlob::State),
+            Some(state(false).serialize_to_bytes())
+        );
+    }
+}
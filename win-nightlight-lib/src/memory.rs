@@ -0,0 +1,271 @@
+use std::sync::{Mutex, MutexGuard};
+
+use crate::nightlight_settings::NightlightSettings;
+use crate::nightlight_state::NightlightState;
+use crate::{NightlightBackend, NightlightBlob, NightlightError};
+
+/// A single write recorded by [MemoryBackend].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryWrite {
+    /// Position of this write across all blobs, starting at 0.
+    pub seq: u64,
+    /// Which blob was written.
+    pub blob: NightlightBlob,
+    /// The bytes that were written.
+    pub data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct MemoryData {
+    settings: Option<Vec<u8>>,
+    state: Option<Vec<u8>>,
+    writes: Vec<MemoryWrite>,
+    next_seq: u64,
+}
+
+impl MemoryData {
+    fn blob(&self, blob: NightlightBlob) -> &Option<Vec<u8>> {
+        match blob {
+            NightlightBlob::Settings => &self.settings,
+            NightlightBlob::State => &self.state,
+        }
+    }
+
+    fn blob_mut(&mut self, blob: NightlightBlob) -> &mut Option<Vec<u8>> {
+        match blob {
+            NightlightBlob::Settings => &mut self.settings,
+            NightlightBlob::State => &mut self.state,
+        }
+    }
+}
+
+/// In-memory backend holding the settings and state blobs as byte buffers.
+///
+/// Intended for tests and simulations: seed it from fixture bytes or from
+/// [NightlightSettings]/[NightlightState] values, drive a [crate::NightlightManager] with it
+/// (pass `&backend` to keep access), then inspect the resulting blobs and the recorded
+/// [MemoryWrite] log.
+#[derive(Default)]
+pub struct MemoryBackend {
+    data: Mutex<MemoryData>,
+}
+
+impl MemoryBackend {
+    /// Creates a backend with no stored blobs; reads fail with [NightlightError::MissingData].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a backend seeded with raw settings and state blobs.
+    pub fn from_bytes(settings: impl Into<Vec<u8>>, state: impl Into<Vec<u8>>) -> Self {
+        Self {
+            data: Mutex::new(MemoryData {
+                settings: Some(settings.into()),
+                state: Some(state.into()),
+                ..MemoryData::default()
+            }),
+        }
+    }
+
+    /// Creates a backend seeded with the serialized form of `settings` and `state`.
+    pub fn from_values(settings: &NightlightSettings, state: &NightlightState) -> Self {
+        Self::from_bytes(settings.serialize_to_bytes(), state.serialize_to_bytes())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryData> {
+        // A panic while holding the lock cannot leave the buffers half-updated
+        self.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns a copy of the stored blob, if any.
+    pub fn bytes(&self, blob: NightlightBlob) -> Option<Vec<u8>> {
+        self.lock().blob(blob).clone()
+    }
+
+    /// Replaces the stored blob without recording a write.
+    pub fn seed(&self, blob: NightlightBlob, data: impl Into<Vec<u8>>) {
+        *self.lock().blob_mut(blob) = Some(data.into());
+    }
+
+    /// Returns every write made through [NightlightBackend] so far, in order.
+    pub fn writes(&self) -> Vec<MemoryWrite> {
+        self.lock().writes.clone()
+    }
+
+    /// Clears the write log. Sequence numbers keep increasing from where they left off.
+    pub fn clear_writes(&self) {
+        self.lock().writes.clear();
+    }
+
+    fn read(&self, blob: NightlightBlob) -> Result<Vec<u8>, NightlightError> {
+        self.bytes(blob).ok_or(NightlightError::MissingData(blob))
+    }
+
+    fn write(&self, blob: NightlightBlob, bytes: &[u8]) {
+        let mut data = self.lock();
+        let seq = data.next_seq;
+        data.next_seq += 1;
+        *data.blob_mut(blob) = Some(bytes.to_vec());
+        data.writes.push(MemoryWrite {
+            seq,
+            blob,
+            data: bytes.to_vec(),
+        });
+    }
+}
+
+impl NightlightBackend for MemoryBackend {
+    fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::Settings)
+    }
+
+    fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        self.write(NightlightBlob::Settings, data);
+        Ok(())
+    }
+
+    fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::State)
+    }
+
+    fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        self.write(NightlightBlob::State, data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+    use crate::nightlight_settings::{ScheduleMode, SettingsEncoding};
+    use crate::nightlight_state::StateEncoding;
+    use crate::{BondStruct, CloudStoreEnvelope, NightlightManager};
+
+    fn settings() -> NightlightSettings {
+        NightlightSettings {
+            timestamp: 1742540908,
+            schedule_mode: ScheduleMode::Off,
+            color_temperature: 2790,
+            start_time: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            end_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            sunset_time: NaiveTime::from_hms_opt(19, 23, 0).unwrap(),
+            sunrise_time: NaiveTime::from_hms_opt(7, 12, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        }
+    }
+
+    fn state(is_enabled: bool) -> NightlightState {
+        NightlightState {
+            timestamp: 1742670473,
+            is_enabled,
+            initialized: 1,
+            last_transition_filetime: 133_871_411_809_270_569,
+            unknown_fields: BondStruct::new(),
+            encoding: StateEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
+        }
+    }
+
+    #[test]
+    fn empty_backend_reports_missing_data() {
+        let mgr = NightlightManager::new(MemoryBackend::new());
+        assert!(matches!(
+            mgr.get_settings(),
+            Err(NightlightError::MissingData(NightlightBlob::Settings))
+        ));
+        assert!(matches!(
+            mgr.get_state(),
+            Err(NightlightError::MissingData(NightlightBlob::State))
+        ));
+    }
+
+    #[test]
+    fn seeded_values_read_back() {
+        let mgr = NightlightManager::new(MemoryBackend::from_values(&settings(), &state(false)));
+        assert_eq!(mgr.get_settings().unwrap(), settings());
+        assert_eq!(mgr.get_state().unwrap(), state(false));
+        assert!(mgr.backend().writes().is_empty());
+    }
+
+    #[test]
+    fn seeded_bytes_are_returned_verbatim() {
+        let settings_bytes = settings().serialize_to_bytes();
+        let backend = MemoryBackend::from_bytes(settings_bytes.clone(), Vec::new());
+        assert_eq!(
+            backend.bytes(NightlightBlob::Settings),
+            Some(settings_bytes.clone())
+        );
+        assert_eq!(backend.read_settings_bytes().unwrap(), settings_bytes);
+        assert_eq!(backend.bytes(NightlightBlob::State), Some(Vec::new()));
+    }
+
+    #[test]
+    fn enable_records_single_state_write() {
+        let backend = MemoryBackend::from_values(&settings(), &state(false));
+        let mgr = NightlightManager::new(&backend);
+        mgr.enable().unwrap();
+        // Already enabled: no further write
+        mgr.enable().unwrap();
+
+        let writes = backend.writes();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].seq, 0);
+        assert_eq!(writes[0].blob, NightlightBlob::State);
+        assert_eq!(
+            Some(writes[0].data.clone()),
+            backend.bytes(NightlightBlob::State)
+        );
+        assert!(mgr.get_state().unwrap().is_enabled);
+    }
+
+    #[test]
+    fn set_schedule_records_writes_in_order() {
+        let backend = MemoryBackend::from_values(&settings(), &state(false));
+        let mgr = NightlightManager::new(&backend);
+        let start = NaiveTime::from_hms_opt(21, 30, 0).unwrap();
+        mgr.set_schedule(ScheduleMode::SetHours, Some(start), None)
+            .unwrap();
+
+        let writes = backend.writes();
+        let order: Vec<_> = writes.iter().map(|w| (w.seq, w.blob)).collect();
+        assert_eq!(
+            order,
+            [(0, NightlightBlob::State), (1, NightlightBlob::Settings)]
+        );
+
+        let stored = mgr.get_settings().unwrap();
+        assert_eq!(stored.schedule_mode, ScheduleMode::SetHours);
+        assert_eq!(stored.start_time, start);
+    }
+
+    #[test]
+    fn clear_writes_keeps_sequence_increasing() {
+        let backend = MemoryBackend::from_values(&settings(), &state(false));
+        let mgr = NightlightManager::new(&backend);
+        mgr.enable().unwrap();
+        backend.clear_writes();
+        mgr.set_color_temperature(3400).unwrap();
+
+        let writes = backend.writes();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].seq, 1);
+        assert_eq!(writes[0].blob, NightlightBlob::Settings);
+    }
+
+    #[test]
+    fn seed_does_not_record_write() {
+        let backend = MemoryBackend::new();
+        backend.seed(NightlightBlob::State, state(true).serialize_to_bytes());
+        assert!(backend.writes().is_empty());
+        assert!(
+            NightlightManager::new(&backend)
+                .get_state()
+                .unwrap()
+                .is_enabled
+        );
+    }
+}
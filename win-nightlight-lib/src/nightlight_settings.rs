@@ -1,10 +1,15 @@
 use std::fmt;
 
 use crate::bond::*;
-use crate::cloudstore;
+use crate::cloudstore::{self, CloudStoreEnvelope};
 use chrono::{NaiveTime, Timelike, Utc};
 use thiserror::Error;
 
+/// Lowest color temperature accepted by Windows, in Kelvin (strongest night light).
+pub const MIN_COLOR_TEMPERATURE: u16 = 1200;
+/// Highest color temperature accepted by Windows, in Kelvin (no warming).
+pub const MAX_COLOR_TEMPERATURE: u16 = 6500;
+
 /// Scheduling modes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScheduleMode {
@@ -42,8 +47,13 @@ pub enum SettingsError {
 /// - Field 50: struct — sunset time (TimeBlock)
 /// - Field 60: struct — sunrise time (TimeBlock)
 ///
+/// Any other inner fields (including known IDs carrying an unexpected type) are kept in
+/// [NightlightSettings::unknown_fields], details like unknown fields inside the time structs
+/// in [NightlightSettings::encoding], and the CloudStore wrapper in
+/// [NightlightSettings::envelope]; all are written back unchanged on serialization.
+///
 /// See `docs/nightlight-registry-format.md` for full details.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NightlightSettings {
     /// The last-modified Unix timestamp in seconds
     pub timestamp: u64,
@@ -59,203 +69,171 @@ pub struct NightlightSettings {
     pub sunset_time: NaiveTime,
     /// The sunrise time
     pub sunrise_time: NaiveTime,
+    /// Inner payload fields not recognised by this schema, preserved for roundtripping
+    pub unknown_fields: BondStruct,
+    /// How the inner payload was encoded beyond the fields above, preserved for roundtripping
+    pub encoding: SettingsEncoding,
+    /// CloudStore wrapper metadata and extra wrapper fields, preserved for roundtripping
+    pub envelope: CloudStoreEnvelope,
 }
 
-/// Reads a TimeBlock struct: { field 0: int8 = hour, field 1: int8 = minute }.
-/// Returns (hour, minute) with defaults of 0 for absent fields.
-fn read_time_block(reader: &mut CompactBinaryReader) -> Result<(u8, u8), BondError> {
-    let mut hour: u8 = 0;
-    let mut minute: u8 = 0;
-    loop {
-        match reader.read_field_header()? {
-            FieldHeader::Stop => break,
-            FieldHeader::StopBase => continue,
-            FieldHeader::Field {
-                id: 0,
-                bond_type: BondType::Int8,
-            } => {
-                hour = reader.read_int8()? as u8;
-            }
-            FieldHeader::Field {
-                id: 1,
-                bond_type: BondType::Int8,
-            } => {
-                minute = reader.read_int8()? as u8;
-            }
-            FieldHeader::Field { bond_type, .. } => {
-                reader.skip_value(bond_type)?;
-            }
+/// Encoding details of the inner settings payload that [NightlightSettings]'s typed fields
+/// don't capture. The default is what Windows writes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SettingsEncoding {
+    /// Field 0 is written as `false` when the schedule is off, instead of being omitted
+    pub explicit_schedule_disabled: bool,
+    /// Field 10 (set hours) is written when the schedule is off
+    pub set_hours_while_off: bool,
+    /// The value of field 10 when it's written. Only its presence matters; Windows writes
+    /// `false`, and [NightlightSettings::set_mode] resets it when the field is newly written.
+    pub set_hours_value: bool,
+    /// The start time struct (field 20)
+    pub start_time: TimeEncoding,
+    /// The end time struct (field 30)
+    pub end_time: TimeEncoding,
+    /// The sunset time struct (field 50)
+    pub sunset_time: TimeEncoding,
+    /// The sunrise time struct (field 60)
+    pub sunrise_time: TimeEncoding,
+}
+
+/// Encoding details of a schedule time struct.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimeEncoding {
+    /// A zero hour is written instead of being omitted
+    pub explicit_zero_hour: bool,
+    /// A zero minute is written instead of being omitted
+    pub explicit_zero_minute: bool,
+    /// Fields of the time struct not recognised by this schema
+    pub unknown_fields: BondStruct,
+}
+
+/// Schedule time of day: `{ field 0: int8 = hour, field 1: int8 = minute }`, where Windows
+/// omits zero fields.
+#[derive(BondStruct, Default)]
+struct TimeBlock {
+    #[bond(id = 0, ty = "int8")]
+    hour: Option<i8>,
+    #[bond(id = 1, ty = "int8")]
+    minute: Option<i8>,
+    #[bond(unknown)]
+    unknown_fields: BondStruct,
+}
+
+impl TimeBlock {
+    fn from_time(time: NaiveTime, encoding: &TimeEncoding) -> Self {
+        let (hour, minute) = (time.hour() as i8, time.minute() as i8);
+        TimeBlock {
+            hour: (hour != 0 || encoding.explicit_zero_hour).then_some(hour),
+            minute: (minute != 0 || encoding.explicit_zero_minute).then_some(minute),
+            unknown_fields: encoding.unknown_fields.clone(),
         }
     }
-    Ok((hour, minute))
-}
 
-/// Writes a TimeBlock struct. Omits fields with value 0 (Bond default omission).
-fn write_time_block(writer: &mut CompactBinaryWriter, field_id: u16, hour: u8, minute: u8) {
-    writer.write_field_header(field_id, BondType::Struct);
-    if hour > 0 {
-        writer.write_field_header(0, BondType::Int8);
-        writer.write_int8(hour as i8);
+    fn to_time(&self) -> Result<NaiveTime, BondError> {
+        let (hour, minute) = (self.hour.unwrap_or(0), self.minute.unwrap_or(0));
+        NaiveTime::from_hms_opt(hour as u8 as u32, minute as u8 as u32, 0)
+            .ok_or(BondError::UnexpectedFieldType(0))
     }
-    if minute > 0 {
-        writer.write_field_header(1, BondType::Int8);
-        writer.write_int8(minute as i8);
+
+    fn into_encoding(self) -> TimeEncoding {
+        TimeEncoding {
+            explicit_zero_hour: self.hour == Some(0),
+            explicit_zero_minute: self.minute == Some(0),
+            unknown_fields: self.unknown_fields,
+        }
     }
-    writer.write_stop();
+}
+
+/// The inner settings payload, see [NightlightSettings] for the fields.
+#[derive(BondStruct)]
+struct SettingsPayload {
+    #[bond(id = 0, ty = "bool")]
+    schedule_enabled: Option<bool>,
+    #[bond(id = 10, ty = "bool")]
+    set_hours_mode: Option<bool>,
+    #[bond(id = 20, ty = "struct")]
+    start_time: TimeBlock,
+    #[bond(id = 30, ty = "struct")]
+    end_time: TimeBlock,
+    #[bond(id = 40, ty = "int16")]
+    color_temperature: i16,
+    #[bond(id = 50, ty = "struct")]
+    sunset_time: TimeBlock,
+    #[bond(id = 60, ty = "struct")]
+    sunrise_time: TimeBlock,
+    #[bond(unknown)]
+    unknown_fields: BondStruct,
 }
 
 impl NightlightSettings {
     /// Deserializes a [NightlightSettings] struct from a byte slice.
     pub fn deserialize_from_bytes(data: &[u8]) -> Result<NightlightSettings, BondError> {
-        let (timestamp, inner_payload) = cloudstore::cloudstore_unwrap(data)?;
+        let (envelope, timestamp, inner_payload) = cloudstore::cloudstore_unwrap_envelope(data)?;
 
         let mut reader = CompactBinaryReader::new(inner_payload);
         reader.read_marshaled_header()?;
+        let payload = SettingsPayload::read_fields(&mut reader)?;
 
-        let mut schedule_enabled = false;
-        let mut set_hours_mode = false;
-        let mut start_time = (0u8, 0u8);
-        let mut end_time = (0u8, 0u8);
-        let mut color_temperature: i16 = 0;
-        let mut sunset_time = (0u8, 0u8);
-        let mut sunrise_time = (0u8, 0u8);
-
-        loop {
-            match reader.read_field_header()? {
-                FieldHeader::Stop => break,
-                FieldHeader::StopBase => continue,
-                FieldHeader::Field {
-                    id: 0,
-                    bond_type: BondType::Bool,
-                } => {
-                    schedule_enabled = reader.read_bool()?;
-                }
-                FieldHeader::Field {
-                    id: 10,
-                    bond_type: BondType::Bool,
-                } => {
-                    let _ = reader.read_bool()?;
-                    set_hours_mode = true; // presence is the signal
-                }
-                FieldHeader::Field {
-                    id: 20,
-                    bond_type: BondType::Struct,
-                } => {
-                    start_time = read_time_block(&mut reader)?;
-                }
-                FieldHeader::Field {
-                    id: 30,
-                    bond_type: BondType::Struct,
-                } => {
-                    end_time = read_time_block(&mut reader)?;
-                }
-                FieldHeader::Field {
-                    id: 40,
-                    bond_type: BondType::Int16,
-                } => {
-                    color_temperature = reader.read_int16()?;
-                }
-                FieldHeader::Field {
-                    id: 50,
-                    bond_type: BondType::Struct,
-                } => {
-                    sunset_time = read_time_block(&mut reader)?;
-                }
-                FieldHeader::Field {
-                    id: 60,
-                    bond_type: BondType::Struct,
-                } => {
-                    sunrise_time = read_time_block(&mut reader)?;
-                }
-                FieldHeader::Field { bond_type, .. } => {
-                    reader.skip_value(bond_type)?;
-                }
-            }
-        }
-
-        let schedule_mode = if schedule_enabled {
-            if set_hours_mode {
-                ScheduleMode::SetHours
-            } else {
-                ScheduleMode::SunsetToSunrise
-            }
-        } else {
-            ScheduleMode::Off
-        };
-
-        let to_time = |h: u8, m: u8| -> Result<NaiveTime, BondError> {
-            NaiveTime::from_hms_opt(u32::from(h), u32::from(m), 0)
-                .ok_or(BondError::UnexpectedFieldType(0))
+        let schedule_mode = match (payload.schedule_enabled, payload.set_hours_mode) {
+            (None | Some(false), _) => ScheduleMode::Off,
+            (Some(true), None) => ScheduleMode::SunsetToSunrise,
+            (Some(true), Some(_)) => ScheduleMode::SetHours,
         };
 
         Ok(NightlightSettings {
             timestamp,
             schedule_mode,
-            color_temperature: color_temperature as u16,
-            start_time: to_time(start_time.0, start_time.1)?,
-            end_time: to_time(end_time.0, end_time.1)?,
-            sunset_time: to_time(sunset_time.0, sunset_time.1)?,
-            sunrise_time: to_time(sunrise_time.0, sunrise_time.1)?,
+            color_temperature: payload.color_temperature as u16,
+            start_time: payload.start_time.to_time()?,
+            end_time: payload.end_time.to_time()?,
+            sunset_time: payload.sunset_time.to_time()?,
+            sunrise_time: payload.sunrise_time.to_time()?,
+            unknown_fields: payload.unknown_fields,
+            encoding: SettingsEncoding {
+                explicit_schedule_disabled: payload.schedule_enabled == Some(false),
+                set_hours_while_off: schedule_mode == ScheduleMode::Off
+                    && payload.set_hours_mode.is_some(),
+                set_hours_value: payload.set_hours_mode.unwrap_or(false),
+                start_time: payload.start_time.into_encoding(),
+                end_time: payload.end_time.into_encoding(),
+                sunset_time: payload.sunset_time.into_encoding(),
+                sunrise_time: payload.sunrise_time.into_encoding(),
+            },
+            envelope,
         })
     }
 
     /// Serializes a [NightlightSettings] struct into a byte slice.
     pub fn serialize_to_bytes(&self) -> Vec<u8> {
-        // Build inner payload
+        let encoding = &self.encoding;
+        let schedule_enabled = self.schedule_mode != ScheduleMode::Off;
+        let payload = SettingsPayload {
+            schedule_enabled: (schedule_enabled || encoding.explicit_schedule_disabled)
+                .then_some(schedule_enabled),
+            set_hours_mode: self.writes_set_hours().then_some(encoding.set_hours_value),
+            start_time: TimeBlock::from_time(self.start_time, &encoding.start_time),
+            end_time: TimeBlock::from_time(self.end_time, &encoding.end_time),
+            color_temperature: self.color_temperature as i16,
+            sunset_time: TimeBlock::from_time(self.sunset_time, &encoding.sunset_time),
+            sunrise_time: TimeBlock::from_time(self.sunrise_time, &encoding.sunrise_time),
+            unknown_fields: self.unknown_fields.clone(),
+        };
+
         let mut inner = CompactBinaryWriter::new();
         inner.write_marshaled_header();
+        payload.write_fields(&mut inner);
 
-        // Field 0: schedule_enabled
-        if self.schedule_mode != ScheduleMode::Off {
-            inner.write_field_header(0, BondType::Bool);
-            inner.write_bool(true);
-        }
+        cloudstore::cloudstore_wrap_envelope(&self.envelope, self.timestamp, &inner.into_bytes())
+    }
 
-        // Field 10: set_hours_mode (presence = set hours)
-        if self.schedule_mode == ScheduleMode::SetHours {
-            inner.write_field_header(10, BondType::Bool);
-            inner.write_bool(false);
+    /// Whether field 10 (set hours) is written for the current mode.
+    fn writes_set_hours(&self) -> bool {
+        match self.schedule_mode {
+            ScheduleMode::Off => self.encoding.set_hours_while_off,
+            mode => mode == ScheduleMode::SetHours,
         }
-
-        // Field 20: schedule start time
-        write_time_block(
-            &mut inner,
-            20,
-            self.start_time.hour() as u8,
-            self.start_time.minute() as u8,
-        );
-
-        // Field 30: schedule end time
-        write_time_block(
-            &mut inner,
-            30,
-            self.end_time.hour() as u8,
-            self.end_time.minute() as u8,
-        );
-
-        // Field 40: color temperature
-        inner.write_field_header(40, BondType::Int16);
-        inner.write_int16(self.color_temperature as i16);
-
-        // Field 50: sunset time
-        write_time_block(
-            &mut inner,
-            50,
-            self.sunset_time.hour() as u8,
-            self.sunset_time.minute() as u8,
-        );
-
-        // Field 60: sunrise time
-        write_time_block(
-            &mut inner,
-            60,
-            self.sunrise_time.hour() as u8,
-            self.sunrise_time.minute() as u8,
-        );
-
-        inner.write_stop();
-
-        cloudstore::cloudstore_wrap(self.timestamp, &inner.into_bytes())
     }
 
     fn update_timestamp(&mut self) {
@@ -268,7 +246,11 @@ impl NightlightSettings {
             return false;
         }
 
+        let wrote_set_hours = self.writes_set_hours();
         self.schedule_mode = mode;
+        if !wrote_set_hours && self.writes_set_hours() {
+            self.encoding.set_hours_value = false;
+        }
         self.update_timestamp();
         true
     }
@@ -279,7 +261,7 @@ impl NightlightSettings {
             return Ok(false);
         }
 
-        if !(1200..=6500).contains(&color_temperature) {
+        if !(MIN_COLOR_TEMPERATURE..=MAX_COLOR_TEMPERATURE).contains(&color_temperature) {
             return Err(SettingsError::InvalidColorTemperature(color_temperature));
         }
         self.color_temperature = color_temperature;
@@ -343,6 +325,26 @@ mod tests {
         0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
     ];
 
+    /// [BYTES] with three extra inner fields: 5 (uint32 = 7), 45 (string = "hi") and
+    /// 70 (struct { 0: bool = true }).
+    const BYTES_WITH_UNKNOWN: [u8; 72] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x32, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xA5, 0x07, 0xC2, 0x0A, 0x00,
+        0xCA, 0x14, 0x0E, 0x01, 0x2E, 0x0F, 0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xC9,
+        0x2D, 0x02, 0x68, 0x69, 0xCA, 0x32, 0x0E, 0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07,
+        0x2E, 0x0C, 0x00, 0xCA, 0x46, 0x02, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    /// [BYTES] with an extra field 2 (uint32 = 9) in the start time struct, and the end time
+    /// struct's zero hour written explicitly.
+    const BYTES_WITH_TIME_FIELDS: [u8; 64] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x2A, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14,
+        0x0E, 0x01, 0x2E, 0x0F, 0x45, 0x09, 0x00, 0xCA, 0x1E, 0x0E, 0x00, 0x00, 0xCF, 0x28, 0xCC,
+        0x2B, 0xCA, 0x32, 0x0E, 0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+
     #[test]
     fn test_serialize_to_bytes() {
         let settings = NightlightSettings {
@@ -353,6 +355,9 @@ mod tests {
             end_time: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
             sunset_time: NaiveTime::from_hms_opt(19, 23, 0).unwrap(),
             sunrise_time: NaiveTime::from_hms_opt(7, 12, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
         };
         let bytes = settings.serialize_to_bytes();
         assert_eq!(BYTES, bytes.as_slice());
@@ -368,6 +373,9 @@ mod tests {
             end_time: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
             sunset_time: NaiveTime::from_hms_opt(19, 23, 0).unwrap(),
             sunrise_time: NaiveTime::from_hms_opt(7, 12, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
         };
         let settings = NightlightSettings::deserialize_from_bytes(&BYTES).unwrap();
         assert_eq!(expected_settings, settings);
@@ -383,6 +391,9 @@ mod tests {
             end_time: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
             sunset_time: NaiveTime::from_hms_opt(18, 26, 0).unwrap(),
             sunrise_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
         };
         let bytes = settings.serialize_to_bytes();
         let settings_from_bytes = NightlightSettings::deserialize_from_bytes(&bytes).unwrap();
@@ -399,6 +410,9 @@ mod tests {
             end_time: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
             sunset_time: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
             sunrise_time: NaiveTime::from_hms_opt(6, 30, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
         };
         let bytes = settings.serialize_to_bytes();
         let roundtripped = NightlightSettings::deserialize_from_bytes(&bytes).unwrap();
@@ -415,9 +429,175 @@ mod tests {
             end_time: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
             sunset_time: NaiveTime::from_hms_opt(20, 45, 0).unwrap(),
             sunrise_time: NaiveTime::from_hms_opt(5, 15, 0).unwrap(),
+            unknown_fields: BondStruct::new(),
+            encoding: SettingsEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
         };
         let bytes = settings.serialize_to_bytes();
         let roundtripped = NightlightSettings::deserialize_from_bytes(&bytes).unwrap();
         assert_eq!(settings, roundtripped);
     }
+
+    #[test]
+    fn test_deserialize_preserves_unknown_fields() {
+        let settings = NightlightSettings::deserialize_from_bytes(&BYTES_WITH_UNKNOWN).unwrap();
+        assert_eq!(settings.color_temperature, 2790);
+        assert_eq!(settings.schedule_mode, ScheduleMode::SetHours);
+        assert_eq!(
+            settings.unknown_fields.fields,
+            vec![
+                (5, BondValue::UInt32(7)),
+                (45, BondValue::String("hi".into())),
+                (
+                    70,
+                    BondValue::Struct(BondStruct {
+                        fields: vec![(0, BondValue::Bool(true))],
+                    }),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_roundtrip_bytes() {
+        let settings = NightlightSettings::deserialize_from_bytes(&BYTES_WITH_UNKNOWN).unwrap();
+        assert_eq!(settings.serialize_to_bytes(), BYTES_WITH_UNKNOWN);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_unknown_fields_json_fixture() {
+        let fixture = include_str!("../tests/fixtures/settings-with-unknown.json");
+        let dumped: crate::dump::MarshaledStruct = serde_json::from_str(fixture).unwrap();
+        assert_eq!(dumped.to_bytes(), BYTES_WITH_UNKNOWN);
+        assert_eq!(
+            serde_json::to_string_pretty(&dumped).unwrap() + "\n",
+            fixture
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_survive_modification() {
+        let mut settings = NightlightSettings::deserialize_from_bytes(&BYTES_WITH_UNKNOWN).unwrap();
+        settings.set_color_temperature(3000).unwrap();
+        let roundtripped =
+            NightlightSettings::deserialize_from_bytes(&settings.serialize_to_bytes()).unwrap();
+        assert_eq!(roundtripped.color_temperature, 3000);
+        assert_eq!(roundtripped.unknown_fields, settings.unknown_fields);
+    }
+
+    #[test]
+    fn test_known_id_with_unexpected_type_is_preserved() {
+        // Field 40 carried as int32 instead of int16 must not be dropped or reinterpreted
+        let (timestamp, inner) = cloudstore::cloudstore_unwrap(&BYTES).unwrap();
+        let mut reader = CompactBinaryReader::new(inner);
+        reader.read_marshaled_header().unwrap();
+        let mut payload = reader.read_struct().unwrap();
+        for (id, value) in &mut payload.fields {
+            if *id == 40 {
+                *value = BondValue::Int32(4000);
+            }
+        }
+        let mut inner = CompactBinaryWriter::new();
+        inner.write_marshaled_header();
+        inner.write_struct(&payload);
+        let inner = inner.into_bytes();
+        let bytes = cloudstore::cloudstore_wrap(timestamp, &inner);
+
+        let settings = NightlightSettings::deserialize_from_bytes(&bytes).unwrap();
+        assert_eq!(settings.color_temperature, 0);
+        assert_eq!(
+            settings.unknown_fields.get(40),
+            Some(&BondValue::Int32(4000))
+        );
+
+        // Written back once, as the original int32
+        let reserialized = settings.serialize_to_bytes();
+        assert_eq!(
+            cloudstore::cloudstore_unwrap(&reserialized).unwrap().1,
+            inner
+        );
+        assert_eq!(reserialized, bytes);
+    }
+
+    #[test]
+    fn test_time_struct_fields_roundtrip_bytes() {
+        let settings = NightlightSettings::deserialize_from_bytes(&BYTES_WITH_TIME_FIELDS).unwrap();
+        assert_eq!(
+            settings.start_time,
+            NaiveTime::from_hms_opt(1, 15, 0).unwrap()
+        );
+        assert_eq!(
+            settings.encoding.start_time.unknown_fields.fields,
+            vec![(2, BondValue::UInt32(9))]
+        );
+        assert!(settings.encoding.end_time.explicit_zero_hour);
+        assert!(settings.unknown_fields.fields.is_empty());
+        assert_eq!(settings.serialize_to_bytes(), BYTES_WITH_TIME_FIELDS);
+
+        // They survive a change to the time, too
+        let mut changed = settings.clone();
+        changed.set_start_time(NaiveTime::from_hms_opt(2, 30, 0).unwrap());
+        let changed = NightlightSettings::deserialize_from_bytes(&changed.serialize_to_bytes());
+        assert_eq!(changed.unwrap().encoding, settings.encoding);
+    }
+
+    #[test]
+    fn test_explicit_schedule_disabled_roundtrip_bytes() {
+        // BYTES with field 0 written as false instead of true
+        let mut bytes = BYTES;
+        bytes[24] = 0x00;
+        let settings = NightlightSettings::deserialize_from_bytes(&bytes).unwrap();
+        assert_eq!(settings.schedule_mode, ScheduleMode::Off);
+        assert!(settings.encoding.explicit_schedule_disabled);
+        assert!(settings.encoding.set_hours_while_off);
+        assert_eq!(settings.serialize_to_bytes(), bytes);
+
+        // Turning the schedule off omits both by default
+        let mut settings = NightlightSettings::deserialize_from_bytes(&BYTES).unwrap();
+        settings.set_mode(ScheduleMode::Off);
+        let bytes = settings.serialize_to_bytes();
+        let (_, inner) = cloudstore::cloudstore_unwrap(&bytes).unwrap();
+        let mut reader = CompactBinaryReader::new(inner);
+        reader.read_marshaled_header().unwrap();
+        let payload = reader.read_struct().unwrap();
+        assert!(!payload.has(0) && !payload.has(10));
+    }
+
+    #[test]
+    fn test_set_hours_value_roundtrip_bytes() {
+        // BYTES with field 10 written as true instead of false
+        let mut bytes = BYTES;
+        bytes[27] = 0x01;
+        let settings = NightlightSettings::deserialize_from_bytes(&bytes).unwrap();
+        assert_eq!(settings.schedule_mode, ScheduleMode::SetHours);
+        assert!(settings.encoding.set_hours_value);
+        assert_eq!(settings.serialize_to_bytes(), bytes);
+
+        // Writing the field anew writes false again
+        let mut settings = settings;
+        settings.set_mode(ScheduleMode::SunsetToSunrise);
+        settings.set_mode(ScheduleMode::SetHours);
+        assert_eq!(settings.serialize_to_bytes()[23..28], BYTES[23..28]);
+    }
+
+    #[test]
+    fn test_unknown_fields_written_in_id_order() {
+        let mut settings = NightlightSettings::deserialize_from_bytes(&BYTES).unwrap();
+        settings.unknown_fields.push(70, BondValue::Bool(true));
+        settings.unknown_fields.push(5, BondValue::UInt32(7));
+
+        let bytes = settings.serialize_to_bytes();
+        let (_, inner) = cloudstore::cloudstore_unwrap(&bytes).unwrap();
+        let mut reader = CompactBinaryReader::new(inner);
+        reader.read_marshaled_header().unwrap();
+        let ids: Vec<u16> = reader
+            .read_struct()
+            .unwrap()
+            .fields
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(ids, [0, 5, 10, 20, 30, 40, 50, 60, 70]);
+    }
 }
@@ -1,7 +1,7 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 use crate::bond::*;
-use crate::cloudstore;
+use crate::cloudstore::{self, CloudStoreEnvelope};
 
 /// Night Light state stored in the registry as a Bond CompactBinary v1 payload.
 ///
@@ -10,8 +10,12 @@ use crate::cloudstore;
 /// - Field 10: int32  — initialized marker (always 1)
 /// - Field 20: uint64 — last transition FILETIME
 ///
+/// Any other inner fields are kept in [NightlightState::unknown_fields], the value of field 0
+/// in [NightlightState::encoding], and the CloudStore wrapper in [NightlightState::envelope];
+/// all are written back unchanged on serialization.
+///
 /// See `docs/nightlight-registry-format.md` for full details.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NightlightState {
     /// The last-modified Unix timestamp in seconds
     pub timestamp: u64,
@@ -23,79 +27,78 @@ pub struct NightlightState {
     /// Windows FILETIME of the last state transition (toggle or scheduled change).
     /// 100-nanosecond intervals since 1601-01-01 UTC.
     pub last_transition_filetime: u64,
+    /// Inner payload fields not recognised by this schema, preserved for roundtripping.
+    pub unknown_fields: BondStruct,
+    /// Encoding details the fields above don't capture, preserved for roundtripping.
+    pub encoding: StateEncoding,
+    /// CloudStore wrapper metadata and extra wrapper fields, preserved for roundtripping.
+    pub envelope: CloudStoreEnvelope,
+}
+
+/// Encoding details of the inner state payload that [NightlightState]'s typed fields don't
+/// capture. The default is what Windows writes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateEncoding {
+    /// The value of field 0 while enabled. Only its presence matters; Windows writes 0, and
+    /// [NightlightState::enable] resets it to 0.
+    pub enabled_value: i32,
+}
+
+/// The inner state payload, see [NightlightState] for the fields.
+#[derive(BondStruct)]
+struct StatePayload {
+    #[bond(id = 0, ty = "int32")]
+    is_enabled: Option<i32>,
+    #[bond(id = 10, ty = "int32")]
+    initialized: i32,
+    #[bond(id = 20, ty = "uint64")]
+    last_transition_filetime: u64,
+    #[bond(unknown)]
+    unknown_fields: BondStruct,
 }
 
 impl NightlightState {
     /// Deserializes a [NightlightState] struct from a byte slice.
     pub fn deserialize_from_bytes(data: &[u8]) -> Result<NightlightState, BondError> {
-        let (timestamp, inner_payload) = cloudstore::cloudstore_unwrap(data)?;
+        let (envelope, timestamp, inner_payload) = cloudstore::cloudstore_unwrap_envelope(data)?;
 
         let mut reader = CompactBinaryReader::new(inner_payload);
         reader.read_marshaled_header()?;
-
-        let mut is_enabled = false;
-        let mut initialized: i32 = 0;
-        let mut last_transition_filetime: u64 = 0;
-
-        loop {
-            match reader.read_field_header()? {
-                FieldHeader::Stop => break,
-                FieldHeader::StopBase => continue,
-                FieldHeader::Field {
-                    id: 0,
-                    bond_type: BondType::Int32,
-                } => {
-                    let _ = reader.read_int32()?;
-                    is_enabled = true; // presence is the signal
-                }
-                FieldHeader::Field {
-                    id: 10,
-                    bond_type: BondType::Int32,
-                } => {
-                    initialized = reader.read_int32()?;
-                }
-                FieldHeader::Field {
-                    id: 20,
-                    bond_type: BondType::UInt64,
-                } => {
-                    last_transition_filetime = reader.read_uint64()?;
-                }
-                FieldHeader::Field { bond_type, .. } => {
-                    reader.skip_value(bond_type)?;
-                }
-            }
-        }
+        let payload = StatePayload::read_fields(&mut reader)?;
 
         Ok(NightlightState {
             timestamp,
-            is_enabled,
-            initialized,
-            last_transition_filetime,
+            is_enabled: payload.is_enabled.is_some(),
+            initialized: payload.initialized,
+            last_transition_filetime: payload.last_transition_filetime,
+            unknown_fields: payload.unknown_fields,
+            encoding: StateEncoding {
+                enabled_value: payload.is_enabled.unwrap_or(0),
+            },
+            envelope,
         })
     }
 
     /// Serializes a [NightlightState] struct into a byte slice.
     pub fn serialize_to_bytes(&self) -> Vec<u8> {
+        let payload = StatePayload {
+            is_enabled: self.is_enabled.then_some(self.encoding.enabled_value),
+            initialized: self.initialized,
+            last_transition_filetime: self.last_transition_filetime,
+            unknown_fields: self.unknown_fields.clone(),
+        };
+
         let mut inner = CompactBinaryWriter::new();
         inner.write_marshaled_header();
+        payload.write_fields(&mut inner);
 
-        // Field 0: enabled flag (only present when enabled)
-        if self.is_enabled {
-            inner.write_field_header(0, BondType::Int32);
-            inner.write_int32(0);
-        }
-
-        // Field 10: initialized marker
-        inner.write_field_header(10, BondType::Int32);
-        inner.write_int32(self.initialized);
-
-        // Field 20: last transition FILETIME
-        inner.write_field_header(20, BondType::UInt64);
-        inner.write_uint64(self.last_transition_filetime);
-
-        inner.write_stop();
+        cloudstore::cloudstore_wrap_envelope(&self.envelope, self.timestamp, &inner.into_bytes())
+    }
 
-        cloudstore::cloudstore_wrap(self.timestamp, &inner.into_bytes())
+    /// Returns [NightlightState::last_transition_filetime] as a UTC date and time, or `None` if
+    /// it is before the Unix epoch or out of range (e.g. 0 on a never-toggled state).
+    pub fn last_transition(&self) -> Option<DateTime<Utc>> {
+        filetime_to_datetime(self.last_transition_filetime)
     }
 
     /// Updates both the outer CloudStore timestamp and the inner field 20
@@ -114,6 +117,7 @@ impl NightlightState {
             return false;
         }
         self.is_enabled = true;
+        self.encoding.enabled_value = 0;
         self.update_transition_timestamps();
         true
     }
@@ -142,6 +146,15 @@ fn unix_to_filetime(secs: u64, subsec_nanos: u32) -> u64 {
         + u64::from(subsec_nanos / 100)
 }
 
+/// Converts a Windows FILETIME into a UTC date and time, if it is on or after the Unix epoch.
+fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    let unix_ticks =
+        filetime.checked_sub(FILETIME_UNIX_EPOCH_OFFSET_SECS * FILETIME_TICKS_PER_SEC)?;
+    let secs = unix_ticks / FILETIME_TICKS_PER_SEC;
+    let nanos = (unix_ticks % FILETIME_TICKS_PER_SEC) * 100;
+    DateTime::from_timestamp(i64::try_from(secs).ok()?, nanos as u32)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,6 +170,19 @@ mod tests {
         0xA9, 0xF6, 0xE2, 0xD3, 0xEF, 0xEA, 0xE6, 0xED, 0x01, 0x00, 0x00, 0x00, 0x00,
     ];
 
+    /// [BYTES_ENABLED] with extra data everywhere Windows could put it:
+    /// - metadata struct gains field 1 (uint32 = 3)
+    /// - data wrapper gains field 0 (bool = false) before the payload list
+    /// - inner payload gains fields 5 (uint16 = 42) and 30 (int64 = -1)
+    /// - payload container gains field 2 (uint8 = 9)
+    /// - outer struct gains field 3 (bool = true)
+    const BYTES_WITH_UNKNOWN: [u8; 56] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x25, 0x03, 0x00, 0x2A, 0x06, 0x89, 0x95, 0xFC,
+        0xBE, 0x06, 0x2A, 0x02, 0x00, 0x2B, 0x0E, 0x1A, 0x43, 0x42, 0x01, 0x00, 0x10, 0x00, 0xA4,
+        0x2A, 0xD0, 0x0A, 0x02, 0xC6, 0x14, 0xA9, 0xF6, 0xE2, 0xD3, 0xEF, 0xEA, 0xE6, 0xED, 0x01,
+        0xD1, 0x1E, 0x01, 0x00, 0x00, 0x43, 0x09, 0x00, 0x62, 0x01, 0x00,
+    ];
+
     // Decode the FILETIME from the test data varint bytes [0xA9, 0xF6, 0xE2, 0xD3, 0xEF, 0xEA, 0xE6, 0xED, 0x01]
     const EXPECTED_FILETIME: u64 = 133_871_411_809_270_569;
 
@@ -166,6 +192,9 @@ mod tests {
             is_enabled: false,
             initialized: 1,
             last_transition_filetime: EXPECTED_FILETIME,
+            unknown_fields: BondStruct::new(),
+            encoding: StateEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
         }
     }
 
@@ -175,6 +204,9 @@ mod tests {
             is_enabled: true,
             initialized: 1,
             last_transition_filetime: EXPECTED_FILETIME,
+            unknown_fields: BondStruct::new(),
+            encoding: StateEncoding::default(),
+            envelope: CloudStoreEnvelope::default(),
         }
     }
 
@@ -206,6 +238,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_filetime_to_datetime() {
+        let dt = filetime_to_datetime(unix_to_filetime(1_742_667_580, 927_056_900)).unwrap();
+        assert_eq!(dt.timestamp(), 1_742_667_580);
+        assert_eq!(dt.timestamp_subsec_nanos(), 927_056_900);
+        assert_eq!(filetime_to_datetime(0), None);
+    }
+
     #[test]
     fn test_enable_updates_transition_filetime() {
         let mut state = expected_disabled();
@@ -238,4 +278,73 @@ mod tests {
         let state_deserialized = NightlightState::deserialize_from_bytes(&bytes).unwrap();
         assert_eq!(state_deserialized, state_enabled);
     }
+
+    #[test]
+    fn test_deserialize_preserves_unknown_fields() {
+        let state = NightlightState::deserialize_from_bytes(&BYTES_WITH_UNKNOWN).unwrap();
+        assert!(state.is_enabled);
+        assert_eq!(state.initialized, 1);
+        assert_eq!(state.last_transition_filetime, EXPECTED_FILETIME);
+        assert_eq!(
+            state.unknown_fields.fields,
+            vec![(5, BondValue::UInt16(42)), (30, BondValue::Int64(-1))]
+        );
+        assert_eq!(
+            state.envelope.metadata,
+            Some(BondStruct {
+                fields: vec![(0, BondValue::Bool(true)), (1, BondValue::UInt32(3))],
+            })
+        );
+        assert_eq!(
+            state.envelope.data_fields.fields,
+            vec![(0, BondValue::Bool(false))]
+        );
+        assert_eq!(
+            state.envelope.container_fields.fields,
+            vec![(2, BondValue::UInt8(9))]
+        );
+        assert_eq!(
+            state.envelope.outer_fields.fields,
+            vec![(3, BondValue::Bool(true))]
+        );
+    }
+
+    #[test]
+    fn test_unmodified_roundtrip_is_byte_identical() {
+        for fixture in [
+            &BYTES_DISABLED[..],
+            &BYTES_ENABLED[..],
+            &BYTES_WITH_UNKNOWN[..],
+        ] {
+            let state = NightlightState::deserialize_from_bytes(fixture).unwrap();
+            assert_eq!(state.serialize_to_bytes(), fixture);
+        }
+    }
+
+    #[test]
+    fn test_non_zero_enabled_value_roundtrip_bytes() {
+        // Field 0 holds int32 1 instead of 0
+        let mut bytes = BYTES_ENABLED;
+        bytes[24] = 0x02;
+        let mut state = NightlightState::deserialize_from_bytes(&bytes).unwrap();
+        assert!(state.is_enabled);
+        assert_eq!(state.encoding.enabled_value, 1);
+        assert_eq!(state.serialize_to_bytes(), bytes);
+
+        // Enabling again after disabling writes what Windows writes
+        state.disable();
+        state.enable();
+        assert_eq!(state.serialize_to_bytes()[23..25], BYTES_ENABLED[23..25]);
+    }
+
+    #[test]
+    fn test_unknown_fields_survive_disable() {
+        let mut state = NightlightState::deserialize_from_bytes(&BYTES_WITH_UNKNOWN).unwrap();
+        assert!(state.disable());
+        let roundtripped =
+            NightlightState::deserialize_from_bytes(&state.serialize_to_bytes()).unwrap();
+        assert!(!roundtripped.is_enabled);
+        assert_eq!(roundtripped.unknown_fields, state.unknown_fields);
+        assert_eq!(roundtripped.envelope, state.envelope);
+    }
 }
@@ -0,0 +1,677 @@
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use thiserror::Error;
+
+use crate::file::write_atomic;
+use crate::{DATA_REG_KEY_NAME, NightlightBackend, NightlightBlob, NightlightError};
+
+/// Header line of `.reg` files exported by `regedit` on Windows 2000 and later.
+pub const REG_HEADER_V5: &str = "Windows Registry Editor Version 5.00";
+/// Header line of legacy (Windows 9x/NT4) `.reg` files.
+pub const REG_HEADER_V4: &str = "REGEDIT4";
+/// Root key holding the current user's hive.
+pub const HKEY_CURRENT_USER: &str = "HKEY_CURRENT_USER";
+/// Root key holding every loaded user hive, keyed by SID.
+pub const HKEY_USERS: &str = "HKEY_USERS";
+
+/// Registry value type of `hex:` data.
+const REG_BINARY: u32 = 3;
+/// Column after which `regedit` wraps `hex:` data onto a continuation line.
+const HEX_WRAP_COLUMN: usize = 76;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RegError {
+    #[error("Missing '{REG_HEADER_V5}' or '{REG_HEADER_V4}' header")]
+    MissingHeader,
+    #[error("Invalid UTF-8 text")]
+    InvalidUtf8,
+    #[error("Invalid UTF-16 text")]
+    InvalidUtf16,
+    #[error("Line {0}: malformed line")]
+    InvalidLine(usize),
+    #[error("Line {0}: value outside of a key section")]
+    ValueOutsideKey(usize),
+    #[error("Line {0}: invalid value data")]
+    InvalidData(usize),
+}
+
+/// The data of a single registry value in a `.reg` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegValue {
+    /// `"text"` — a `REG_SZ` string.
+    String(String),
+    /// `dword:0000002a` — a `REG_DWORD`.
+    Dword(u32),
+    /// `hex:...` (`kind` 3, `REG_BINARY`) or `hex(N):...` raw data of value type `N`.
+    Hex { kind: u32, data: Vec<u8> },
+    /// `-` — deletes the value on import.
+    Delete,
+}
+
+impl RegValue {
+    /// Creates a `REG_BINARY` value.
+    pub fn binary(data: impl Into<Vec<u8>>) -> Self {
+        RegValue::Hex {
+            kind: REG_BINARY,
+            data: data.into(),
+        }
+    }
+
+    /// Returns the bytes of a `REG_BINARY` value.
+    pub fn as_binary(&self) -> Option<&[u8]> {
+        match self {
+            RegValue::Hex {
+                kind: REG_BINARY,
+                data,
+            } => Some(data),
+            _ => None,
+        }
+    }
+}
+
+/// A `[key]` section of a `.reg` file and the values listed under it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegKey {
+    /// Full key path including the root, e.g. `HKEY_CURRENT_USER\Software\...`.
+    pub path: String,
+    /// `true` for `[-...]` sections, which delete the key on import.
+    pub delete: bool,
+    /// Values in file order as (name, data). The default value (`@`) has an empty name.
+    pub values: Vec<(String, RegValue)>,
+}
+
+/// A parsed `.reg` export.
+///
+/// Comments and blank lines are dropped; every key section and value is kept in file order so
+/// that unrelated keys survive a parse/modify/write cycle.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RegFile {
+    pub keys: Vec<RegKey>,
+}
+
+impl RegFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a `.reg` file from raw bytes, detecting UTF-16LE (with BOM, as written by
+    /// `regedit`) or UTF-8 (with or without BOM) text.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RegError> {
+        if let Some(utf16) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+            if utf16.len() % 2 != 0 {
+                return Err(RegError::InvalidUtf16);
+            }
+            let units: Vec<u16> = utf16
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            let text = String::from_utf16(&units).map_err(|_| RegError::InvalidUtf16)?;
+            return Self::parse(&text);
+        }
+        let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+        let text = std::str::from_utf8(bytes).map_err(|_| RegError::InvalidUtf8)?;
+        Self::parse(text)
+    }
+
+    /// Parses the text of a `.reg` file.
+    pub fn parse(text: &str) -> Result<Self, RegError> {
+        let mut lines = logical_lines(text)
+            .into_iter()
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        match lines.next() {
+            Some((_, header)) if matches!(header.trim(), REG_HEADER_V5 | REG_HEADER_V4) => {}
+            _ => return Err(RegError::MissingHeader),
+        }
+
+        let mut file = RegFile::new();
+        for (line_no, line) in lines {
+            let line = line.trim();
+            if line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(section) = line.strip_prefix('[') {
+                let section = section
+                    .strip_suffix(']')
+                    .ok_or(RegError::InvalidLine(line_no))?;
+                let (path, delete) = match section.strip_prefix('-') {
+                    Some(path) => (path, true),
+                    None => (section, false),
+                };
+                file.keys.push(RegKey {
+                    path: path.to_string(),
+                    delete,
+                    values: Vec::new(),
+                });
+                continue;
+            }
+
+            let key = file
+                .keys
+                .last_mut()
+                .ok_or(RegError::ValueOutsideKey(line_no))?;
+            let (name, rest) = parse_value_name(line).ok_or(RegError::InvalidLine(line_no))?;
+            let data = rest
+                .trim_start()
+                .strip_prefix('=')
+                .ok_or(RegError::InvalidLine(line_no))?;
+            let value = parse_value_data(data.trim()).ok_or(RegError::InvalidData(line_no))?;
+            key.values.push((name, value));
+        }
+        Ok(file)
+    }
+
+    /// Looks up a value by key path and name (both case-insensitive, like the registry).
+    /// If the file lists the value more than once, the last occurrence wins.
+    pub fn get(&self, key_path: &str, name: &str) -> Option<&RegValue> {
+        self.keys
+            .iter()
+            .filter(|k| !k.delete && k.path.eq_ignore_ascii_case(key_path))
+            .flat_map(|k| k.values.iter())
+            .rev()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    /// Sets a value, replacing the last existing occurrence or appending it to the key's
+    /// section (creating the section at the end of the file if needed).
+    pub fn set(&mut self, key_path: &str, name: &str, value: RegValue) {
+        if let Some(existing) = self.value_mut(|path| path.eq_ignore_ascii_case(key_path), name) {
+            *existing = value;
+            return;
+        }
+        self.section_mut(key_path)
+            .values
+            .push((name.to_string(), value));
+    }
+
+    fn value_mut(&mut self, matches: impl Fn(&str) -> bool, name: &str) -> Option<&mut RegValue> {
+        self.keys
+            .iter_mut()
+            .filter(|k| !k.delete && matches(&k.path))
+            .flat_map(|k| k.values.iter_mut())
+            .rev()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    fn section_mut(&mut self, key_path: &str) -> &mut RegKey {
+        let idx = match self
+            .keys
+            .iter()
+            .rposition(|k| !k.delete && k.path.eq_ignore_ascii_case(key_path))
+        {
+            Some(idx) => idx,
+            None => {
+                self.keys.push(RegKey {
+                    path: key_path.to_string(),
+                    delete: false,
+                    values: Vec::new(),
+                });
+                self.keys.len() - 1
+            }
+        };
+        &mut self.keys[idx]
+    }
+
+    /// Returns the `Data` bytes of a Night Light blob.
+    ///
+    /// The key is matched under `HKEY_CURRENT_USER`, or under any `HKEY_USERS\<SID>` for
+    /// exports taken from another user's loaded hive.
+    pub fn nightlight_data(&self, blob: NightlightBlob) -> Option<&[u8]> {
+        let matches = nightlight_key_matcher(blob);
+        self.keys
+            .iter()
+            .filter(|k| !k.delete && matches(&k.path))
+            .flat_map(|k| k.values.iter())
+            .rev()
+            .find(|(n, _)| n.eq_ignore_ascii_case(DATA_REG_KEY_NAME))
+            .and_then(|(_, v)| v.as_binary())
+    }
+
+    /// Sets the `Data` bytes of a Night Light blob, updating the value in place if the file
+    /// already has it, or adding it under `HKEY_CURRENT_USER` otherwise.
+    pub fn set_nightlight_data(&mut self, blob: NightlightBlob, data: &[u8]) {
+        let matches = nightlight_key_matcher(blob);
+        if let Some(existing) = self.value_mut(matches, DATA_REG_KEY_NAME) {
+            *existing = RegValue::binary(data);
+            return;
+        }
+        let key_path = format!(r"{HKEY_CURRENT_USER}\{}", blob.reg_key());
+        self.section_mut(&key_path)
+            .values
+            .push((DATA_REG_KEY_NAME.to_string(), RegValue::binary(data)));
+    }
+
+    /// Encodes the file the way `regedit` writes it: UTF-16LE with a byte order mark.
+    pub fn to_utf16le_bytes(&self) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xFE];
+        for unit in self.to_string().encode_utf16() {
+            out.extend_from_slice(&unit.to_le_bytes());
+        }
+        out
+    }
+}
+
+/// Writes the file as `.reg` text with CRLF line endings, wrapping `hex:` data like `regedit`.
+impl fmt::Display for RegFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{REG_HEADER_V5}\r\n\r\n")?;
+        for key in &self.keys {
+            let delete = if key.delete { "-" } else { "" };
+            write!(f, "[{delete}{}]\r\n", key.path)?;
+            for (name, value) in &key.values {
+                let mut line = if name.is_empty() {
+                    "@=".to_string()
+                } else {
+                    format!("{}=", quote(name))
+                };
+                match value {
+                    RegValue::String(s) => line.push_str(&quote(s)),
+                    RegValue::Dword(v) => {
+                        let _ = write!(line, "dword:{v:08x}");
+                    }
+                    RegValue::Delete => line.push('-'),
+                    RegValue::Hex { kind, data } => {
+                        if *kind == REG_BINARY {
+                            line.push_str("hex:");
+                        } else {
+                            let _ = write!(line, "hex({kind:x}):");
+                        }
+                        push_wrapped_hex(&mut line, data);
+                    }
+                }
+                write!(f, "{line}\r\n")?;
+            }
+            write!(f, "\r\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns a predicate matching the key paths that may hold the given Night Light blob.
+fn nightlight_key_matcher(blob: NightlightBlob) -> impl Fn(&str) -> bool {
+    let key = blob.reg_key().to_ascii_lowercase();
+    move |path: &str| {
+        let path = path.to_ascii_lowercase();
+        let Some((root, rest)) = path.split_once('\\') else {
+            return false;
+        };
+        match root {
+            "hkey_current_user" | "hkcu" => rest == key,
+            // HKEY_USERS\<SID>\Software\...
+            "hkey_users" | "hku" => rest
+                .split_once('\\')
+                .is_some_and(|(_, under_sid)| under_sid == key),
+            _ => false,
+        }
+    }
+}
+
+/// Joins `\`-continued physical lines into logical lines, paired with their 1-based starting
+/// line number.
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut out = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (idx, raw) in text.lines().enumerate() {
+        let line = raw.trim_end();
+        let (line_no, buf) = current.get_or_insert_with(|| (idx + 1, String::new()));
+        let piece = if buf.is_empty() {
+            line
+        } else {
+            line.trim_start()
+        };
+        match piece.strip_suffix('\\') {
+            Some(continued) => buf.push_str(continued),
+            None => {
+                buf.push_str(piece);
+                out.push((*line_no, std::mem::take(buf)));
+                current = None;
+            }
+        }
+    }
+    if let Some(pending) = current {
+        out.push(pending);
+    }
+    out
+}
+
+/// Parses a `"quoted"` string with `\\` and `\"` escapes, returning it and the remaining input.
+fn parse_quoted(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut out = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &s[i + 2..])),
+            '\\' => out.push(chars.next()?.1),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+fn parse_value_name(line: &str) -> Option<(String, &str)> {
+    match line.strip_prefix('@') {
+        Some(rest) => Some((String::new(), rest)),
+        None => parse_quoted(line),
+    }
+}
+
+fn parse_value_data(data: &str) -> Option<RegValue> {
+    if data == "-" {
+        return Some(RegValue::Delete);
+    }
+    if data.starts_with('"') {
+        let (s, rest) = parse_quoted(data)?;
+        return rest.trim().is_empty().then_some(RegValue::String(s));
+    }
+    if let Some(hex) = data.strip_prefix("dword:") {
+        return u32::from_str_radix(hex.trim(), 16)
+            .ok()
+            .map(RegValue::Dword);
+    }
+    let (kind, bytes) = if let Some(bytes) = data.strip_prefix("hex:") {
+        (REG_BINARY, bytes)
+    } else {
+        let (kind, bytes) = data.strip_prefix("hex(")?.split_once("):")?;
+        (u32::from_str_radix(kind, 16).ok()?, bytes)
+    };
+    let data = bytes
+        .split(',')
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(|b| {
+            (b.len() <= 2)
+                .then(|| u8::from_str_radix(b, 16).ok())
+                .flatten()
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    Some(RegValue::Hex { kind, data })
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+/// Appends comma-separated hex bytes, breaking onto indented `\` continuation lines once the
+/// current line passes [HEX_WRAP_COLUMN].
+fn push_wrapped_hex(line: &mut String, data: &[u8]) {
+    let mut column = line.len();
+    for (i, b) in data.iter().enumerate() {
+        let _ = write!(line, "{b:02x}");
+        column += 3;
+        if i + 1 < data.len() {
+            line.push(',');
+            if column >= HEX_WRAP_COLUMN {
+                line.push_str("\\\r\n  ");
+                column = 2;
+            }
+        }
+    }
+}
+
+/// Backend over the Night Light `Data` values of a `.reg` export.
+///
+/// Reads come from the parsed file; writes update it in memory. Call [RegFileBackend::save]
+/// to write a `.reg` file that can be re-imported with a double-click. Keys and values other
+/// than the two Night Light blobs are kept as-is.
+#[derive(Debug, Default)]
+pub struct RegFileBackend {
+    file: Mutex<RegFile>,
+}
+
+impl RegFileBackend {
+    pub fn new(file: RegFile) -> Self {
+        Self {
+            file: Mutex::new(file),
+        }
+    }
+
+    /// Parses the text of a `.reg` file.
+    pub fn parse(text: &str) -> Result<Self, RegError> {
+        Ok(Self::new(RegFile::parse(text)?))
+    }
+
+    /// Reads and parses a `.reg` file from disk.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, NightlightError> {
+        let bytes = fs::read(path)?;
+        Ok(Self::new(RegFile::from_bytes(&bytes)?))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RegFile> {
+        self.file.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns a copy of the current file contents, including any writes made so far.
+    pub fn reg_file(&self) -> RegFile {
+        self.lock().clone()
+    }
+
+    /// Writes the current file contents to disk as UTF-16LE `.reg` text, via a temporary file
+    /// and an atomic rename.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), NightlightError> {
+        let bytes = self.lock().to_utf16le_bytes();
+        write_atomic(path.as_ref(), &bytes)?;
+        Ok(())
+    }
+
+    fn read(&self, blob: NightlightBlob) -> Result<Vec<u8>, NightlightError> {
+        self.lock()
+            .nightlight_data(blob)
+            .map(<[u8]>::to_vec)
+            .ok_or(NightlightError::MissingData(blob))
+    }
+
+    fn write(&self, blob: NightlightBlob, data: &[u8]) -> Result<(), NightlightError> {
+        self.lock().set_nightlight_data(blob, data);
+        Ok(())
+    }
+}
+
+impl NightlightBackend for RegFileBackend {
+    fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::Settings)
+    }
+
+    fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        self.write(NightlightBlob::Settings, data)
+    }
+
+    fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        self.read(NightlightBlob::State)
+    }
+
+    fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        self.write(NightlightBlob::State, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NightlightManager;
+    use crate::nightlight_settings::ScheduleMode;
+
+    // The settings test bytes from nightlight_settings.rs
+    const SETTINGS_BYTES: [u8; 60] = [
+        0x43, 0x42, 0x01, 0x00, 0x0A, 0x02, 0x01, 0x00, 0x2A, 0x06, 0xEC, 0xA0, 0xF4, 0xBE, 0x06,
+        0x2A, 0x2B, 0x0E, 0x26, 0x43, 0x42, 0x01, 0x00, 0x02, 0x01, 0xC2, 0x0A, 0x00, 0xCA, 0x14,
+        0x0E, 0x01, 0x2E, 0x0F, 0x00, 0xCA, 0x1E, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0xCA, 0x32, 0x0E,
+        0x13, 0x2E, 0x17, 0x00, 0xCA, 0x3C, 0x0E, 0x07, 0x2E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    /// A `regedit` export of the settings key (holding [SETTINGS_BYTES]) and the state key.
+    const EXPORT: &str = concat!(
+        "Windows Registry Editor Version 5.00\r\n",
+        "\r\n",
+        r"[HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.settings\windows.data.bluelightreduction.settings]",
+        "\r\n",
+        "\"Data\"=hex:43,42,01,00,0a,02,01,00,2a,06,ec,a0,f4,be,06,2a,2b,0e,26,43,42,01,\\\r\n",
+        "  00,02,01,c2,0a,00,ca,14,0e,01,2e,0f,00,ca,1e,00,cf,28,cc,2b,ca,32,0e,13,2e,\\\r\n",
+        "  17,00,ca,3c,0e,07,2e,0c,00,00,00,00,00\r\n",
+        "\r\n",
+        r"[HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\CloudStore\Store\DefaultAccount\Current\default$windows.data.bluelightreduction.bluelightreductionstate\windows.data.bluelightreduction.bluelightreductionstate]",
+        "\r\n",
+        "\"Data\"=hex:43,42,01,00,0a,02,01,00,2a,06,89,95,fc,be,06,2a,2b,0e,13,43,42,01,\\\r\n",
+        "  00,d0,0a,02,c6,14,a9,f6,e2,d3,ef,ea,e6,ed,01,00,00,00,00\r\n",
+        "\r\n",
+    );
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xFE];
+        for unit in text.encode_utf16() {
+            out.extend_from_slice(&unit.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn parse_regedit_export() {
+        let file = RegFile::from_bytes(&utf16le(EXPORT)).unwrap();
+        assert_eq!(file.keys.len(), 2);
+        assert_eq!(
+            file.nightlight_data(NightlightBlob::Settings),
+            Some(&SETTINGS_BYTES[..])
+        );
+        assert_eq!(
+            file.nightlight_data(NightlightBlob::State).map(<[u8]>::len),
+            Some(41)
+        );
+    }
+
+    #[test]
+    fn write_matches_regedit_layout() {
+        let file = RegFile::parse(EXPORT).unwrap();
+        assert_eq!(file.to_string(), EXPORT);
+        assert_eq!(file.to_utf16le_bytes(), utf16le(EXPORT));
+    }
+
+    #[test]
+    fn parse_value_kinds() {
+        let text = concat!(
+            "REGEDIT4\n",
+            "; comment\n",
+            "[HKEY_CURRENT_USER\\Software\\Test]\n",
+            "@=\"default\"\n",
+            "\"Path\"=\"C:\\\\Temp \\\"x\\\"\"\n",
+            "\"Count\"=dword:0000002a\n",
+            "\"Multi\"=hex(7):61,00,00,00\n",
+            "\"Empty\"=hex:\n",
+            "\"Gone\"=-\n",
+            "[-HKEY_CURRENT_USER\\Software\\Old]\n",
+        );
+        let file = RegFile::parse(text).unwrap();
+        let key = r"hkey_current_user\software\test";
+        assert_eq!(file.get(key, ""), Some(&RegValue::String("default".into())));
+        assert_eq!(
+            file.get(key, "path"),
+            Some(&RegValue::String(r#"C:\Temp "x""#.into()))
+        );
+        assert_eq!(file.get(key, "Count"), Some(&RegValue::Dword(42)));
+        assert_eq!(
+            file.get(key, "Multi"),
+            Some(&RegValue::Hex {
+                kind: 7,
+                data: vec![0x61, 0, 0, 0]
+            })
+        );
+        assert_eq!(file.get(key, "Empty"), Some(&RegValue::binary(vec![])));
+        assert_eq!(file.get(key, "Gone"), Some(&RegValue::Delete));
+        assert!(file.keys[1].delete);
+
+        // Everything survives a write/parse cycle
+        assert_eq!(RegFile::parse(&file.to_string()).unwrap(), file);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            RegFile::parse("[HKEY_CURRENT_USER\\x]\n"),
+            Err(RegError::MissingHeader)
+        );
+        assert_eq!(
+            RegError::MissingHeader.to_string(),
+            "Missing 'Windows Registry Editor Version 5.00' or 'REGEDIT4' header"
+        );
+        assert_eq!(
+            RegFile::parse("REGEDIT4\n\"a\"=dword:1\n"),
+            Err(RegError::ValueOutsideKey(2))
+        );
+        assert_eq!(
+            RegFile::parse("REGEDIT4\n[HKEY_CURRENT_USER\\x]\n\"a\"=hex:4g\n"),
+            Err(RegError::InvalidData(3))
+        );
+        assert_eq!(
+            RegFile::parse("REGEDIT4\n[HKEY_CURRENT_USER\\x\n"),
+            Err(RegError::InvalidLine(2))
+        );
+    }
+
+    #[test]
+    fn matches_keys_under_hkey_users() {
+        let text = format!(
+            "{REG_HEADER_V5}\r\n\r\n[HKEY_USERS\\S-1-5-21-1000\\{}]\r\n\"Data\"=hex:01,02\r\n",
+            NightlightBlob::State.reg_key()
+        );
+        let file = RegFile::parse(&text).unwrap();
+        assert_eq!(
+            file.nightlight_data(NightlightBlob::State),
+            Some(&[1, 2][..])
+        );
+        assert_eq!(file.nightlight_data(NightlightBlob::Settings), None);
+    }
+
+    #[test]
+    fn backend_reads_and_writes_blobs() {
+        let backend = RegFileBackend::parse(EXPORT).unwrap();
+        let mgr = NightlightManager::new(&backend);
+        assert_eq!(mgr.get_settings().unwrap().color_temperature, 2790);
+
+        mgr.set_schedule(ScheduleMode::SunsetToSunrise, None, None)
+            .unwrap();
+
+        let file = backend.reg_file();
+        assert_eq!(file.keys.len(), 2);
+        let reparsed = RegFileBackend::parse(&file.to_string()).unwrap();
+        let mgr = NightlightManager::new(&reparsed);
+        assert_eq!(
+            mgr.get_settings().unwrap().schedule_mode,
+            ScheduleMode::SunsetToSunrise
+        );
+        assert!(mgr.get_state().unwrap().is_enabled);
+    }
+
+    #[test]
+    fn backend_adds_missing_keys_under_current_user() {
+        let backend = RegFileBackend::default();
+        assert!(matches!(
+            backend.read_state_bytes(),
+            Err(NightlightError::MissingData(NightlightBlob::State))
+        ));
+
+        backend.write_state_bytes(&[0xAA, 0xBB]).unwrap();
+        let file = backend.reg_file();
+        assert_eq!(
+            file.keys[0].path,
+            format!(r"HKEY_CURRENT_USER\{}", NightlightBlob::State.reg_key())
+        );
+        assert_eq!(backend.read_state_bytes().unwrap(), [0xAA, 0xBB]);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("wnl-reg-{}.reg", std::process::id()));
+        let backend = RegFileBackend::parse(EXPORT).unwrap();
+        backend.save(&path).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(&bytes[..2], &[0xFF, 0xFE]);
+        let loaded = RegFileBackend::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.read_settings_bytes().unwrap(), SETTINGS_BYTES);
+    }
+}
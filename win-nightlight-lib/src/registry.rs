@@ -0,0 +1,50 @@
+use windows_registry::{CURRENT_USER, Value};
+
+use crate::{
+    DATA_REG_KEY_NAME, NightlightBackend, NightlightError, SETTINGS_REG_KEY, STATE_REG_KEY,
+};
+
+/// Windows Registry backend implementation.
+pub struct RegistryBackend;
+
+impl RegistryBackend {
+    fn read_registry_data(reg_key: &str) -> Result<Vec<u8>, NightlightError> {
+        let key = CURRENT_USER
+            .options()
+            .read()
+            .open(reg_key)
+            .map_err(NightlightError::OpenRegistryKey)?;
+        let data: Value = key
+            .get_value(DATA_REG_KEY_NAME)
+            .map_err(NightlightError::ReadRegistryValue)?;
+        Ok(data.to_vec())
+    }
+
+    fn write_registry_data(reg_key: &str, bytes: &[u8]) -> Result<(), NightlightError> {
+        let key = CURRENT_USER
+            .options()
+            .write()
+            .open(reg_key)
+            .map_err(NightlightError::OpenRegistryKey)?;
+        key.set_value(DATA_REG_KEY_NAME, &Value::from(bytes))
+            .map_err(NightlightError::WriteRegistryValue)
+    }
+}
+
+impl NightlightBackend for RegistryBackend {
+    fn read_settings_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        Self::read_registry_data(SETTINGS_REG_KEY)
+    }
+
+    fn write_settings_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        Self::write_registry_data(SETTINGS_REG_KEY, data)
+    }
+
+    fn read_state_bytes(&self) -> Result<Vec<u8>, NightlightError> {
+        Self::read_registry_data(STATE_REG_KEY)
+    }
+
+    fn write_state_bytes(&self, data: &[u8]) -> Result<(), NightlightError> {
+        Self::write_registry_data(STATE_REG_KEY, data)
+    }
+}
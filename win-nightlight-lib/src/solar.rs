@@ -0,0 +1,250 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+use thiserror::Error;
+
+/// Zenith angle of the sun's center at apparent sunrise/sunset, in degrees: 90° plus
+/// atmospheric refraction (34') and the sun's apparent radius (16').
+const SUNRISE_ZENITH: f64 = 90.833;
+/// Julian day of the Unix epoch (1970-01-01 00:00 UTC).
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+/// Julian day of the J2000.0 epoch.
+const J2000_JULIAN_DAY: f64 = 2_451_545.0;
+const MINUTES_PER_DAY: f64 = 1440.0;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SolarError {
+    #[error("Invalid latitude {0}, expected -90 to 90 degrees")]
+    InvalidLatitude(f64),
+    #[error("Invalid longitude {0}, expected -180 to 180 degrees")]
+    InvalidLongitude(f64),
+}
+
+/// Sunrise and sunset on a given date at a given location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SunTimes {
+    /// The sun rises and sets. Times are local wall-clock times in the requested UTC offset.
+    RiseSet {
+        sunrise: NaiveTime,
+        sunset: NaiveTime,
+    },
+    /// The sun stays above the horizon all day (midnight sun).
+    PolarDay,
+    /// The sun stays below the horizon all day.
+    PolarNight,
+}
+
+/// Solar declination and equation of time at a moment given as a Julian day.
+struct SolarPosition {
+    /// Declination in radians.
+    declination: f64,
+    /// Equation of time in minutes.
+    equation_of_time: f64,
+}
+
+/// Computes the sun's position using the NOAA solar calculator formulas (after Meeus,
+/// "Astronomical Algorithms").
+fn solar_position(julian_day: f64) -> SolarPosition {
+    let t = (julian_day - J2000_JULIAN_DAY) / 36525.0;
+
+    let mean_longitude = (280.46646 + t * (36000.76983 + t * 0.0003032)).rem_euclid(360.0);
+    let mean_anomaly = (357.52911 + t * (35999.05029 - 0.0001537 * t)).to_radians();
+    let eccentricity = 0.016708634 - t * (0.000042037 + 0.0000001267 * t);
+
+    let equation_of_center = mean_anomaly.sin() * (1.914602 - t * (0.004817 + 0.000014 * t))
+        + (2.0 * mean_anomaly).sin() * (0.019993 - 0.000101 * t)
+        + (3.0 * mean_anomaly).sin() * 0.000289;
+    let true_longitude = mean_longitude + equation_of_center;
+    let omega = (125.04 - 1934.136 * t).to_radians();
+    let apparent_longitude = (true_longitude - 0.00569 - 0.00478 * omega.sin()).to_radians();
+
+    let mean_obliquity =
+        23.0 + (26.0 + (21.448 - t * (46.815 + t * (0.00059 - t * 0.001813))) / 60.0) / 60.0;
+    let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+
+    let declination = (obliquity.sin() * apparent_longitude.sin()).asin();
+
+    let y = (obliquity / 2.0).tan().powi(2);
+    let l0 = mean_longitude.to_radians();
+    let equation_of_time = 4.0
+        * (y * (2.0 * l0).sin() - 2.0 * eccentricity * mean_anomaly.sin()
+            + 4.0 * eccentricity * y * mean_anomaly.sin() * (2.0 * l0).cos()
+            - 0.5 * y * y * (4.0 * l0).sin()
+            - 1.25 * eccentricity * eccentricity * (2.0 * mean_anomaly).sin())
+        .to_degrees();
+
+    SolarPosition {
+        declination,
+        equation_of_time,
+    }
+}
+
+/// Returns the cosine of the sunrise hour angle; outside [-1, 1] the sun doesn't cross the
+/// horizon.
+fn cos_hour_angle(latitude: f64, declination: f64) -> f64 {
+    let lat = latitude.to_radians();
+    SUNRISE_ZENITH.to_radians().cos() / (lat.cos() * declination.cos())
+        - lat.tan() * declination.tan()
+}
+
+/// Returns the time of sunrise (`sign` = -1) or sunset (`sign` = 1) in minutes after local
+/// midnight, or `None` if the sun doesn't cross the horizon at that moment.
+///
+/// The sun's position is first evaluated at local noon, then again at the estimated event
+/// time to account for its movement during the day.
+fn event_minutes(
+    latitude: f64,
+    longitude: f64,
+    midnight_julian_day: f64,
+    offset_minutes: f64,
+    sign: f64,
+) -> Option<f64> {
+    let mut minutes = 720.0;
+    for _ in 0..2 {
+        let position = solar_position(midnight_julian_day + minutes / MINUTES_PER_DAY);
+        let cos_ha = cos_hour_angle(latitude, position.declination);
+        if !(-1.0..=1.0).contains(&cos_ha) {
+            return None;
+        }
+        let hour_angle = cos_ha.acos().to_degrees();
+        let solar_noon = 720.0 - 4.0 * longitude - position.equation_of_time + offset_minutes;
+        minutes = solar_noon + sign * 4.0 * hour_angle;
+    }
+    Some(minutes)
+}
+
+/// Converts minutes after local midnight to a wall-clock time, rounded to the nearest minute.
+/// Events that fall on the previous or next day (extreme offsets) wrap around.
+fn minutes_to_time(minutes: f64) -> NaiveTime {
+    let rounded = (minutes.round() as i64).rem_euclid(MINUTES_PER_DAY as i64) as u32;
+    NaiveTime::from_hms_opt(rounded / 60, rounded % 60, 0).expect("minutes are within a day")
+}
+
+/// Calculates sunrise and sunset for `date` at the given coordinates (degrees, north and east
+/// positive), as wall-clock times in `utc_offset`.
+///
+/// Uses the NOAA solar calculator algorithm, which is accurate to about a minute for
+/// latitudes within ±72°; closer to the poles the times can be off by several minutes.
+pub fn sun_times(
+    latitude: f64,
+    longitude: f64,
+    date: NaiveDate,
+    utc_offset: FixedOffset,
+) -> Result<SunTimes, SolarError> {
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(SolarError::InvalidLatitude(latitude));
+    }
+    if !(-180.0..=180.0).contains(&longitude) {
+        return Err(SolarError::InvalidLongitude(longitude));
+    }
+
+    let offset_minutes = f64::from(utc_offset.local_minus_utc()) / 60.0;
+    let days_since_epoch = date
+        .signed_duration_since(DateTime::UNIX_EPOCH.date_naive())
+        .num_days() as f64;
+    // Julian day at local midnight
+    let midnight = UNIX_EPOCH_JULIAN_DAY + days_since_epoch - offset_minutes / MINUTES_PER_DAY;
+
+    let sunrise = event_minutes(latitude, longitude, midnight, offset_minutes, -1.0);
+    let sunset = event_minutes(latitude, longitude, midnight, offset_minutes, 1.0);
+    match (sunrise, sunset) {
+        (Some(sunrise), Some(sunset)) => Ok(SunTimes::RiseSet {
+            sunrise: minutes_to_time(sunrise),
+            sunset: minutes_to_time(sunset),
+        }),
+        _ => {
+            let noon = solar_position(midnight + 0.5);
+            if cos_hour_angle(latitude, noon.declination) < -1.0 {
+                Ok(SunTimes::PolarDay)
+            } else {
+                Ok(SunTimes::PolarNight)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(hours: i32) -> FixedOffset {
+        FixedOffset::east_opt(hours * 3600).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// Asserts that `actual` is within a minute of `expected` (`HH:MM`), which covers the
+    /// almanacs rounding to whole minutes.
+    fn assert_near(actual: NaiveTime, expected: &str) {
+        let expected = NaiveTime::parse_from_str(expected, "%H:%M").unwrap();
+        let diff = (actual - expected).num_minutes().abs();
+        assert!(diff <= 1, "got {actual}, expected {expected}");
+    }
+
+    fn assert_rise_set(times: SunTimes, sunrise: &str, sunset: &str) {
+        match times {
+            SunTimes::RiseSet {
+                sunrise: rise,
+                sunset: set,
+            } => {
+                assert_near(rise, sunrise);
+                assert_near(set, sunset);
+            }
+            other => panic!("expected sunrise and sunset, got {other:?}"),
+        }
+    }
+
+    // Expected values from the U.S. Naval Observatory "Sun and Moon Data for One Day" service
+
+    #[test]
+    fn new_york_summer_solstice() {
+        let times = sun_times(40.7128, -74.0060, date(2025, 6, 21), offset(-4)).unwrap();
+        assert_rise_set(times, "05:25", "20:31");
+    }
+
+    #[test]
+    fn london_winter_solstice() {
+        let times = sun_times(51.5074, -0.1278, date(2025, 12, 21), offset(0)).unwrap();
+        assert_rise_set(times, "08:04", "15:54");
+    }
+
+    #[test]
+    fn sydney_southern_winter() {
+        let times = sun_times(-33.8688, 151.2093, date(2025, 6, 21), offset(10)).unwrap();
+        assert_rise_set(times, "07:00", "16:54");
+    }
+
+    #[test]
+    fn tromso_polar_day_and_night() {
+        let (lat, lon) = (69.6492, 18.9553);
+        assert_eq!(
+            sun_times(lat, lon, date(2025, 6, 21), offset(2)).unwrap(),
+            SunTimes::PolarDay
+        );
+        assert_eq!(
+            sun_times(lat, lon, date(2025, 12, 21), offset(1)).unwrap(),
+            SunTimes::PolarNight
+        );
+    }
+
+    #[test]
+    fn southern_polar_day() {
+        // McMurdo Station in December
+        assert_eq!(
+            sun_times(-77.85, 166.67, date(2025, 12, 21), offset(13)).unwrap(),
+            SunTimes::PolarDay
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_coordinates() {
+        assert_eq!(
+            sun_times(91.0, 0.0, date(2025, 1, 1), offset(0)),
+            Err(SolarError::InvalidLatitude(91.0))
+        );
+        assert_eq!(
+            sun_times(0.0, -181.0, date(2025, 1, 1), offset(0)),
+            Err(SolarError::InvalidLongitude(-181.0))
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::nightlight_settings::{MAX_COLOR_TEMPERATURE, MIN_COLOR_TEMPERATURE};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TemperatureError {
+    #[error(
+        "Invalid color temperature '{0}', expected Kelvin (2700), a relative change (+300, -10%), or a preset ({presets})",
+        presets = Preset::NAMES.join(", ")
+    )]
+    Invalid(String),
+    #[error("Invalid strength {0}, expected 0 to 100")]
+    InvalidStrength(f64),
+}
+
+/// Named color temperatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// 1900K, candle light
+    Candle,
+    /// 2700K, incandescent bulb
+    Warm,
+    /// 4000K, neutral white
+    Neutral,
+    /// 5000K, cool white
+    Cool,
+    /// 6500K, daylight (no warming)
+    Daylight,
+}
+
+impl Preset {
+    const ALL: [Preset; 5] = [
+        Preset::Candle,
+        Preset::Warm,
+        Preset::Neutral,
+        Preset::Cool,
+        Preset::Daylight,
+    ];
+    const NAMES: [&str; 5] = ["candle", "warm", "neutral", "cool", "daylight"];
+
+    pub fn kelvin(&self) -> u16 {
+        match self {
+            Preset::Candle => 1900,
+            Preset::Warm => 2700,
+            Preset::Neutral => 4000,
+            Preset::Cool => 5000,
+            Preset::Daylight => 6500,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[*self as usize]
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Converts a Settings app "strength" slider position (0 to 100) to Kelvin.
+///
+/// The slider maps linearly from 6500K at 0% to 1200K at 100%.
+pub fn strength_to_kelvin(strength: f64) -> Result<u16, TemperatureError> {
+    if !(0.0..=100.0).contains(&strength) {
+        return Err(TemperatureError::InvalidStrength(strength));
+    }
+    let range = f64::from(MAX_COLOR_TEMPERATURE - MIN_COLOR_TEMPERATURE);
+    Ok((f64::from(MAX_COLOR_TEMPERATURE) - range * strength / 100.0).round() as u16)
+}
+
+/// Converts Kelvin to the Settings app "strength" slider position (0 to 100), clamping
+/// temperatures outside the supported range.
+pub fn kelvin_to_strength(kelvin: u16) -> f64 {
+    let kelvin = kelvin.clamp(MIN_COLOR_TEMPERATURE, MAX_COLOR_TEMPERATURE);
+    let range = f64::from(MAX_COLOR_TEMPERATURE - MIN_COLOR_TEMPERATURE);
+    f64::from(MAX_COLOR_TEMPERATURE - kelvin) * 100.0 / range
+}
+
+/// A requested color temperature, possibly relative to the current one.
+///
+/// Parses from `2700` or `2700K` (absolute), `+300`/`-300` (relative Kelvin), `-10%`/`+5%`
+/// (relative to the current Kelvin), or a [Preset] name. Strength percentages aren't parsed
+/// since `65%` would be ambiguous; construct [TemperatureTarget::Strength] directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemperatureTarget {
+    /// An exact temperature in Kelvin, passed through unclamped so out-of-range values are
+    /// still rejected by [crate::nightlight_settings::NightlightSettings::set_color_temperature].
+    Absolute(u16),
+    /// A change in Kelvin.
+    RelativeKelvin(i32),
+    /// A change in percent of the current Kelvin.
+    RelativePercent(f64),
+    /// A Settings app slider position, see [strength_to_kelvin].
+    Strength(f64),
+    Preset(Preset),
+}
+
+impl TemperatureTarget {
+    /// Returns the Kelvin value to set, given the `current` one. Relative and named targets
+    /// are clamped to 1200-6500K.
+    pub fn resolve(&self, current: u16) -> Result<u16, TemperatureError> {
+        let clamp = |k: f64| {
+            k.round().clamp(
+                f64::from(MIN_COLOR_TEMPERATURE),
+                f64::from(MAX_COLOR_TEMPERATURE),
+            ) as u16
+        };
+        Ok(match *self {
+            TemperatureTarget::Absolute(kelvin) => kelvin,
+            TemperatureTarget::RelativeKelvin(delta) => {
+                clamp(f64::from(current) + f64::from(delta))
+            }
+            TemperatureTarget::RelativePercent(pct) => {
+                clamp(f64::from(current) * (1.0 + pct / 100.0))
+            }
+            TemperatureTarget::Strength(strength) => strength_to_kelvin(strength)?,
+            TemperatureTarget::Preset(preset) => preset.kelvin(),
+        })
+    }
+}
+
+impl FromStr for TemperatureTarget {
+    type Err = TemperatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TemperatureError::Invalid(s.to_string());
+        let trimmed = s.trim();
+
+        let lower = trimmed.to_ascii_lowercase();
+        if let Some(i) = Preset::NAMES.iter().position(|n| *n == lower) {
+            return Ok(TemperatureTarget::Preset(Preset::ALL[i]));
+        }
+
+        if trimmed.starts_with(['+', '-']) {
+            if let Some(pct) = trimmed.strip_suffix('%') {
+                let pct: f64 = pct.parse().map_err(|_| invalid())?;
+                if !pct.is_finite() {
+                    return Err(invalid());
+                }
+                return Ok(TemperatureTarget::RelativePercent(pct));
+            }
+            let kelvin = trimmed.strip_suffix(['K', 'k']).unwrap_or(trimmed);
+            return kelvin
+                .parse()
+                .map(TemperatureTarget::RelativeKelvin)
+                .map_err(|_| invalid());
+        }
+
+        let kelvin = trimmed.strip_suffix(['K', 'k']).unwrap_or(trimmed);
+        kelvin
+            .parse()
+            .map(TemperatureTarget::Absolute)
+            .map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> TemperatureTarget {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_targets() {
+        assert_eq!(parse("2700"), TemperatureTarget::Absolute(2700));
+        assert_eq!(parse("2700K"), TemperatureTarget::Absolute(2700));
+        assert_eq!(parse("+300"), TemperatureTarget::RelativeKelvin(300));
+        assert_eq!(parse("-300k"), TemperatureTarget::RelativeKelvin(-300));
+        assert_eq!(parse("-10%"), TemperatureTarget::RelativePercent(-10.0));
+        assert_eq!(parse("+2.5%"), TemperatureTarget::RelativePercent(2.5));
+        assert_eq!(parse("Candle"), TemperatureTarget::Preset(Preset::Candle));
+        assert_eq!(parse("warm"), TemperatureTarget::Preset(Preset::Warm));
+        for bad in ["", "hot", "65%", "+", "-x%", "+inf%", "2700.5", "99999"] {
+            assert_eq!(
+                bad.parse::<TemperatureTarget>(),
+                Err(TemperatureError::Invalid(bad.to_string())),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_relative_and_clamp() {
+        assert_eq!(parse("+300").resolve(2700), Ok(3000));
+        assert_eq!(parse("-10%").resolve(3000), Ok(2700));
+        assert_eq!(parse("+1000").resolve(6000), Ok(6500));
+        assert_eq!(parse("-50%").resolve(2000), Ok(1200));
+        assert_eq!(parse("neutral").resolve(2000), Ok(4000));
+        // Absolute values are left for set_color_temperature to validate
+        assert_eq!(parse("800").resolve(2000), Ok(800));
+    }
+
+    #[test]
+    fn strength_mapping() {
+        assert_eq!(strength_to_kelvin(0.0), Ok(6500));
+        assert_eq!(strength_to_kelvin(100.0), Ok(1200));
+        assert_eq!(strength_to_kelvin(50.0), Ok(3850));
+        assert_eq!(strength_to_kelvin(65.0), Ok(3055));
+        assert_eq!(
+            strength_to_kelvin(101.0),
+            Err(TemperatureError::InvalidStrength(101.0))
+        );
+        assert_eq!(kelvin_to_strength(6500), 0.0);
+        assert_eq!(kelvin_to_strength(1200), 100.0);
+        assert_eq!(kelvin_to_strength(3850), 50.0);
+        assert_eq!(kelvin_to_strength(800), 100.0);
+        assert_eq!(TemperatureTarget::Strength(65.0).resolve(6500), Ok(3055));
+    }
+
+    #[test]
+    fn preset_names() {
+        for preset in Preset::ALL {
+            assert_eq!(parse(preset.name()), TemperatureTarget::Preset(preset));
+        }
+    }
+}
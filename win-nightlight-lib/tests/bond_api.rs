@@ -0,0 +1,185 @@
+// Exercises the public `bond` API the way a downstream crate would
+use win_nightlight_lib::bond::varint::{
+    decode_zigzag_i32, encode_zigzag_i16, encode_zigzag_i32, encode_zigzag_i64, read_varint,
+    write_varint,
+};
+use win_nightlight_lib::bond::{
+    BondError, BondSchema, BondStruct, BondType, BondValue, COMPACT_BINARY_MAGIC,
+    COMPACT_BINARY_V1, CompactBinaryReader, CompactBinaryWriter, FieldHeader,
+};
+
+#[derive(BondStruct, Debug, Default, PartialEq)]
+struct Sample<'a> {
+    #[bond(id = 0, ty = "bool", omit_default)]
+    enabled: bool,
+    #[bond(id = 40, ty = "int16")]
+    temperature: i16,
+    #[bond(id = 41, ty = "list<int8>")]
+    blob: Option<&'a [u8]>,
+    #[bond(unknown)]
+    unknown_fields: BondStruct,
+}
+
+#[test]
+fn varint_and_zigzag() {
+    let mut buf = Vec::new();
+    write_varint(&mut buf, 300);
+    assert_eq!(buf, [0xAC, 0x02]);
+    assert_eq!(read_varint(&buf, 0).unwrap(), (300, 2));
+    assert!(matches!(
+        read_varint(&[0x80], 0),
+        Err(BondError::UnexpectedEof(_))
+    ));
+
+    assert_eq!(encode_zigzag_i16(-1), 1);
+    assert_eq!(encode_zigzag_i32(2790), 5580);
+    assert_eq!(decode_zigzag_i32(5580), 2790);
+    assert_eq!(encode_zigzag_i64(i64::MIN), u64::MAX);
+}
+
+#[test]
+fn primitives_roundtrip() {
+    let mut w = CompactBinaryWriter::new();
+    w.write_marshaled_header();
+    w.write_field_header(40, BondType::Int16);
+    w.write_int16(-2790);
+    w.write_field_header(300, BondType::WString);
+    w.write_wstring("nuit");
+    w.write_stop();
+    let bytes = w.into_bytes();
+    assert_eq!(bytes[..2], COMPACT_BINARY_MAGIC);
+    assert_eq!(bytes[2..4], COMPACT_BINARY_V1);
+
+    let mut r = CompactBinaryReader::new(&bytes);
+    r.read_marshaled_header().unwrap();
+    assert_eq!(r.position(), 4);
+    assert_eq!(
+        r.read_field_header().unwrap(),
+        FieldHeader::Field {
+            id: 40,
+            bond_type: BondType::Int16
+        }
+    );
+    assert_eq!(r.read_int16().unwrap(), -2790);
+    assert_eq!(
+        r.read_field_header().unwrap(),
+        FieldHeader::Field {
+            id: 300,
+            bond_type: BondType::WString
+        }
+    );
+    assert_eq!(r.read_wstring().unwrap(), "nuit");
+    assert_eq!(r.read_field_header().unwrap(), FieldHeader::Stop);
+    assert_eq!(r.remaining(), 0);
+}
+
+#[test]
+fn values_roundtrip() {
+    let mut s = BondStruct::new();
+    s.push(1, BondValue::UInt64(133_000_000_000_000_000));
+    s.push(
+        2,
+        BondValue::List {
+            element_type: BondType::String,
+            elements: vec![BondValue::String("a".into())],
+        },
+    );
+    let mut w = CompactBinaryWriter::new();
+    w.write_struct(&s);
+    let bytes = w.into_bytes();
+
+    let decoded = CompactBinaryReader::new(&bytes).read_struct().unwrap();
+    assert_eq!(decoded, s);
+    assert_eq!(decoded.get(2).unwrap().bond_type(), BondType::List);
+}
+
+#[test]
+fn derived_schema_outside_the_crate() {
+    let mut extra = BondStruct::new();
+    extra.push(50, BondValue::Int32(7));
+    let sample = Sample {
+        enabled: true,
+        temperature: 2790,
+        blob: Some(&[0xDE, 0xAD]),
+        unknown_fields: extra,
+    };
+    let mut w = CompactBinaryWriter::new();
+    sample.write_fields(&mut w);
+    let bytes = w.into_bytes();
+
+    let mut r = CompactBinaryReader::new(&bytes);
+    assert_eq!(Sample::read_fields(&mut r).unwrap(), sample);
+    assert_eq!(r.remaining(), 0);
+}
+
+#[test]
+fn errors_are_reported() {
+    let mut r = CompactBinaryReader::new(&[0x43, 0x42, 0x03, 0x00]);
+    assert!(matches!(
+        r.read_marshaled_header(),
+        Err(BondError::InvalidHeader)
+    ));
+
+    // Field 0 with type ID 31
+    let mut r = CompactBinaryReader::new(&[0x1F]);
+    assert!(matches!(
+        r.read_field_header(),
+        Err(BondError::InvalidTypeId(31))
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_roundtrip() {
+    use serde::{Deserialize, Serialize};
+    use win_nightlight_lib::bond::serde::{from_slice, to_vec};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Settings {
+        #[serde(rename = "40")]
+        temperature: i16,
+    }
+
+    let bytes = to_vec(&Settings { temperature: 2790 }).unwrap();
+    assert_eq!(
+        bytes,
+        [0x43, 0x42, 0x01, 0x00, 0xCF, 0x28, 0xCC, 0x2B, 0x00]
+    );
+    assert_eq!(
+        from_slice::<Settings>(&bytes).unwrap(),
+        Settings { temperature: 2790 }
+    );
+}
+
+#[test]
+fn other_protocols() {
+    use win_nightlight_lib::bond::{
+        FastBinaryReader, FastBinaryWriter, Protocol, SimpleBinaryReader, SimpleBinaryWriter,
+        StructSchema, sniff_protocol,
+    };
+
+    let mut s = BondStruct::new();
+    s.push(0, BondValue::Int16(2790));
+    s.push(1, BondValue::WString("nuit".into()));
+
+    let mut w = FastBinaryWriter::new();
+    w.write_marshaled_header();
+    w.write_struct(&s);
+    let fast = w.into_bytes();
+    assert_eq!(sniff_protocol(&fast), Some(Protocol::FastBinary));
+    let mut r = FastBinaryReader::new(&fast);
+    r.read_marshaled_header().unwrap();
+    assert_eq!(r.read_struct().unwrap(), s);
+
+    let mut w = SimpleBinaryWriter::new();
+    w.write_marshaled_header();
+    w.write_struct(&s);
+    let simple = w.into_bytes();
+    assert!(matches!(
+        sniff_protocol(&simple),
+        Some(Protocol::SimpleBinary(_))
+    ));
+    let mut r = SimpleBinaryReader::new(&simple);
+    r.read_marshaled_header().unwrap();
+    assert_eq!(r.read_struct(&StructSchema::of(&s)).unwrap(), s);
+}